@@ -0,0 +1,87 @@
+use crate::date::Date;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The directory, next to the data file, that earlier versions of it are copied into.
+pub const BACKUP_DIR: &str = "backups";
+
+/// How many backups of each data file are kept unless `--keep-backups` says otherwise.
+pub const DEFAULT_KEEP: usize = 10;
+
+/// Returns the backup directory belonging to `data_file`.
+pub fn backup_dir(data_file: &str) -> PathBuf {
+    Path::new(data_file)
+        .parent()
+        .unwrap_or(Path::new(""))
+        .join(BACKUP_DIR)
+}
+
+/// Returns the name backups of `data_file` start with, followed by their timestamp.
+fn prefix(data_file: &str) -> String {
+    let name = Path::new(data_file)
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or(data_file);
+    format!("{}.", name)
+}
+
+/// Copies `data_file` into the backup directory before it is overwritten, then deletes
+/// all but the `keep` most recent backups of it.
+///
+/// Backups are named after the data file and the UTC time they were made, e.g.
+/// `data.json.2026-10-16-153012.250`, so they sort from oldest to newest. Nothing is
+/// copied if the data file doesn't exist yet or `keep` is 0.
+pub fn create(data_file: &str, keep: usize) -> io::Result<()> {
+    if keep == 0 || !Path::new(data_file).exists() {
+        return Ok(());
+    }
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    let seconds = now.as_secs();
+    let time = seconds % 86_400;
+    let name = format!(
+        "{}{}-{:02}{:02}{:02}.{:03}",
+        prefix(data_file),
+        Date::from_unix(seconds),
+        time / 3_600,
+        time / 60 % 60,
+        time % 60,
+        now.subsec_millis()
+    );
+
+    let dir = backup_dir(data_file);
+    fs::create_dir_all(&dir)?;
+    fs::copy(data_file, dir.join(name))?;
+
+    let backups = list(data_file)?;
+    for old in &backups[..backups.len().saturating_sub(keep)] {
+        fs::remove_file(old)?;
+    }
+    Ok(())
+}
+
+/// Returns the backups of `data_file`, from oldest to newest.
+pub fn list(data_file: &str) -> io::Result<Vec<PathBuf>> {
+    let dir = backup_dir(data_file);
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+    let prefix = prefix(data_file);
+    let mut backups = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .is_some_and(|n| n.starts_with(&prefix))
+        {
+            backups.push(path);
+        }
+    }
+    backups.sort();
+    Ok(backups)
+}
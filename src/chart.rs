@@ -0,0 +1,43 @@
+/// Block characters from one eighth of a cell wide up to a full cell.
+const BLOCKS: [char; 8] = ['▏', '▎', '▍', '▌', '▋', '▊', '▉', '█'];
+
+/// Renders `value` as a horizontal bar, where `max` fills `width` cells.
+///
+/// Bars are drawn in eighths of a cell, so small differences still show.
+pub fn bar(value: f64, max: f64, width: usize) -> String {
+    if max <= 0.0 || value <= 0.0 {
+        return String::new();
+    }
+    let eighths = ((value / max).min(1.0) * width as f64 * 8.0).round() as usize;
+    let mut bar = BLOCKS[7].to_string().repeat(eighths / 8);
+    let rest = eighths % 8;
+    if rest > 0 {
+        bar.push(BLOCKS[rest - 1]);
+    }
+    bar
+}
+
+/// Renders a labelled horizontal bar chart, one row per line.
+///
+/// # Arguments
+///
+/// * `rows` - The label, value and value text of each row, e.g. `("Mcq", 0.75, "75%")`.
+/// * `max` - The value that fills the whole width.
+/// * `width` - The width of the longest possible bar, in cells.
+pub fn bar_chart(rows: &[(String, f64, String)], max: f64, width: usize) -> String {
+    let label_width = rows
+        .iter()
+        .map(|(l, _, _)| l.chars().count())
+        .max()
+        .unwrap_or(0);
+    let mut chart = String::new();
+    for (label, value, text) in rows {
+        chart.push_str(&format!(
+            "  {:<label_width$} │{:<width$} {}\n",
+            label,
+            bar(*value, max, width),
+            text,
+        ));
+    }
+    chart
+}
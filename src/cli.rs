@@ -0,0 +1,74 @@
+use std::env;
+
+/// A thin wrapper around the raw command line arguments.
+///
+/// The program only understands a handful of `--flag` and `--key value` style options,
+/// so this keeps lookups in one place instead of pulling in a full argument parser.
+pub struct Args {
+    args: Vec<String>,
+}
+
+impl Args {
+    /// Collects the command line arguments of the current process.
+    pub fn from_env() -> Args {
+        Args {
+            args: env::args().collect(),
+        }
+    }
+
+    /// Returns the name the program was invoked with.
+    pub fn program(&self) -> &str {
+        self.args.first().map(String::as_str).unwrap_or("program")
+    }
+
+    /// Checks whether a flag such as `--input` was passed, with or without an `=value`.
+    pub fn flag(&self, name: &str) -> bool {
+        self.args
+            .iter()
+            .skip(1)
+            .any(|a| a == name || a.strip_prefix(name).is_some_and(|r| r.starts_with('=')))
+    }
+
+    /// Returns the `n`th argument after the program name, if it isn't an option, for
+    /// subcommands such as `deck new <name>`.
+    pub fn positional(&self, n: usize) -> Option<&str> {
+        self.args
+            .get(n + 1)
+            .map(String::as_str)
+            .filter(|a| !a.starts_with("--"))
+    }
+
+    /// Returns the arguments following an option up to the next option, for modes that
+    /// take several values such as `--merge-stats a.json b.json`.
+    pub fn values(&self, name: &str) -> Vec<&str> {
+        self.args
+            .iter()
+            .skip_while(|a| *a != name)
+            .skip(1)
+            .take_while(|a| !a.starts_with("--"))
+            .map(String::as_str)
+            .collect()
+    }
+
+    /// Returns the value following an option, accepting both `--key value` and `--key=value`.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The option name including the leading dashes.
+    ///
+    /// # Returns
+    ///
+    /// The option's value, or `None` if the option is absent or has no value.
+    pub fn value(&self, name: &str) -> Option<&str> {
+        let mut iter = self.args.iter().skip(1);
+        while let Some(arg) = iter.next() {
+            if arg == name {
+                return iter.next().map(String::as_str);
+            }
+            if let Some(value) = arg.strip_prefix(name).and_then(|r| r.strip_prefix('=')) {
+                return Some(value);
+            }
+        }
+        None
+    }
+}
@@ -0,0 +1,69 @@
+use crate::exercise::{Exercise, Mcq, Recall};
+use crate::rng::Rng;
+
+/// Builds multiple choice questions out of every `Recall` card in a deck.
+///
+/// Each recall answer becomes the correct option of a new `Mcq`, and the distractors are
+/// sampled at random from the answers of the other recall cards in the deck. The options
+/// are shuffled so the correct answer does not always come first.
+///
+/// # Arguments
+///
+/// * `exercises` - The deck to draw recall cards and distractors from.
+/// * `distractors` - How many wrong options to add to every question.
+/// * `rng` - The random number generator used for sampling and shuffling.
+///
+/// # Returns
+///
+/// One `Exercise::Mcq` group for every `Exercise::Recall` group in the deck.
+pub fn recall_to_mcq(exercises: &[Exercise], distractors: usize, rng: &mut Rng) -> Vec<Exercise> {
+    let recalls: Vec<&Vec<Recall>> = exercises
+        .iter()
+        .filter_map(|e| match e {
+            Exercise::Recall(items) => Some(items),
+            _ => None,
+        })
+        .collect();
+
+    // Every distinct answer in the deck is a candidate distractor.
+    let mut pool: Vec<&str> = recalls
+        .iter()
+        .flat_map(|items| items.iter().map(Recall::answer))
+        .collect();
+    pool.sort_unstable();
+    pool.dedup();
+
+    recalls
+        .into_iter()
+        .map(|items| {
+            Exercise::Mcq(
+                items
+                    .iter()
+                    .map(|recall| {
+                        let mut candidates: Vec<&str> = pool
+                            .iter()
+                            .copied()
+                            .filter(|a| !recall.accepts(a))
+                            .collect();
+                        rng.shuffle(&mut candidates);
+
+                        let mut options: Vec<String> = candidates
+                            .into_iter()
+                            .take(distractors)
+                            .map(String::from)
+                            .collect();
+                        options.push(recall.answer().to_string());
+                        rng.shuffle(&mut options);
+
+                        Mcq::new(
+                            recall.question().to_string(),
+                            recall.answer().to_string(),
+                            options,
+                            Vec::new(),
+                        )
+                    })
+                    .collect(),
+            )
+        })
+        .collect()
+}
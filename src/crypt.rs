@@ -0,0 +1,699 @@
+//! Encrypting data files with a passphrase.
+//!
+//! Files are sealed with ChaCha20-Poly1305 (RFC 8439) under a key derived from the
+//! passphrase by PBKDF2-HMAC-SHA256 with a random salt. An encrypted file is the magic
+//! bytes, the PBKDF2 rounds, the salt and the nonce, followed by the ciphertext and its
+//! tag; the header is authenticated along with the ciphertext, so a wrong passphrase or
+//! any change to the file is reported instead of yielding garbage.
+//!
+//! Passphrases are asked for once per file and run, or read from `PASSPHRASE_VAR`.
+
+use inquire::{InquireError, Password, PasswordDisplayMode};
+use std::collections::hash_map::RandomState;
+use std::collections::HashMap;
+use std::env;
+use std::fs::File;
+use std::hash::{BuildHasher, Hasher};
+use std::io::{self, Read};
+use std::sync::{Mutex, OnceLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The first bytes of every encrypted data file.
+const MAGIC: &[u8; 8] = b"WPCRYPT1";
+
+/// The PBKDF2 rounds used for new passphrases.
+const ROUNDS: u32 = 600_000;
+
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+const TAG_LEN: usize = 16;
+
+/// The length of everything before the ciphertext.
+const HEADER_LEN: usize = MAGIC.len() + 4 + SALT_LEN + NONCE_LEN;
+
+/// The environment variable holding the passphrase, for use without a terminal.
+pub const PASSPHRASE_VAR: &str = "WORD_POWER_PASSPHRASE";
+
+/// A key derived from a passphrase, with the salt and rounds it was derived with.
+#[derive(Clone)]
+struct Key {
+    rounds: u32,
+    salt: [u8; SALT_LEN],
+    key: [u8; 32],
+}
+
+/// How saves of a file are to be stored, once that is known in this run.
+#[derive(Clone)]
+enum Protection {
+    Encrypted(Key),
+    Plain,
+}
+
+/// The protection of every file unlocked, encrypted or decrypted in this run, by path.
+fn files() -> &'static Mutex<HashMap<String, Protection>> {
+    static FILES: OnceLock<Mutex<HashMap<String, Protection>>> = OnceLock::new();
+    FILES.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn protection(file_path: &str) -> Option<Protection> {
+    files().lock().unwrap().get(file_path).cloned()
+}
+
+fn set_protection(file_path: &str, protection: Protection) {
+    files()
+        .lock()
+        .unwrap()
+        .insert(file_path.to_string(), protection);
+}
+
+/// Checks whether `bytes` start like an encrypted file.
+pub fn is_encrypted(bytes: &[u8]) -> bool {
+    bytes.starts_with(MAGIC)
+}
+
+/// Checks whether the file at `file_path` is encrypted. A missing file isn't.
+pub fn is_encrypted_file(file_path: &str) -> io::Result<bool> {
+    let mut file = match File::open(file_path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(false),
+        Err(e) => return Err(e),
+    };
+    let mut start = Vec::with_capacity(MAGIC.len());
+    file.by_ref()
+        .take(MAGIC.len() as u64)
+        .read_to_end(&mut start)?;
+    Ok(is_encrypted(&start))
+}
+
+/// Checks whether saves of `file_path` should be encrypted: they are if the file is
+/// encrypted already, or if `protect` was called for it in this run.
+pub fn encrypts(file_path: &str) -> io::Result<bool> {
+    match protection(file_path) {
+        Some(Protection::Encrypted(_)) => Ok(true),
+        Some(Protection::Plain) => Ok(false),
+        None => is_encrypted_file(file_path),
+    }
+}
+
+/// Asks for a new passphrase for `file_path`, so that its saves from now on are encrypted.
+pub fn protect(file_path: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let passphrase = match env::var(PASSPHRASE_VAR) {
+        Ok(passphrase) => passphrase,
+        Err(_) => Password::new(&format!("New passphrase for {}:", file_path))
+            .with_display_mode(PasswordDisplayMode::Hidden)
+            .with_custom_confirmation_message("Repeat the passphrase:")
+            .with_custom_confirmation_error_message("The passphrases don't match.")
+            .prompt()
+            .map_err(|e| passphrase_error(file_path, e))?,
+    };
+    if passphrase.is_empty() {
+        return Err("The passphrase can't be empty".into());
+    }
+    let mut salt = [0; SALT_LEN];
+    random_bytes(&mut salt);
+    let key = derive_key(&passphrase, &salt, ROUNDS);
+    set_protection(
+        file_path,
+        Protection::Encrypted(Key {
+            rounds: ROUNDS,
+            salt,
+            key,
+        }),
+    );
+    Ok(())
+}
+
+/// Makes saves of `file_path` plain text from now on, even if the file is encrypted.
+pub fn unprotect(file_path: &str) {
+    set_protection(file_path, Protection::Plain);
+}
+
+/// Returns the key `file_path` was unlocked or protected with in this run, unlocking it
+/// first if needed, which asks for its passphrase.
+fn key(file_path: &str) -> Result<Key, Box<dyn std::error::Error>> {
+    if let Some(Protection::Encrypted(key)) = protection(file_path) {
+        return Ok(key);
+    }
+    unseal(file_path, &std::fs::read(file_path)?)?;
+    match protection(file_path) {
+        Some(Protection::Encrypted(key)) => Ok(key),
+        _ => Err(format!("{} is not encrypted", file_path).into()),
+    }
+}
+
+/// Makes saves of `other` encrypted with the same key as `file_path`, if that is
+/// encrypted, for files that are kept next to a data file such as a draft.
+pub fn share(file_path: &str, other: &str) -> Result<(), Box<dyn std::error::Error>> {
+    if encrypts(file_path)? {
+        set_protection(other, Protection::Encrypted(key(file_path)?));
+    }
+    Ok(())
+}
+
+/// Encrypts the contents of `file_path` with the key it was unlocked or protected with.
+///
+/// A file that is encrypted but wasn't read in this run is unlocked first, asking for its
+/// passphrase.
+pub fn seal(file_path: &str, plaintext: &[u8]) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    let key = key(file_path)?;
+    let mut nonce = [0; NONCE_LEN];
+    random_bytes(&mut nonce);
+    let mut out = Vec::with_capacity(HEADER_LEN + plaintext.len() + TAG_LEN);
+    out.extend_from_slice(MAGIC);
+    out.extend_from_slice(&key.rounds.to_be_bytes());
+    out.extend_from_slice(&key.salt);
+    out.extend_from_slice(&nonce);
+    let mut ciphertext = plaintext.to_vec();
+    let tag = aead_seal(&key.key, &nonce, &out, &mut ciphertext);
+    out.extend_from_slice(&ciphertext);
+    out.extend_from_slice(&tag);
+    Ok(out)
+}
+
+/// Decrypts the contents of the encrypted file `file_path`.
+///
+/// The passphrase is asked for, or taken from `PASSPHRASE_VAR`, unless the file was
+/// unlocked with the same salt earlier in this run; the key is remembered for saving the
+/// file again.
+pub fn unseal(file_path: &str, sealed: &[u8]) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    if !is_encrypted(sealed) || sealed.len() < HEADER_LEN + TAG_LEN {
+        return Err(format!("{} is not an encrypted data file", file_path).into());
+    }
+    let (header, body) = sealed.split_at(HEADER_LEN);
+    let rounds = u32::from_be_bytes(header[MAGIC.len()..MAGIC.len() + 4].try_into().unwrap());
+    let salt: [u8; SALT_LEN] = header[MAGIC.len() + 4..MAGIC.len() + 4 + SALT_LEN]
+        .try_into()
+        .unwrap();
+    let nonce: [u8; NONCE_LEN] = header[HEADER_LEN - NONCE_LEN..].try_into().unwrap();
+    let (ciphertext, tag) = body.split_at(body.len() - TAG_LEN);
+
+    let key = match protection(file_path) {
+        Some(Protection::Encrypted(key)) if key.salt == salt && key.rounds == rounds => key,
+        _ => {
+            let passphrase = match env::var(PASSPHRASE_VAR) {
+                Ok(passphrase) => passphrase,
+                Err(_) => Password::new(&format!("Passphrase for {}:", file_path))
+                    .with_display_mode(PasswordDisplayMode::Hidden)
+                    .without_confirmation()
+                    .prompt()
+                    .map_err(|e| passphrase_error(file_path, e))?,
+            };
+            Key {
+                rounds,
+                salt,
+                key: derive_key(&passphrase, &salt, rounds),
+            }
+        }
+    };
+    let mut plaintext = ciphertext.to_vec();
+    if !aead_open(&key.key, &nonce, header, &mut plaintext, tag) {
+        return Err(format!(
+            "Cannot decrypt {}: wrong passphrase, or the file is damaged",
+            file_path
+        )
+        .into());
+    }
+    if protection(file_path).is_none() {
+        set_protection(file_path, Protection::Encrypted(key));
+    }
+    Ok(plaintext)
+}
+
+fn passphrase_error(file_path: &str, e: InquireError) -> String {
+    match e {
+        InquireError::NotTTY => format!(
+            "{} is encrypted; set {} to give its passphrase without a terminal",
+            file_path, PASSPHRASE_VAR
+        ),
+        InquireError::OperationCanceled | InquireError::OperationInterrupted => {
+            format!("No passphrase given for {}", file_path)
+        }
+        e => e.to_string(),
+    }
+}
+
+/// Fills `out` with random bytes from the operating system.
+///
+/// Where `/dev/urandom` can't be read, the bytes are hashed from the time, the process
+/// id and the standard library's per-process random hash keys instead.
+fn random_bytes(out: &mut [u8]) {
+    if let Ok(mut file) = File::open("/dev/urandom") {
+        if file.read_exact(out).is_ok() {
+            return;
+        }
+    }
+    for (i, chunk) in out.chunks_mut(32).enumerate() {
+        let mut seed = RandomState::new().build_hasher();
+        seed.write_usize(i);
+        let mut hash = Sha256::new();
+        hash.update(&seed.finish().to_le_bytes());
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos())
+            .unwrap_or(0);
+        hash.update(&nanos.to_le_bytes());
+        hash.update(&std::process::id().to_le_bytes());
+        let digest = hash.finish();
+        chunk.copy_from_slice(&digest[..chunk.len()]);
+    }
+}
+
+/// Derives a 32-byte key from a passphrase with PBKDF2-HMAC-SHA256 (RFC 8018).
+fn derive_key(passphrase: &str, salt: &[u8], rounds: u32) -> [u8; 32] {
+    let mac = Hmac::new(passphrase.as_bytes());
+    // One block of output is exactly one SHA-256 digest.
+    let mut u = mac.clone().chain(salt).chain(&1u32.to_be_bytes()).finish();
+    let mut key = u;
+    for _ in 1..rounds {
+        u = mac.clone().chain(&u).finish();
+        for (k, b) in key.iter_mut().zip(u) {
+            *k ^= b;
+        }
+    }
+    key
+}
+
+/// HMAC-SHA256 (RFC 2104), keeping the hash states of the padded keys so that it can be
+/// cloned for each message cheaply.
+#[derive(Clone)]
+struct Hmac {
+    inner: Sha256,
+    outer: Sha256,
+}
+
+impl Hmac {
+    fn new(key: &[u8]) -> Hmac {
+        let mut block = [0u8; 64];
+        if key.len() > block.len() {
+            let mut hash = Sha256::new();
+            hash.update(key);
+            block[..32].copy_from_slice(&hash.finish());
+        } else {
+            block[..key.len()].copy_from_slice(key);
+        }
+        let mut inner = Sha256::new();
+        inner.update(&block.map(|b| b ^ 0x36));
+        let mut outer = Sha256::new();
+        outer.update(&block.map(|b| b ^ 0x5c));
+        Hmac { inner, outer }
+    }
+
+    fn chain(mut self, data: &[u8]) -> Hmac {
+        self.inner.update(data);
+        self
+    }
+
+    fn finish(mut self) -> [u8; 32] {
+        let inner = self.inner.finish();
+        self.outer.update(&inner);
+        self.outer.finish()
+    }
+}
+
+const SHA256_K: [u32; 64] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+    0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+    0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+    0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+    0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+    0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+/// A SHA-256 hash (FIPS 180-4) that data is fed to piece by piece.
+#[derive(Clone)]
+struct Sha256 {
+    state: [u32; 8],
+    block: [u8; 64],
+    filled: usize,
+    total: u64,
+}
+
+impl Sha256 {
+    fn new() -> Sha256 {
+        Sha256 {
+            state: [
+                0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab,
+                0x5be0cd19,
+            ],
+            block: [0; 64],
+            filled: 0,
+            total: 0,
+        }
+    }
+
+    fn update(&mut self, mut data: &[u8]) {
+        self.total += data.len() as u64;
+        while !data.is_empty() {
+            let n = (64 - self.filled).min(data.len());
+            self.block[self.filled..self.filled + n].copy_from_slice(&data[..n]);
+            self.filled += n;
+            data = &data[n..];
+            if self.filled == 64 {
+                self.compress();
+                self.filled = 0;
+            }
+        }
+    }
+
+    fn finish(mut self) -> [u8; 32] {
+        let bits = self.total * 8;
+        self.update(&[0x80]);
+        while self.filled != 56 {
+            self.update(&[0]);
+        }
+        self.update(&bits.to_be_bytes());
+        let mut digest = [0; 32];
+        for (out, word) in digest.chunks_mut(4).zip(self.state) {
+            out.copy_from_slice(&word.to_be_bytes());
+        }
+        digest
+    }
+
+    fn compress(&mut self) {
+        let mut w = [0u32; 64];
+        for (w, bytes) in w.iter_mut().zip(self.block.chunks(4)) {
+            *w = u32::from_be_bytes(bytes.try_into().unwrap());
+        }
+        for i in 16..64 {
+            let s0 = w[i - 15].rotate_right(7) ^ w[i - 15].rotate_right(18) ^ (w[i - 15] >> 3);
+            let s1 = w[i - 2].rotate_right(17) ^ w[i - 2].rotate_right(19) ^ (w[i - 2] >> 10);
+            w[i] = w[i - 16]
+                .wrapping_add(s0)
+                .wrapping_add(w[i - 7])
+                .wrapping_add(s1);
+        }
+        let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = self.state;
+        for i in 0..64 {
+            let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+            let ch = (e & f) ^ (!e & g);
+            let t1 = h
+                .wrapping_add(s1)
+                .wrapping_add(ch)
+                .wrapping_add(SHA256_K[i])
+                .wrapping_add(w[i]);
+            let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+            let maj = (a & b) ^ (a & c) ^ (b & c);
+            let t2 = s0.wrapping_add(maj);
+            h = g;
+            g = f;
+            f = e;
+            e = d.wrapping_add(t1);
+            d = c;
+            c = b;
+            b = a;
+            a = t1.wrapping_add(t2);
+        }
+        for (state, v) in self.state.iter_mut().zip([a, b, c, d, e, f, g, h]) {
+            *state = state.wrapping_add(v);
+        }
+    }
+}
+
+/// Computes one 64-byte ChaCha20 keystream block (RFC 8439, section 2.3).
+fn chacha20_block(key: &[u8; 32], counter: u32, nonce: &[u8; NONCE_LEN]) -> [u8; 64] {
+    let mut state = [0u32; 16];
+    state[..4].copy_from_slice(&[0x61707865, 0x3320646e, 0x79622d32, 0x6b206574]);
+    for (s, bytes) in state[4..12].iter_mut().zip(key.chunks(4)) {
+        *s = u32::from_le_bytes(bytes.try_into().unwrap());
+    }
+    state[12] = counter;
+    for (s, bytes) in state[13..].iter_mut().zip(nonce.chunks(4)) {
+        *s = u32::from_le_bytes(bytes.try_into().unwrap());
+    }
+
+    fn quarter_round(x: &mut [u32; 16], a: usize, b: usize, c: usize, d: usize) {
+        x[a] = x[a].wrapping_add(x[b]);
+        x[d] = (x[d] ^ x[a]).rotate_left(16);
+        x[c] = x[c].wrapping_add(x[d]);
+        x[b] = (x[b] ^ x[c]).rotate_left(12);
+        x[a] = x[a].wrapping_add(x[b]);
+        x[d] = (x[d] ^ x[a]).rotate_left(8);
+        x[c] = x[c].wrapping_add(x[d]);
+        x[b] = (x[b] ^ x[c]).rotate_left(7);
+    }
+
+    let mut x = state;
+    for _ in 0..10 {
+        quarter_round(&mut x, 0, 4, 8, 12);
+        quarter_round(&mut x, 1, 5, 9, 13);
+        quarter_round(&mut x, 2, 6, 10, 14);
+        quarter_round(&mut x, 3, 7, 11, 15);
+        quarter_round(&mut x, 0, 5, 10, 15);
+        quarter_round(&mut x, 1, 6, 11, 12);
+        quarter_round(&mut x, 2, 7, 8, 13);
+        quarter_round(&mut x, 3, 4, 9, 14);
+    }
+    let mut out = [0; 64];
+    for (out, (x, s)) in out.chunks_mut(4).zip(x.iter().zip(state)) {
+        out.copy_from_slice(&x.wrapping_add(s).to_le_bytes());
+    }
+    out
+}
+
+/// XORs `data` with the ChaCha20 keystream starting at block `counter`.
+fn chacha20_xor(key: &[u8; 32], counter: u32, nonce: &[u8; NONCE_LEN], data: &mut [u8]) {
+    for (i, chunk) in data.chunks_mut(64).enumerate() {
+        let block = chacha20_block(key, counter.wrapping_add(i as u32), nonce);
+        for (b, k) in chunk.iter_mut().zip(block) {
+            *b ^= k;
+        }
+    }
+}
+
+/// Computes the Poly1305 tag of `message` (RFC 8439, section 2.5), with 26-bit limbs.
+fn poly1305(key: &[u8; 32], message: &[u8]) -> [u8; 16] {
+    const MASK: u64 = 0x3ff_ffff;
+    let le = |bytes: &[u8]| u32::from_le_bytes(bytes[..4].try_into().unwrap()) as u64;
+
+    let r0 = le(&key[0..]) & 0x3ff_ffff;
+    let r1 = (le(&key[3..]) >> 2) & 0x3ff_ff03;
+    let r2 = (le(&key[6..]) >> 4) & 0x3ff_c0ff;
+    let r3 = (le(&key[9..]) >> 6) & 0x3f0_3fff;
+    let r4 = (le(&key[12..]) >> 8) & 0x00f_ffff;
+    let (s1, s2, s3, s4) = (r1 * 5, r2 * 5, r3 * 5, r4 * 5);
+
+    let [mut h0, mut h1, mut h2, mut h3, mut h4] = [0u64; 5];
+    for chunk in message.chunks(16) {
+        // The block with a 1 byte after it; that byte is the 129th bit of a full block.
+        let mut block = [0u8; 17];
+        block[..chunk.len()].copy_from_slice(chunk);
+        block[chunk.len()] = 1;
+        h0 += le(&block[0..]) & MASK;
+        h1 += (le(&block[3..]) >> 2) & MASK;
+        h2 += (le(&block[6..]) >> 4) & MASK;
+        h3 += (le(&block[9..]) >> 6) & MASK;
+        h4 += (le(&block[12..]) >> 8) | ((block[16] as u64) << 24);
+
+        let d0 = h0 * r0 + h1 * s4 + h2 * s3 + h3 * s2 + h4 * s1;
+        let mut d1 = h0 * r1 + h1 * r0 + h2 * s4 + h3 * s3 + h4 * s2;
+        let mut d2 = h0 * r2 + h1 * r1 + h2 * r0 + h3 * s4 + h4 * s3;
+        let mut d3 = h0 * r3 + h1 * r2 + h2 * r1 + h3 * r0 + h4 * s4;
+        let mut d4 = h0 * r4 + h1 * r3 + h2 * r2 + h3 * r1 + h4 * r0;
+        d1 += d0 >> 26;
+        h0 = d0 & MASK;
+        d2 += d1 >> 26;
+        h1 = d1 & MASK;
+        d3 += d2 >> 26;
+        h2 = d2 & MASK;
+        d4 += d3 >> 26;
+        h3 = d3 & MASK;
+        h0 += (d4 >> 26) * 5;
+        h4 = d4 & MASK;
+        h1 += h0 >> 26;
+        h0 &= MASK;
+    }
+
+    // Carry fully, then subtract 2^130 - 5 if h is at least that.
+    h2 += h1 >> 26;
+    h1 &= MASK;
+    h3 += h2 >> 26;
+    h2 &= MASK;
+    h4 += h3 >> 26;
+    h3 &= MASK;
+    h0 += (h4 >> 26) * 5;
+    h4 &= MASK;
+    h1 += h0 >> 26;
+    h0 &= MASK;
+
+    let mut g0 = h0 + 5;
+    let mut g1 = h1 + (g0 >> 26);
+    g0 &= MASK;
+    let mut g2 = h2 + (g1 >> 26);
+    g1 &= MASK;
+    let mut g3 = h3 + (g2 >> 26);
+    g2 &= MASK;
+    let g4 = (h4 + (g3 >> 26)).wrapping_sub(1 << 26);
+    g3 &= MASK;
+    // All ones if g didn't underflow, so that g is used, without branching on the secret.
+    let use_g = (g4 >> 63).wrapping_sub(1);
+    let pick = |h: u64, g: u64| (h & !use_g) | (g & use_g);
+    let (h0, h1, h2, h3, h4) = (
+        pick(h0, g0),
+        pick(h1, g1),
+        pick(h2, g2),
+        pick(h3, g3),
+        pick(h4, g4 & MASK),
+    );
+
+    // Add s and keep the low 128 bits.
+    let words = [
+        (h0 | (h1 << 26)) & 0xffff_ffff,
+        ((h1 >> 6) | (h2 << 20)) & 0xffff_ffff,
+        ((h2 >> 12) | (h3 << 14)) & 0xffff_ffff,
+        ((h3 >> 18) | (h4 << 8)) & 0xffff_ffff,
+    ];
+    let mut tag = [0; 16];
+    let mut carry = 0;
+    for (i, word) in words.iter().enumerate() {
+        let sum = word + le(&key[16 + 4 * i..]) + carry;
+        tag[4 * i..4 * i + 4].copy_from_slice(&(sum as u32).to_le_bytes());
+        carry = sum >> 32;
+    }
+    tag
+}
+
+/// Computes the Poly1305 tag of the AEAD construction over `aad` and `ciphertext`.
+fn aead_tag(key: &[u8; 32], nonce: &[u8; NONCE_LEN], aad: &[u8], ciphertext: &[u8]) -> [u8; 16] {
+    let block = chacha20_block(key, 0, nonce);
+    let one_time_key: [u8; 32] = block[..32].try_into().unwrap();
+    let pad = |len: usize| vec![0u8; (16 - len % 16) % 16];
+    let mut data = Vec::with_capacity(aad.len() + ciphertext.len() + 48);
+    data.extend_from_slice(aad);
+    data.extend(pad(aad.len()));
+    data.extend_from_slice(ciphertext);
+    data.extend(pad(ciphertext.len()));
+    data.extend_from_slice(&(aad.len() as u64).to_le_bytes());
+    data.extend_from_slice(&(ciphertext.len() as u64).to_le_bytes());
+    poly1305(&one_time_key, &data)
+}
+
+/// Encrypts `data` in place with ChaCha20-Poly1305 (RFC 8439, section 2.8).
+///
+/// # Returns
+///
+/// The tag authenticating `aad` and the ciphertext.
+fn aead_seal(key: &[u8; 32], nonce: &[u8; NONCE_LEN], aad: &[u8], data: &mut [u8]) -> [u8; 16] {
+    chacha20_xor(key, 1, nonce, data);
+    aead_tag(key, nonce, aad, data)
+}
+
+/// Decrypts `data` in place if `tag` authenticates it and `aad`.
+///
+/// # Returns
+///
+/// `false`, leaving `data` untouched, if the tag doesn't match.
+fn aead_open(
+    key: &[u8; 32],
+    nonce: &[u8; NONCE_LEN],
+    aad: &[u8],
+    data: &mut [u8],
+    tag: &[u8],
+) -> bool {
+    let expected = aead_tag(key, nonce, aad, data);
+    // Compare every byte, so the time taken doesn't reveal where a forged tag goes wrong.
+    let difference = expected
+        .iter()
+        .zip(tag)
+        .fold(0, |difference, (a, b)| difference | (a ^ b));
+    if tag.len() != TAG_LEN || difference != 0 {
+        return false;
+    }
+    chacha20_xor(key, 1, nonce, data);
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    #[test]
+    fn sha256_and_pbkdf2_match_published_vectors() {
+        let mut hash = Sha256::new();
+        hash.update(b"abc");
+        assert_eq!(
+            hex(&hash.finish()),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        let mut hash = Sha256::new();
+        for _ in 0..10 {
+            hash.update(&[b'a'; 100]);
+        }
+        assert_eq!(
+            hex(&hash.finish()),
+            "41edece42d63e8d9bf515a9ba6932e1c20cbc9f5a5d134645adb5db1b9737ea3"
+        );
+
+        assert_eq!(
+            hex(&derive_key("password", b"salt", 1)),
+            "120fb6cffcf8b32c43e7225256c4f837a86548c92ccc35480805987cb70be17b"
+        );
+        assert_eq!(
+            hex(&derive_key("password", b"salt", 4096)),
+            "c5e478d59288c841aa530db6845c4c8d962893a001ce4e11a4963873aa98134a"
+        );
+    }
+
+    #[test]
+    fn chacha20_poly1305_matches_rfc_8439() {
+        let key: [u8; 32] = std::array::from_fn(|i| 0x80 + i as u8);
+        let nonce = [
+            0x07, 0, 0, 0, 0x40, 0x41, 0x42, 0x43, 0x44, 0x45, 0x46, 0x47,
+        ];
+        let aad = [
+            0x50, 0x51, 0x52, 0x53, 0xc0, 0xc1, 0xc2, 0xc3, 0xc4, 0xc5, 0xc6, 0xc7,
+        ];
+        let plaintext = b"Ladies and Gentlemen of the class of '99: If I could offer you only \
+                          one tip for the future, sunscreen would be it.";
+
+        let mut data = plaintext.to_vec();
+        let tag = aead_seal(&key, &nonce, &aad, &mut data);
+        assert_eq!(
+            hex(&data),
+            "d31a8d34648e60db7b86afbc53ef7ec2a4aded51296e08fea9e2b5a736ee62d63dbea45e8ca967\
+             1282fafb69da92728b1a71de0a9e060b2905d6a5b67ecd3b3692ddbd7f2d778b8c9803aee328091b\
+             58fab324e4fad675945585808b4831d7bc3ff4def08e4b7a9de576d26586cec64b6116"
+        );
+        assert_eq!(hex(&tag), "1ae10b594f09e26a7e902ecbd0600691");
+
+        assert!(aead_open(&key, &nonce, &aad, &mut data, &tag));
+        assert_eq!(data, plaintext);
+    }
+
+    #[test]
+    fn sealed_files_need_the_right_passphrase_and_an_untouched_file() {
+        let key = Key {
+            rounds: 2,
+            salt: [7; SALT_LEN],
+            key: derive_key("correct horse", &[7; SALT_LEN], 2),
+        };
+        set_protection("sealed-test.json", Protection::Encrypted(key.clone()));
+        let sealed = seal("sealed-test.json", b"[1, 2, 3]").unwrap();
+        assert!(is_encrypted(&sealed));
+        assert_eq!(unseal("sealed-test.json", &sealed).unwrap(), b"[1, 2, 3]");
+
+        // The same salt with a key derived from another passphrase.
+        set_protection(
+            "sealed-test.json",
+            Protection::Encrypted(Key {
+                rounds: 2,
+                salt: [7; SALT_LEN],
+                key: derive_key("battery staple", &[7; SALT_LEN], 2),
+            }),
+        );
+        let result = unseal("sealed-test.json", &sealed);
+        assert!(result.unwrap_err().to_string().contains("wrong passphrase"));
+
+        set_protection("sealed-test.json", Protection::Encrypted(key));
+        for i in [HEADER_LEN - 1, HEADER_LEN, sealed.len() - 1] {
+            let mut tampered = sealed.clone();
+            tampered[i] ^= 1;
+            assert!(unseal("sealed-test.json", &tampered).is_err());
+        }
+    }
+}
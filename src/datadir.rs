@@ -0,0 +1,145 @@
+use crate::backup::BACKUP_DIR;
+use crate::deck::DECK_DIR;
+use crate::draft;
+use crate::goal::GOAL_FILE;
+use crate::media::MEDIA_DIR;
+use crate::session_log::SESSION_LOG;
+use crate::storage;
+use crate::streak::STREAK_FILE;
+use crate::template::TEMPLATE_DIR;
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// The directory, inside the platform's data directory, that this program's files go in.
+const APP_DIR: &str = "word_power";
+
+/// The file in the data directory recording that the user chose to keep a local data file
+/// where it is, so they are only asked once.
+const KEEP_LOCAL_MARKER: &str = ".keep-local";
+
+/// Returns the directory the data file is kept in by default.
+///
+/// This is `%APPDATA%\word_power` on Windows, `~/Library/Application Support/word_power`
+/// on macOS, and `$XDG_DATA_HOME/word_power` elsewhere, where `XDG_DATA_HOME` defaults to
+/// `~/.local/share`.
+///
+/// # Returns
+///
+/// `None` if the environment doesn't say where the home directory is.
+pub fn data_dir() -> Option<PathBuf> {
+    let var = |name: &str| {
+        env::var_os(name)
+            .map(PathBuf::from)
+            .filter(|p| p.is_absolute())
+    };
+    let base = if cfg!(windows) {
+        var("APPDATA")?
+    } else if cfg!(target_os = "macos") {
+        var("HOME")?.join("Library").join("Application Support")
+    } else {
+        var("XDG_DATA_HOME").or_else(|| Some(var("HOME")?.join(".local").join("share")))?
+    };
+    Some(base.join(APP_DIR))
+}
+
+/// Returns whether the user already declined moving a local data file into `dir`.
+pub fn kept_local(dir: &Path) -> bool {
+    dir.join(KEEP_LOCAL_MARKER).exists()
+}
+
+/// Records that the user wants to keep their local data file, see `kept_local`.
+pub fn keep_local(dir: &Path) -> io::Result<()> {
+    fs::create_dir_all(dir)?;
+    fs::write(dir.join(KEEP_LOCAL_MARKER), "")
+}
+
+/// Moves a data file into `dir`, together with the files kept next to it, such as its
+/// append log, draft, media, backups and session log, and the named decks and templates,
+/// unless `dir` already has its own.
+pub fn move_into(data_file: &Path, dir: &Path) -> io::Result<()> {
+    let name = data_file
+        .file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "not a file name"))?;
+    fs::create_dir_all(dir)?;
+    move_path(data_file, &dir.join(name))?;
+
+    // The log and the draft are named after the data file, so they keep their names.
+    let file = data_file.to_string_lossy();
+    for path in [storage::log_path(&file), draft::path(&file)] {
+        let path = Path::new(&path);
+        if let Some(name) = path.file_name().filter(|_| path.exists()) {
+            if !dir.join(name).exists() {
+                move_path(path, &dir.join(name))?;
+            }
+        }
+    }
+
+    let from = data_file.parent().unwrap_or(Path::new(""));
+    for sub in [
+        MEDIA_DIR,
+        BACKUP_DIR,
+        STREAK_FILE,
+        GOAL_FILE,
+        SESSION_LOG,
+        DECK_DIR,
+        TEMPLATE_DIR,
+    ] {
+        if from.join(sub).exists() && !dir.join(sub).exists() {
+            move_path(&from.join(sub), &dir.join(sub))?;
+        }
+    }
+    Ok(())
+}
+
+/// Moves a file or directory, copying it when it can't be renamed, e.g. because the data
+/// directory is on another file system.
+fn move_path(from: &Path, to: &Path) -> io::Result<()> {
+    if fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+    if from.is_dir() {
+        fs::create_dir_all(to)?;
+        for entry in fs::read_dir(from)? {
+            let entry = entry?;
+            move_path(&entry.path(), &to.join(entry.file_name()))?;
+        }
+        fs::remove_dir(from)
+    } else {
+        fs::copy(from, to)?;
+        fs::remove_file(from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn move_into_takes_the_files_named_after_the_data_file_along() {
+        let scratch = env::temp_dir().join(format!("word_power-datadir-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&scratch);
+        let (from, to) = (scratch.join("from"), scratch.join("to"));
+        fs::create_dir_all(from.join(MEDIA_DIR)).unwrap();
+        for name in [
+            "data.json",
+            "data.log.jsonl",
+            "data.json.draft",
+            "other.json",
+        ] {
+            fs::write(from.join(name), name).unwrap();
+        }
+
+        move_into(&from.join("data.json"), &to).unwrap();
+        for name in ["data.json", "data.log.jsonl", "data.json.draft", MEDIA_DIR] {
+            assert!(
+                to.join(name).exists() && !from.join(name).exists(),
+                "{}",
+                name
+            );
+        }
+        assert!(from.join("other.json").exists());
+        fs::remove_dir_all(&scratch).unwrap();
+    }
+}
@@ -0,0 +1,107 @@
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// A calendar day, stored as the number of days since 1970-01-01.
+///
+/// Dates are written to the data file as `YYYY-MM-DD` so that it stays readable.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct Date {
+    days: i64,
+}
+
+const SECONDS_PER_DAY: u64 = 86_400;
+
+impl Date {
+    /// Returns the current day in UTC.
+    pub fn today() -> Date {
+        let seconds = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        Date::from_unix(seconds)
+    }
+
+    /// Returns the UTC day of a time given in seconds since the Unix epoch.
+    pub fn from_unix(seconds: u64) -> Date {
+        Date {
+            days: (seconds / SECONDS_PER_DAY) as i64,
+        }
+    }
+
+    /// Returns the date `days` days later (or earlier, if negative).
+    pub fn add_days(self, days: i64) -> Date {
+        Date {
+            days: self.days + days,
+        }
+    }
+
+    /// Converts to year, month and day, using Howard Hinnant's `civil_from_days` algorithm.
+    fn to_civil(self) -> (i64, u32, u32) {
+        let z = self.days + 719_468;
+        let era = z.div_euclid(146_097);
+        let doe = z.rem_euclid(146_097);
+        let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+        let year = yoe + era * 400 + i64::from(month <= 2);
+        (year, month, day)
+    }
+
+    /// Converts from year, month and day, the inverse of `to_civil`.
+    fn from_civil(year: i64, month: u32, day: u32) -> Date {
+        let year = year - i64::from(month <= 2);
+        let era = year.div_euclid(400);
+        let yoe = year.rem_euclid(400);
+        let mp = i64::from((month + 9) % 12);
+        let doy = (153 * mp + 2) / 5 + i64::from(day) - 1;
+        let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+        Date {
+            days: era * 146_097 + doe - 719_468,
+        }
+    }
+}
+
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (year, month, day) = self.to_civil();
+        write!(f, "{:04}-{:02}-{:02}", year, month, day)
+    }
+}
+
+impl FromStr for Date {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("Expected a date like 2024-01-31, found '{}'", text);
+        let mut parts = text.trim().splitn(3, '-');
+        let mut next = || parts.next().ok_or_else(invalid);
+
+        let year: i64 = next()?.parse().map_err(|_| invalid())?;
+        let month: u32 = next()?.parse().map_err(|_| invalid())?;
+        let day: u32 = next()?.parse().map_err(|_| invalid())?;
+
+        let date = Date::from_civil(year, month, day);
+        // Out of range months and days would silently roll over into other dates.
+        if date.to_civil() != (year, month, day) {
+            return Err(invalid());
+        }
+        Ok(date)
+    }
+}
+
+impl Serialize for Date {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Date {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        text.parse().map_err(serde::de::Error::custom)
+    }
+}
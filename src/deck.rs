@@ -0,0 +1,105 @@
+use crate::datadir;
+use crate::storage;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The directory, inside the data directory, that named decks are kept in, one
+/// `<name>.json` data file each.
+pub const DECK_DIR: &str = "decks";
+
+/// Returns the deck directory, or `DECK_DIR` in the working directory if there is no data
+/// directory.
+pub fn deck_dir() -> PathBuf {
+    datadir::data_dir().map_or(PathBuf::from(DECK_DIR), |d| d.join(DECK_DIR))
+}
+
+/// The extension of a deck's data file.
+const EXTENSION: &str = "json";
+
+/// Returns the data file of the deck called `name`, whether or not it exists yet.
+///
+/// Deck names may only contain letters, digits, `-` and `_`, so that they map to a file
+/// inside `DECK_DIR` on every platform.
+pub fn path(name: &str) -> Result<String, String> {
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err(format!(
+            "Invalid deck name '{}': use letters, digits, - and _ only",
+            name
+        ));
+    }
+    Ok(deck_dir()
+        .join(format!("{}.{}", name, EXTENSION))
+        .to_string_lossy()
+        .into_owned())
+}
+
+/// Returns the data file of an existing deck.
+pub fn existing(name: &str) -> Result<String, String> {
+    let path = path(name)?;
+    if !Path::new(&path).exists() {
+        return Err(format!(
+            "There is no deck named '{}'; create it with `deck new {}`",
+            name, name
+        ));
+    }
+    Ok(path)
+}
+
+/// Returns the names of all decks, sorted.
+pub fn names() -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let dir = deck_dir();
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+    let mut names = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().is_some_and(|e| e == EXTENSION) {
+            if let Some(name) = path.file_stem().and_then(|s| s.to_str()) {
+                names.push(name.to_string());
+            }
+        }
+    }
+    names.sort();
+    Ok(names)
+}
+
+/// Creates an empty deck called `name`.
+pub fn create(name: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let path = path(name)?;
+    if Path::new(&path).exists() {
+        return Err(format!("A deck named '{}' already exists", name).into());
+    }
+    fs::create_dir_all(deck_dir())?;
+    storage::open(&path, None, 0)?.save(&[])
+}
+
+/// Deletes the deck called `name` and everything in it.
+pub fn delete(name: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let path = existing(name)?;
+    fs::remove_file(&path)?;
+    let log = storage::log_path(&path);
+    if Path::new(&log).exists() {
+        fs::remove_file(log)?;
+    }
+    Ok(())
+}
+
+/// Renames the deck called `from` to `to`, which must not be taken.
+pub fn rename(from: &str, to: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let source = existing(from)?;
+    let target = path(to)?;
+    if Path::new(&target).exists() {
+        return Err(format!("A deck named '{}' already exists", to).into());
+    }
+    fs::rename(&source, &target)?;
+    let log = storage::log_path(&source);
+    if Path::new(&log).exists() {
+        fs::rename(log, storage::log_path(&target))?;
+    }
+    Ok(())
+}
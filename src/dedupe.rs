@@ -0,0 +1,86 @@
+use crate::exercise::Exercise;
+use crate::similar::normalize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+/// Questions of one exercise type whose texts are the same once case, punctuation and
+/// spacing are ignored.
+pub struct Duplicates {
+    /// The position of every copy, as its group and its index in the group, in deck order.
+    pub positions: Vec<(usize, usize)>,
+    /// The index into `positions` of the copy to keep: the one with the most history, the
+    /// first of those if several tie.
+    pub keep: usize,
+    /// Whether the copies differ in more than their text, e.g. in their answers, so that
+    /// the user should choose which one to keep.
+    pub ambiguous: bool,
+}
+
+/// Finds every set of duplicate questions in a deck, in the order of their first copy.
+pub fn find(exercises: &[Exercise]) -> Vec<Duplicates> {
+    let mut sets: BTreeMap<(usize, usize), Vec<(usize, usize)>> = BTreeMap::new();
+    let mut first: HashMap<(&'static str, String), (usize, usize)> = HashMap::new();
+    for (g, exercise) in exercises.iter().enumerate() {
+        let kind: &'static str = exercise.into();
+        for (q, question) in exercise.questions().into_iter().enumerate() {
+            let key = (kind, normalize(&question.text()));
+            let position = *first.entry(key).or_insert((g, q));
+            sets.entry(position).or_default().push((g, q));
+        }
+    }
+
+    sets.into_values()
+        .filter(|positions| positions.len() > 1)
+        .map(|positions| {
+            let questions: Vec<_> = positions
+                .iter()
+                .map(|&(g, q)| exercises[g].questions()[q].to_string())
+                .collect();
+            let history = |i: usize| {
+                let (g, q) = positions[i];
+                exercises[g].questions()[q].meta().history.len()
+            };
+            // `max_by_key` picks the last of equal maxima, so compare against the index too.
+            let keep = (0..positions.len())
+                .max_by_key(|&i| (history(i), std::cmp::Reverse(i)))
+                .unwrap_or(0);
+            Duplicates {
+                ambiguous: questions
+                    .iter()
+                    .any(|q| normalize(q) != normalize(&questions[0])),
+                positions,
+                keep,
+            }
+        })
+        .collect()
+}
+
+/// Removes questions from a deck, dropping groups left empty.
+///
+/// Links to a removed question are pointed at the question kept in its place instead, as
+/// given by `replaced_by`, which maps removed IDs to kept ones.
+pub fn remove(
+    exercises: &mut Vec<Exercise>,
+    removed: &HashSet<(usize, usize)>,
+    replaced_by: &HashMap<String, String>,
+) {
+    for (g, exercise) in exercises.iter_mut().enumerate() {
+        let mut q = 0;
+        exercise.retain_questions(|_| {
+            q += 1;
+            !removed.contains(&(g, q - 1))
+        });
+        for question in exercise.questions_mut() {
+            let meta = question.meta_mut();
+            for link in &mut meta.links {
+                if let Some(kept) = replaced_by.get(link) {
+                    link.clone_from(kept);
+                }
+            }
+            let mut seen = HashSet::new();
+            let id = meta.id.clone();
+            meta.links
+                .retain(|link| *link != id && seen.insert(link.clone()));
+        }
+    }
+    exercises.retain(|e| e.question_count() > 0);
+}
@@ -0,0 +1,39 @@
+use crate::exercise::Exercise;
+use crate::storage;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Returns the file that exercises typed in with `--input` are kept in until they are
+/// added to `data_file`.
+pub fn path(data_file: &str) -> String {
+    format!("{}.draft", data_file)
+}
+
+/// Replaces the draft with the exercises entered so far.
+///
+/// The draft is written like a data file, atomically and without backups, so a crash
+/// while writing it leaves the previous draft intact.
+pub fn save(draft: &str, exercises: &[Exercise]) -> Result<(), Box<dyn std::error::Error>> {
+    storage::open(draft, Some("json"), 0)?.save(exercises)
+}
+
+/// Loads the exercises left in a draft by a session that didn't finish.
+///
+/// # Returns
+///
+/// `None` if there is no draft, e.g. because the last session saved or quit normally.
+pub fn load(draft: &str) -> Result<Option<Vec<Exercise>>, Box<dyn std::error::Error>> {
+    if !Path::new(draft).exists() {
+        return Ok(None);
+    }
+    storage::open(draft, Some("json"), 0)?.load().map(Some)
+}
+
+/// Deletes the draft once its exercises were saved or deliberately thrown away.
+pub fn discard(draft: &str) -> io::Result<()> {
+    match fs::remove_file(draft) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
@@ -0,0 +1,121 @@
+use crate::exercise::{Meta, Question};
+use crate::history::unix_now;
+use inquire::error::InquireResult;
+use inquire::{Select, Text};
+
+/// The changes offered for a question by `--edit`.
+#[derive(strum_macros::Display)]
+enum EditChoice {
+    #[strum(serialize = "Edit explanation")]
+    Explanation,
+    #[strum(serialize = "Edit pronunciation")]
+    Pronunciation,
+    #[strum(serialize = "Edit mnemonic")]
+    Mnemonic,
+    #[strum(serialize = "Edit links")]
+    Links,
+    #[strum(serialize = "Suspend from practice")]
+    Suspend,
+    #[strum(serialize = "Unsuspend")]
+    Unsuspend,
+    Unstar,
+    Done,
+}
+
+impl EditChoice {
+    /// Returns the edit choices for a question, offering to suspend it or to unsuspend it
+    /// depending on `suspended`, and to unstar it if `starred`.
+    fn all(suspended: bool, starred: bool) -> Vec<EditChoice> {
+        let mut choices = vec![
+            Self::Explanation,
+            Self::Pronunciation,
+            Self::Mnemonic,
+            Self::Links,
+            if suspended {
+                Self::Unsuspend
+            } else {
+                Self::Suspend
+            },
+        ];
+        if starred {
+            choices.push(Self::Unstar);
+        }
+        choices.push(Self::Done);
+        choices
+    }
+}
+
+/// Lets the user change a question's notes and links, or suspend it, until they choose
+/// "Done".
+///
+/// # Returns
+///
+/// Whether anything was changed; the question's modification time is updated if so.
+pub fn edit(question: &mut dyn Question) -> InquireResult<bool> {
+    println!("{}", question);
+    let mut changed = false;
+
+    loop {
+        let choices = EditChoice::all(question.meta().suspended, question.meta().starred);
+        let (label, field): (&str, fn(&mut Meta) -> &mut Option<String>) =
+            match Select::new("Change what?", choices).prompt()? {
+                EditChoice::Explanation => ("Explanation", |meta| &mut meta.explanation),
+                EditChoice::Pronunciation => ("Pronunciation", |meta| &mut meta.pronunciation),
+                EditChoice::Mnemonic => ("Mnemonic", |meta| &mut meta.mnemonic),
+                EditChoice::Links => {
+                    changed |= edit_links(question.meta_mut())?;
+                    continue;
+                }
+                EditChoice::Suspend | EditChoice::Unsuspend => {
+                    let meta = question.meta_mut();
+                    meta.suspended = !meta.suspended;
+                    changed = true;
+                    continue;
+                }
+                EditChoice::Unstar => {
+                    question.meta_mut().starred = false;
+                    changed = true;
+                    continue;
+                }
+                EditChoice::Done => break,
+            };
+
+        let note = field(question.meta_mut());
+        let text = Text::new(label)
+            .with_initial_value(note.as_deref().unwrap_or_default())
+            .with_help_message("leave blank to remove")
+            .prompt()?;
+        let text = text.trim();
+        let text = (!text.is_empty()).then(|| text.to_string());
+        if *note != text {
+            *note = text;
+            changed = true;
+        }
+    }
+
+    if changed {
+        question.meta_mut().modified = Some(unix_now());
+    }
+    Ok(changed)
+}
+
+/// Asks for the IDs of the questions a question links to, separated by commas.
+///
+/// # Returns
+///
+/// Whether the links changed.
+fn edit_links(meta: &mut Meta) -> InquireResult<bool> {
+    let links: Vec<String> = Text::new("Linked question IDs")
+        .with_initial_value(&meta.links.join(", "))
+        .with_help_message("separate IDs with commas; leave blank to remove all links")
+        .prompt()?
+        .split(',')
+        .map(str::trim)
+        .filter(|id| !id.is_empty() && *id != meta.id)
+        .map(String::from)
+        .collect();
+
+    let changed = links != meta.links;
+    meta.links = links;
+    Ok(changed)
+}
@@ -1,15 +1,22 @@
 // Import the necessary types from the exercise module.
+use crate::draft;
 use crate::exercise::{
-    Exercise, FillInTheBlank, Matching, Mcq, Recall, RecognizeRoot, SameOrOpposite, YesNo,
+    option_letter, Analogy, Cloze, Custom, Difficulty, Etymology, Exercise, FillInTheBlank,
+    Matching, MatchingSet, Mcq, McqMulti, Meta, OddOneOut, Ordering, Question, Recall,
+    RecognizeRoot, SameOrOpposite, Spelling, UseInSentence, YesNo,
 };
-// Derive macros for automatic trait implementations.
-use strum_macros;
-
+use crate::media;
+use crate::pattern::Pattern;
+use crate::template::{self, Template};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
 // Import the inquire crate for interactive CLI prompts.
-use inquire;
-use inquire::formatter::{BoolFormatter, OptionFormatter};
-use inquire::parser::{BoolParser};
-use inquire::{Confirm, Select, Text};
+use inquire::error::InquireResult;
+use inquire::formatter::{BoolFormatter, MultiOptionFormatter, OptionFormatter};
+use inquire::parser::BoolParser;
+use inquire::type_aliases::Scorer;
+use inquire::validator::{MinLengthValidator, Validation};
+use inquire::{Confirm, InquireError, MultiSelect, Select, Text};
 
 /// A common trait for types that can be interactively read from user input.
 ///
@@ -18,10 +25,88 @@ use inquire::{Confirm, Select, Text};
 pub trait Entry: Sized {
     /// Reads a list of entries from the user.
     ///
+    /// # Arguments
+    ///
+    /// * `config` - Settings that control how the prompts are shown.
+    ///
     /// # Returns
     ///
-    /// A vector containing all instances that were read from user input.
-    fn read() -> Vec<Self>;
+    /// A vector containing all instances that were read from user input, or the prompt
+    /// error (e.g. `OperationInterrupted` on Ctrl-C) that stopped the entry.
+    fn read(config: &EntryConfig) -> InquireResult<Vec<Self>>;
+}
+
+/// Settings shared by every `Entry` implementation.
+pub struct EntryConfig {
+    /// Whether prompts are prefixed with the question's position in the batch ("1. ").
+    pub numbered: bool,
+    /// The largest number of questions or options that can be entered in one go.
+    pub max_count: usize,
+    /// The chapter every batch belongs to; asked for each batch when `None`.
+    pub chapter: Option<String>,
+    /// Where attached media files are copied to.
+    pub media_dir: PathBuf,
+    /// The file every finished batch is saved to until the session ends, see `draft`, so a
+    /// crash loses at most the batch being typed; nothing is saved when `None`.
+    pub draft: Option<String>,
+}
+
+impl EntryConfig {
+    /// Returns the prefix shown before the `i`th question of a batch, e.g. "1. ".
+    fn prefix(&self, i: usize) -> String {
+        if self.numbered {
+            format!("{}. ", i + 1)
+        } else {
+            String::new()
+        }
+    }
+
+    /// Returns the prompt used when asking for the text of the `i`th question.
+    fn question_prompt(&self, i: usize) -> String {
+        if self.numbered {
+            self.prefix(i)
+        } else {
+            String::from("Question:")
+        }
+    }
+}
+
+/// The fewest questions a batch can be started with.
+const MIN_QUESTIONS: usize = 1;
+
+/// The fewest options a multiple choice question can have.
+const MIN_OPTIONS: usize = 2;
+
+/// The most options or items a question can have, as they are lettered `a` to `z`.
+const MAX_OPTIONS: usize = 26;
+
+/// Prompts the user for a count such as the number of questions.
+///
+/// Numbers outside `min..=max` are rejected and the user is asked again, so a mistyped
+/// count can't start an endless entry loop. Cancelling the prompt (Esc) is treated as a
+/// count of zero.
+fn read_count(message: &str, min: usize, max: usize) -> InquireResult<usize> {
+    let max = max.max(min);
+    match inquire::CustomType::<usize>::new(message)
+        .with_error_message("Please enter a valid number")
+        .with_help_message(&format!("{} to {}", min, max))
+        .with_validator(move |n: &usize| {
+            Ok(if (min..=max).contains(n) {
+                Validation::Valid
+            } else {
+                Validation::Invalid(format!("Please enter a number from {} to {}", min, max).into())
+            })
+        })
+        .prompt()
+    {
+        Err(InquireError::OperationCanceled) => Ok(0),
+        result => result,
+    }
+}
+
+/// Prompts for the number of options or items in a question, at most `MAX_OPTIONS`.
+fn read_option_count(message: &str, min: usize, config: &EntryConfig) -> InquireResult<usize> {
+    read_count(message, min, config.max_count.min(MAX_OPTIONS))
 }
 
 /// Prompts the user for the number of questions and then reads that many questions.
@@ -29,15 +114,12 @@ pub trait Entry: Sized {
 /// # Returns
 ///
 /// A vector of question strings entered by the user.
-fn read_questions() -> Vec<String> {
-    let n = inquire::CustomType::<usize>::new("How many questions?")
-        .with_error_message("Please enter a valid number")
-        .prompt()
-        .unwrap_or(0);
+fn read_questions(config: &EntryConfig) -> InquireResult<Vec<String>> {
+    let n = read_count("How many questions?", MIN_QUESTIONS, config.max_count)?;
 
     // Collect each question with an index (starting at 1) as a prompt.
     (0..n)
-        .map(|i| Text::new(&format!("{}. ", i + 1)).prompt().unwrap())
+        .map(|i| Text::new(&config.question_prompt(i)).prompt())
         .collect()
 }
 
@@ -52,19 +134,253 @@ fn read_questions() -> Vec<String> {
 /// # Returns
 ///
 /// A vector containing the option strings provided by the user.
-fn read_options(n: usize) -> Vec<String> {
-    ('a'..)
-        .take(n)
-        .map(|c| Text::new(&format!("({})", c)).prompt().unwrap())
+fn read_options(n: usize) -> InquireResult<Vec<String>> {
+    (0..n)
+        .map(|i| Text::new(&format!("({})", option_letter(i))).prompt())
         .collect()
 }
 
+/// Warns about questions whose answer appears verbatim in the prompt.
+///
+/// Each such question is shown and the user can either keep it anyway or drop it from
+/// the batch.
+fn confirm_leaked_answers(exercise: &mut Exercise) -> InquireResult<()> {
+    let keep = exercise
+        .questions()
+        .into_iter()
+        .map(|question| {
+            if !question.gives_away_answer() {
+                return Ok(true);
+            }
+            println!("Warning: the answer appears in the question: {}", question);
+            Confirm::new("Keep this question anyway?")
+                .with_default(true)
+                .prompt()
+        })
+        .collect::<InquireResult<Vec<_>>>()?;
+
+    let mut keep = keep.into_iter();
+    exercise.retain_questions(|_| keep.next().unwrap_or(true));
+    Ok(())
+}
+
+/// Prompts for where the words of a batch were encountered.
+///
+/// # Returns
+///
+/// The entered source, or `None` if the answer was left blank or the prompt was skipped.
+fn read_source() -> InquireResult<Option<String>> {
+    let source = Text::new("Source")
+        .with_help_message("book, page or article; leave blank for none")
+        .prompt_skippable()?
+        .unwrap_or_default();
+    let source = source.trim();
+
+    Ok((!source.is_empty()).then(|| source.to_string()))
+}
+
+/// Prompts for the chapter or session a batch belongs to.
+///
+/// # Returns
+///
+/// The entered chapter, or `None` if the answer was left blank or the prompt was skipped.
+fn read_chapter() -> InquireResult<Option<String>> {
+    let chapter = Text::new("Chapter")
+        .with_help_message("e.g. 3 or session 12; leave blank for none")
+        .prompt_skippable()?
+        .unwrap_or_default();
+    let chapter = chapter.trim();
+
+    Ok((!chapter.is_empty()).then(|| chapter.to_string()))
+}
+
+/// Offers to attach a note, such as an explanation, to every question of a batch.
+///
+/// Questions whose prompt is skipped or left blank get none. A note entered with the
+/// question itself is offered for editing.
+///
+/// # Arguments
+///
+/// * `exercise` - The batch the notes are added to.
+/// * `label` - What the note is called, e.g. "Explanation".
+/// * `help` - What the note is for, shown when asking whether to add any.
+/// * `field` - The note's place in a question's metadata.
+fn read_notes(
+    exercise: &mut Exercise,
+    label: &str,
+    help: &str,
+    field: fn(&mut Meta) -> &mut Option<String>,
+) -> InquireResult<()> {
+    if !Confirm::new(&format!("Add {}s?", label.to_lowercase()))
+        .with_default(false)
+        .with_help_message(help)
+        .prompt()?
+    {
+        return Ok(());
+    }
+
+    for question in exercise.questions_mut() {
+        let message = format!("{} for {}", label, question.text());
+        let note = field(question.meta_mut());
+        let current = note.clone().unwrap_or_default();
+        let text = Text::new(&message)
+            .with_initial_value(&current)
+            .prompt_skippable()?
+            .unwrap_or_default();
+        let text = text.trim();
+        *note = (!text.is_empty()).then(|| text.to_string());
+    }
+    Ok(())
+}
+
+/// Offers to attach an image or other media file to every question of a batch.
+///
+/// Each file is copied into the media directory and is shown with the question in quizzes.
+/// Questions whose prompt is skipped or left blank get none; a path that can't be copied
+/// is asked for again.
+fn read_media(exercise: &mut Exercise, dir: &Path) -> InquireResult<()> {
+    if !Confirm::new("Attach images?")
+        .with_default(false)
+        .with_help_message("files are copied into the media directory next to the deck")
+        .prompt()?
+    {
+        return Ok(());
+    }
+
+    for question in exercise.questions_mut() {
+        let message = format!("Image for {}", question.text());
+        question.meta_mut().media = loop {
+            let path = Text::new(&message)
+                .with_help_message("path to the file; leave blank for none")
+                .prompt_skippable()?
+                .unwrap_or_default();
+            let path = path.trim();
+            if path.is_empty() {
+                break None;
+            }
+            match media::import(dir, Path::new(path)) {
+                Ok(name) => break Some(name),
+                Err(e) => println!("Could not copy {}: {}", path, e),
+            }
+        };
+    }
+    Ok(())
+}
+
+/// Prompts for how hard the questions of a batch are.
+///
+/// # Returns
+///
+/// The chosen difficulty, or `None` if the prompt was skipped.
+fn read_difficulty() -> InquireResult<Option<Difficulty>> {
+    Select::new("Difficulty", Difficulty::all())
+        .with_help_message("esc to leave unrated")
+        .prompt_skippable()
+}
+
+/// Prompts for the tags of a batch, separated by commas.
+///
+/// # Returns
+///
+/// The entered tags without duplicates, or none if the answer was left blank or the
+/// prompt was skipped.
+fn read_tags() -> InquireResult<Vec<String>> {
+    let tags = Text::new("Tags")
+        .with_help_message("separated by commas, e.g. roots, emotions; leave blank for none")
+        .prompt_skippable()?
+        .unwrap_or_default();
+
+    let mut unique: Vec<String> = Vec::new();
+    for tag in tags.split(',').map(str::trim).filter(|t| !t.is_empty()) {
+        if !unique.iter().any(|u| u.eq_ignore_ascii_case(tag)) {
+            unique.push(tag.to_string());
+        }
+    }
+    Ok(unique)
+}
+
+/// Lets the user revise the options entered earlier, one at a time.
+///
+/// Each prompt starts out with the current option text.
+fn edit_options(options: &[String]) -> InquireResult<Vec<String>> {
+    options
+        .iter()
+        .enumerate()
+        .map(|(i, option)| {
+            Text::new(&format!("({})", option_letter(i)))
+                .with_initial_value(option)
+                .prompt()
+        })
+        .collect()
+}
+
+/// Prompts for an optional explanation of every wrong option.
+///
+/// # Returns
+///
+/// One entry per option, `None` for the correct option and for options left blank.
+fn read_feedback(options: &[String], answer: &str) -> InquireResult<Vec<Option<String>>> {
+    options
+        .iter()
+        .enumerate()
+        .map(|(i, option)| {
+            if option == answer {
+                return Ok(None);
+            }
+            let feedback = Text::new(&format!("Feedback for ({}) {}", option_letter(i), option))
+                .with_help_message("shown when this option is picked; leave blank for none")
+                .prompt_skippable()?
+                .unwrap_or_default();
+            let feedback = feedback.trim();
+
+            Ok((!feedback.is_empty()).then(|| feedback.to_string()))
+        })
+        .collect()
+}
+
+/// Asks the user to pick the correct option for a question.
+fn select_answer(message: &str, options: &[String]) -> InquireResult<String> {
+    Select::new(message, options.to_vec())
+        .with_formatter(OPTION_FORMATTER)
+        .prompt()
+}
+
 /// Formatter for displaying options in the select prompt.
 ///
 /// This formatter takes an index and an option value and returns a string in the
 /// format "(letter) option_value", where the letter corresponds to the option's position.
-const OPTION_FORMATTER: OptionFormatter<String> =
-    &|i| format!("({}) {}", ('a'..).nth(i.index).unwrap(), i.value);
+pub const OPTION_FORMATTER: OptionFormatter<String> =
+    &|i| format!("({}) {}", option_letter(i.index), i.value);
+
+/// Formatter for displaying the chosen options of a multi-select prompt.
+///
+/// Each selected option is shown as "(letter) option_value", separated by commas.
+pub const MULTI_OPTION_FORMATTER: MultiOptionFormatter<String> = &|opts| {
+    opts.iter()
+        .map(|o| format!("({}) {}", option_letter(o.index), o.value))
+        .collect::<Vec<_>>()
+        .join(", ")
+};
+
+/// Formatter that shows a same-or-opposite answer as "Same" or "Opposite".
+pub const SAME_OPPOSITE_FORMATTER: BoolFormatter<'_> = &|ans| {
+    if ans {
+        String::from("Same")
+    } else {
+        String::from("Opposite")
+    }
+};
+
+/// Parser that accepts "s"/"same" and "o"/"opposite" in any case.
+pub const SAME_OPPOSITE_PARSER: BoolParser<'_> = &|ans| {
+    let ans = ans.to_lowercase();
+
+    match ans.as_str() {
+        "s" | "same" => Ok(true),
+        "o" | "opposite" => Ok(false),
+        _ => Err(()),
+    }
+};
 
 /// Implementation of the `Entry` trait for `Matching` exercises.
 ///
@@ -73,20 +389,22 @@ const OPTION_FORMATTER: OptionFormatter<String> =
 /// - Prompting for a set of options that correspond to these questions.
 /// - Asking the user to select the correct option for each question.
 impl Entry for Matching {
-    fn read() -> Vec<Self> {
-        let questions = read_questions();
-        let options = read_options(questions.len());
+    fn read(config: &EntryConfig) -> InquireResult<Vec<Self>> {
+        let questions = read_questions(config)?;
+        let options = read_options(questions.len())?;
 
         questions
             .into_iter()
             .enumerate()
             .map(|(i, question)| {
-                let answer = Select::new(&format!("{}. {}", i + 1, question), options.clone())
-                    .with_formatter(OPTION_FORMATTER)
-                    .prompt()
-                    .unwrap();
+                let answer = Select::new(
+                    &format!("{}{}", config.prefix(i), question),
+                    options.clone(),
+                )
+                .with_formatter(OPTION_FORMATTER)
+                .prompt()?;
 
-                Matching::new(question, answer)
+                Ok(Matching::new(question, answer))
             })
             .collect()
     }
@@ -97,17 +415,15 @@ impl Entry for Matching {
 /// This reads yes/no exercises by prompting the user with each question and recording
 /// a boolean response.
 impl Entry for YesNo {
-    fn read() -> Vec<Self> {
-        let questions = read_questions();
+    fn read(config: &EntryConfig) -> InquireResult<Vec<Self>> {
+        let questions = read_questions(config)?;
 
         questions
             .into_iter()
             .enumerate()
             .map(|(i, question)| {
-                let answer = Confirm::new(&format!("{}. {}", i + 1, question))
-                    .prompt()
-                    .unwrap();
-                YesNo::new(question, answer)
+                let answer = Confirm::new(&format!("{}{}", config.prefix(i), question)).prompt()?;
+                Ok(YesNo::new(question, answer))
             })
             .collect()
     }
@@ -116,19 +432,44 @@ impl Entry for YesNo {
 /// Implementation of the `Entry` trait for `Recall` exercises.
 ///
 /// This reads recall exercises by prompting the user for questions and capturing
-/// free-text answers.
+/// free-text answers, several of them when separated by `/`.
 impl Entry for Recall {
-    fn read() -> Vec<Self> {
-        let questions = read_questions();
+    fn read(config: &EntryConfig) -> InquireResult<Vec<Self>> {
+        let questions = read_questions(config)?;
 
         questions
             .into_iter()
             .enumerate()
             .map(|(i, question)| {
-                let answer = Text::new(&format!("{}. {}", i + 1, question))
-                    .prompt()
-                    .unwrap();
-                Recall::new(question, answer)
+                let answers: Vec<String> = Text::new(&format!("{}{}", config.prefix(i), question))
+                    .with_help_message(
+                        "separate several accepted answers with /; start one with re: or glob: \
+                         to accept a pattern",
+                    )
+                    .with_validator(|text: &str| {
+                        if let Some(e) =
+                            text.split('/').find_map(|a| Pattern::parse(a.trim()).err())
+                        {
+                            return Ok(Validation::Invalid(e.into()));
+                        }
+                        Ok(if text.split('/').any(|a| !a.trim().is_empty()) {
+                            Validation::Valid
+                        } else {
+                            Validation::Invalid("Enter at least one answer".into())
+                        })
+                    })
+                    .prompt()?
+                    .split('/')
+                    .map(str::trim)
+                    .filter(|a| !a.is_empty())
+                    .map(String::from)
+                    .collect();
+                // Only answers with capitals can differ by case, so skip the question otherwise.
+                let case_sensitive = answers.iter().any(|a| a.chars().any(char::is_uppercase))
+                    && Confirm::new("Must the capitalization match?")
+                        .with_default(false)
+                        .prompt()?;
+                Ok(Recall::new(question, answers, case_sensitive))
             })
             .collect()
     }
@@ -141,34 +482,68 @@ impl Entry for Recall {
 /// - Asking for the number of options for each question.
 /// - Prompting for the question text and its options.
 /// - Allowing the user to select the correct answer for each question.
+/// - Optionally explaining why each wrong option is wrong.
+/// - Letting the user revise the question or its options before moving on.
 impl Entry for Mcq {
-    fn read() -> Vec<Self> {
-        let n = inquire::CustomType::<usize>::new("How many questions?")
-            .with_error_message("Please enter a valid number")
-            .prompt()
-            .unwrap_or(0);
+    fn read(config: &EntryConfig) -> InquireResult<Vec<Self>> {
+        let n = read_count("How many questions?", MIN_QUESTIONS, config.max_count)?;
+        let m = read_option_count("How many options?", MIN_OPTIONS, config)?;
+
+        (0..n)
+            .map(|i| {
+                let mut q = Text::new(&config.question_prompt(i)).prompt()?;
+                let mut opts = read_options(m)?;
+                let mut answer = select_answer(&format!("{}{}", config.prefix(i), q), &opts)?;
+                let mut feedback = read_feedback(&opts, &answer)?;
+
+                loop {
+                    match Select::new("Looks good?", McqReview::all()).prompt()? {
+                        McqReview::Save => return Ok(Mcq::new(q, answer, opts, feedback)),
+                        McqReview::EditQuestion => {
+                            q = Text::new(&config.question_prompt(i))
+                                .with_initial_value(&q)
+                                .prompt()?;
+                        }
+                        McqReview::EditOptions => {
+                            opts = edit_options(&opts)?;
+                            answer = select_answer(&format!("{}{}", config.prefix(i), q), &opts)?;
+                            feedback = read_feedback(&opts, &answer)?;
+                        }
+                    }
+                }
+            })
+            .collect()
+    }
+}
 
-        let m = inquire::CustomType::<usize>::new("How many options?")
-            .with_error_message("Please enter a valid number")
-            .prompt()
-            .unwrap_or(0);
+/// Implementation of the `Entry` trait for `McqMulti` ("select all that apply") exercises.
+///
+/// This reads the questions and options the same way as `Mcq`, but lets the user mark
+/// every correct option with a multi-select prompt. At least one option must be chosen.
+impl Entry for McqMulti {
+    fn read(config: &EntryConfig) -> InquireResult<Vec<Self>> {
+        let n = read_count("How many questions?", MIN_QUESTIONS, config.max_count)?;
+        let m = read_option_count("How many options?", MIN_OPTIONS, config)?;
 
         (0..n)
             .map(|i| {
-                (
+                Ok((
                     i,
-                    Text::new(&format!("{}. ", i + 1)).prompt().unwrap(),
-                    read_options(m),
-                )
+                    Text::new(&config.question_prompt(i)).prompt()?,
+                    read_options(m)?,
+                ))
             })
-            .collect::<Vec<_>>()
+            .collect::<InquireResult<Vec<_>>>()?
             .into_iter()
             .map(|(i, q, opts)| {
-                let answer = Select::new(&format!("{}. {}", i + 1, q), opts.clone())
-                    .with_formatter(OPTION_FORMATTER)
-                    .prompt()
-                    .unwrap();
-                Mcq::new(q, answer, opts)
+                let answers = MultiSelect::new(&format!("{}{}", config.prefix(i), q), opts.clone())
+                    .with_formatter(MULTI_OPTION_FORMATTER)
+                    .with_validator(
+                        MinLengthValidator::new(1)
+                            .with_message("Select at least one correct option"),
+                    )
+                    .prompt()?;
+                Ok(McqMulti::new(q, answers, opts))
             })
             .collect()
     }
@@ -179,104 +554,434 @@ impl Entry for Mcq {
 /// This reads recognize-root exercises by:
 /// - Prompting for the number of questions.
 /// - For each question, capturing the question text, an example, and the user's answer.
+/// - Letting the user revise any of the three before moving on.
 impl Entry for RecognizeRoot {
-    fn read() -> Vec<Self> {
-        let n = inquire::CustomType::<usize>::new("How many questions?")
-            .prompt()
-            .unwrap_or(0);
+    fn read(config: &EntryConfig) -> InquireResult<Vec<Self>> {
+        let n = read_count("How many questions?", MIN_QUESTIONS, config.max_count)?;
 
         (0..n)
             .map(|i| {
-                (
-                    i,
-                    Text::new(&format!("{}. ", i + 1)).prompt().unwrap(),
-                    Text::new("Example").prompt().unwrap(),
-                )
-            })
-            .collect::<Vec<_>>()
-            .into_iter()
-            .map(|(i, q, ex)| {
-                let answer = Text::new(&format!("{}. {}, Example: {}", i + 1, q, ex))
-                    .prompt()
-                    .unwrap();
-                RecognizeRoot::new(q, answer, ex)
+                let mut q = Text::new(&config.question_prompt(i)).prompt()?;
+                let mut ex = Text::new("Example").prompt()?;
+                let mut answer =
+                    Text::new(&format!("{}{}, Example: {}", config.prefix(i), q, ex)).prompt()?;
+
+                loop {
+                    match Select::new("Looks good?", RootReview::all()).prompt()? {
+                        RootReview::Save => return Ok(RecognizeRoot::new(q, answer, ex)),
+                        RootReview::EditQuestion => {
+                            q = Text::new(&config.question_prompt(i))
+                                .with_initial_value(&q)
+                                .prompt()?;
+                        }
+                        RootReview::EditExample => {
+                            ex = Text::new("Example").with_initial_value(&ex).prompt()?;
+                        }
+                        RootReview::EditAnswer => {
+                            answer =
+                                Text::new(&format!("{}{}, Example: {}", config.prefix(i), q, ex))
+                                    .with_initial_value(&answer)
+                                    .prompt()?;
+                        }
+                    }
+                }
             })
             .collect()
     }
 }
 
 impl Entry for FillInTheBlank {
-    fn read() -> Vec<Self> {
-        let n = inquire::CustomType::<usize>::new("How many questions?")
-            .prompt()
-            .unwrap_or(0);
+    fn read(config: &EntryConfig) -> InquireResult<Vec<Self>> {
+        let n = read_count("How many questions?", MIN_QUESTIONS, config.max_count)?;
 
         (0..n)
             .map(|i| {
-                (
+                Ok((
                     i,
-                    Text::new(&format!("{}. ", i + 1)).prompt().unwrap(),
-                    Text::new(&format!("{}. ", i + 1)).prompt().unwrap(),
-                )
+                    Text::new(&config.question_prompt(i))
+                        .with_help_message(
+                            "the clue, e.g. a sentence that describes the missing word",
+                        )
+                        .prompt()?,
+                    Text::new(&config.question_prompt(i))
+                        .with_help_message(
+                            "the phrase with _ in place of the missing word, e.g. An _ gambler",
+                        )
+                        .prompt()?,
+                ))
             })
-            .collect::<Vec<_>>()
+            .collect::<InquireResult<Vec<_>>>()?
             .into_iter()
             .map(|(i, q, bl)| {
-                let answer = Text::new(&format!("{}. {}\n {}. {}", i + 1, q, i + 1, bl))
-                    .prompt()
-                    .unwrap();
-                FillInTheBlank::new(q, answer, bl)
+                let answer = Text::new(&format!("{0}{1}\n {0}{2}", config.prefix(i), q, bl))
+                    .with_help_message("the missing word")
+                    .prompt()?;
+                Ok(FillInTheBlank::new(q, answer, bl))
             })
             .collect()
     }
 }
 
 impl Entry for SameOrOpposite {
-    fn read() -> Vec<Self> {
-        const CUSTOM_BOOL_FORMATTER: BoolFormatter<'_> = &|ans| {
-            if ans {
-                String::from("Same")
-            } else {
-                String::from("Opposite")
-            }
-        };
+    fn read(config: &EntryConfig) -> InquireResult<Vec<Self>> {
+        let n = read_count("How many questions?", MIN_QUESTIONS, config.max_count)?;
+
+        (0..n)
+            .map(|i| {
+                Ok((
+                    i,
+                    Text::new(&format!("{}a. ", config.prefix(i))).prompt()?,
+                    Text::new(&format!("{}b. ", config.prefix(i))).prompt()?,
+                ))
+            })
+            .collect::<InquireResult<Vec<_>>>()?
+            .into_iter()
+            .map(|(i, a, b)| {
+                let answer = Confirm::new(&format!("{}{}-{}", config.prefix(i), a, b))
+                    .with_formatter(SAME_OPPOSITE_FORMATTER)
+                    .with_parser(SAME_OPPOSITE_PARSER)
+                    .prompt()?;
+                Ok(SameOrOpposite::new(a, b, answer))
+            })
+            .collect()
+    }
+}
 
-        const CUSTOM_BOOL_PARSER: BoolParser<'_> = &|ans| {
-            let ans = ans.to_lowercase();
+/// Implementation of the `Entry` trait for `Ordering` exercises.
+///
+/// This reads ordering exercises by:
+/// - Asking for the number of questions and the number of items in each.
+/// - Prompting for each question's instruction and its items, in the correct order.
+impl Entry for Ordering {
+    fn read(config: &EntryConfig) -> InquireResult<Vec<Self>> {
+        let n = read_count("How many questions?", MIN_QUESTIONS, config.max_count)?;
+        let m = read_option_count("How many items?", MIN_OPTIONS, config)?;
 
-            match ans.as_str() {
-                "s" | "same" => Ok(true),
-                "o" | "opposite" => Ok(false),
-                _ => Err(()),
-            }
-        };
+        (0..n)
+            .map(|i| {
+                let q = Text::new(&config.question_prompt(i))
+                    .with_help_message(
+                        "what the items are ordered by, e.g. intensity, mildest first",
+                    )
+                    .prompt()?;
+                let items = (0..m)
+                    .map(|j| {
+                        Text::new(&format!("{}{}.", config.prefix(i), j + 1))
+                            .with_help_message("enter the items in the correct order")
+                            .prompt()
+                    })
+                    .collect::<InquireResult<Vec<_>>>()?;
+                Ok(Ordering::new(q, items))
+            })
+            .collect()
+    }
+}
 
-        let n = inquire::CustomType::<usize>::new("How many questions?")
-            .prompt()
-            .unwrap_or(0);
+/// Implementation of the `Entry` trait for `Cloze` exercises.
+///
+/// The text is checked for numbered blanks as it is typed, then the answer for each blank
+/// is read in order.
+impl Entry for Cloze {
+    fn read(config: &EntryConfig) -> InquireResult<Vec<Self>> {
+        let n = read_count("How many questions?", MIN_QUESTIONS, config.max_count)?;
 
         (0..n)
             .map(|i| {
-                (
-                    i,
-                    Text::new(&format!("{} a. ", i + 1)).prompt().unwrap(),
-                    Text::new(&format!("{} b. ", i + 1)).prompt().unwrap(),
-                )
+                let text = Text::new(&config.question_prompt(i))
+                    .with_help_message("mark the blanks with {{1}}, {{2}} and so on")
+                    .with_validator(|text: &str| {
+                        Ok(match Cloze::count_blanks(text) {
+                            Ok(_) => Validation::Valid,
+                            Err(e) => Validation::Invalid(e.into()),
+                        })
+                    })
+                    .prompt()?;
+                let blanks = Cloze::count_blanks(&text).unwrap_or_default();
+                let answers = (1..=blanks)
+                    .map(|b| Text::new(&format!("{}{{{{{}}}}}", config.prefix(i), b)).prompt())
+                    .collect::<InquireResult<Vec<_>>>()?;
+                Ok(Cloze::new(text, answers))
             })
-            .collect::<Vec<_>>()
+            .collect()
+    }
+}
+
+/// Implementation of the `Entry` trait for `Etymology` exercises.
+///
+/// This reads etymology exercises by prompting, for each question, for the word, its roots
+/// (separated by commas), the language they come from and their literal meaning.
+impl Entry for Etymology {
+    fn read(config: &EntryConfig) -> InquireResult<Vec<Self>> {
+        let n = read_count("How many questions?", MIN_QUESTIONS, config.max_count)?;
+
+        (0..n)
+            .map(|i| {
+                let word = Text::new(&config.question_prompt(i))
+                    .with_help_message("the word, e.g. egocentric")
+                    .prompt()?;
+                let roots: Vec<String> = Text::new("Roots")
+                    .with_help_message("separate several roots with commas, e.g. ego, centrum")
+                    .with_validator(|text: &str| {
+                        Ok(if text.split(',').any(|r| !r.trim().is_empty()) {
+                            Validation::Valid
+                        } else {
+                            Validation::Invalid("Enter at least one root".into())
+                        })
+                    })
+                    .prompt()?
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|r| !r.is_empty())
+                    .map(String::from)
+                    .collect();
+                let language = Text::new("Language")
+                    .with_help_message("where the roots come from, e.g. Latin")
+                    .prompt()?;
+                let meaning = Text::new(&format!(
+                    "{}{} ({})",
+                    config.prefix(i),
+                    word,
+                    roots.join(" + ")
+                ))
+                .with_help_message("the literal meaning of the roots, e.g. I, center")
+                .prompt()?;
+                Ok(Etymology::new(word, roots, language, meaning))
+            })
+            .collect()
+    }
+}
+
+impl Entry for UseInSentence {
+    fn read(config: &EntryConfig) -> InquireResult<Vec<Self>> {
+        let words = read_questions(config)?;
+
+        words
             .into_iter()
-            .map(|(i, a, b)| {
-                let answer = Confirm::new(&format!("{}. {}-{}", i + 1, a, b))
-                    .with_formatter(CUSTOM_BOOL_FORMATTER)
-                    .with_parser(CUSTOM_BOOL_PARSER)
-                    .prompt()
-                    .unwrap();
-                SameOrOpposite::new(a, b, answer)
+            .enumerate()
+            .map(|(i, word)| {
+                let model = Text::new(&format!("{}{}", config.prefix(i), word))
+                    .with_help_message("a model sentence that uses the word correctly")
+                    .prompt()?;
+                Ok(UseInSentence::new(word, model))
             })
             .collect()
     }
 }
 
+/// Implementation of the `Entry` trait for `Spelling` exercises.
+///
+/// This reads spelling exercises by prompting for the definitions first and then for the
+/// correctly spelled word of each. A pronunciation can be added afterwards like for any
+/// other question, and is shown with the definition in quizzes.
+impl Entry for Spelling {
+    fn read(config: &EntryConfig) -> InquireResult<Vec<Self>> {
+        let definitions = read_questions(config)?;
+
+        definitions
+            .into_iter()
+            .enumerate()
+            .map(|(i, definition)| {
+                let word = Text::new(&format!("{}{}", config.prefix(i), definition))
+                    .with_help_message("the word, spelled exactly")
+                    .prompt()?;
+                Ok(Spelling::new(definition, word.trim().to_string()))
+            })
+            .collect()
+    }
+}
+
+/// Implementation of the `Entry` trait for `MatchingSet` exercises.
+///
+/// This reads matching sets by:
+/// - Asking for the number of sets, and the number of items in the left and right column.
+/// - Prompting for each set's instruction and the items of both columns.
+/// - Letting the user pick the match of every left item among the right items not yet used.
+///
+/// Right items that are never picked stay in the set as distractors.
+impl Entry for MatchingSet {
+    fn read(config: &EntryConfig) -> InquireResult<Vec<Self>> {
+        let n = read_count("How many sets?", MIN_QUESTIONS, config.max_count)?;
+        let l = read_option_count("How many items on the left?", MIN_OPTIONS, config)?;
+        let r = read_option_count("How many items on the right?", l, config)?;
+
+        (0..n)
+            .map(|i| {
+                let q = Text::new(&config.question_prompt(i))
+                    .with_help_message("the instruction, e.g. Match each word with its meaning")
+                    .prompt()?;
+                let left = (0..l)
+                    .map(|j| Text::new(&format!("{}{}.", config.prefix(i), j + 1)).prompt())
+                    .collect::<InquireResult<Vec<_>>>()?;
+                let right = read_options(r)?;
+
+                let mut unused: Vec<usize> = (0..r).collect();
+                let pairs = left
+                    .iter()
+                    .map(|item| {
+                        let choices: Vec<String> =
+                            unused.iter().map(|&j| right[j].clone()).collect();
+                        let pick = Select::new(item, choices).raw_prompt()?;
+                        Ok(unused.remove(pick.index))
+                    })
+                    .collect::<InquireResult<Vec<_>>>()?;
+                Ok(MatchingSet::new(q, left, right, pairs))
+            })
+            .collect()
+    }
+}
+
+/// Implementation of the `Entry` trait for `OddOneOut` exercises.
+///
+/// This reads odd-one-out exercises by prompting for the words of each question, letting
+/// the user pick the one that doesn't belong and asking why, which becomes the question's
+/// explanation.
+impl Entry for OddOneOut {
+    fn read(config: &EntryConfig) -> InquireResult<Vec<Self>> {
+        let n = read_count("How many questions?", MIN_QUESTIONS, config.max_count)?;
+        let m = read_count("How many words?", MIN_OPTIONS + 1, config.max_count)?;
+
+        (0..n)
+            .map(|i| {
+                let words = read_options(m)?;
+                let answer = Select::new(
+                    &format!("{}Which word doesn't belong?", config.prefix(i)),
+                    words.clone(),
+                )
+                .with_formatter(OPTION_FORMATTER)
+                .prompt()?;
+                let reason = Text::new("Why?")
+                    .with_help_message("shown after grading; leave blank to skip")
+                    .prompt_skippable()?
+                    .unwrap_or_default();
+
+                let mut odd = OddOneOut::new(words, answer);
+                let reason = reason.trim();
+                odd.meta_mut().explanation = (!reason.is_empty()).then(|| reason.to_string());
+                Ok(odd)
+            })
+            .collect()
+    }
+}
+
+/// Implementation of the `Entry` trait for `Analogy` exercises.
+///
+/// This reads analogies by prompting for the three given terms, the answer and optional
+/// distractors separated by commas. Analogies with distractors are quizzed as multiple
+/// choice questions, the others by typing the answer.
+impl Entry for Analogy {
+    fn read(config: &EntryConfig) -> InquireResult<Vec<Self>> {
+        let n = read_count("How many questions?", MIN_QUESTIONS, config.max_count)?;
+
+        (0..n)
+            .map(|i| {
+                let prefix = config.prefix(i);
+                let a = Text::new(&format!("{}A", prefix))
+                    .with_help_message("A is to B as C is to ?")
+                    .prompt()?;
+                let b = Text::new(&format!("{}{} is to", prefix, a)).prompt()?;
+                let c = Text::new(&format!("{}{} is to {} as", prefix, a, b)).prompt()?;
+                let answer =
+                    Text::new(&format!("{}{} is to {} as {} is to", prefix, a, b, c)).prompt()?;
+                let distractors = Text::new("Distractors")
+                    .with_help_message(
+                        "wrong options separated by commas; leave blank to type the answer",
+                    )
+                    .prompt_skippable()?
+                    .unwrap_or_default()
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|d| !d.is_empty())
+                    .map(String::from)
+                    .collect();
+                Ok(Analogy::new([a, b, c], answer, distractors))
+            })
+            .collect()
+    }
+}
+
+/// Implementation of the `Entry` trait for `Custom` exercises.
+///
+/// This reads custom exercises by:
+/// - Letting the user pick one of the templates in the template directory.
+/// - Prompting for every field the template declares, for each question.
+/// - Showing the filled-in prompt and answer as a check.
+impl Entry for Custom {
+    fn read(config: &EntryConfig) -> InquireResult<Vec<Self>> {
+        let dir = template::template_dir();
+        let templates = Template::load_all(&dir)
+            .map_err(|e| InquireError::Custom(format!("Could not load templates: {}", e).into()))?;
+        if templates.is_empty() {
+            println!("No templates found in {}", dir.display());
+            return Ok(Vec::new());
+        }
+
+        let template = Select::new("Template", templates).prompt()?;
+        let n = read_count("How many questions?", MIN_QUESTIONS, config.max_count)?;
+
+        (0..n)
+            .map(|i| {
+                let fields = template
+                    .fields
+                    .iter()
+                    .map(|field| {
+                        let value =
+                            Text::new(&format!("{}{}", config.prefix(i), field)).prompt()?;
+                        Ok((field.clone(), value))
+                    })
+                    .collect::<InquireResult<BTreeMap<_, _>>>()?;
+
+                println!(
+                    "  {} -> {}",
+                    Template::fill(&template.prompt, &fields),
+                    Template::fill(&template.answer, &fields)
+                );
+                Ok(Custom::new(template.name.clone(), fields))
+            })
+            .collect()
+    }
+}
+
+/// The choices offered after an `Mcq` question has been entered.
+#[derive(strum_macros::Display)]
+enum McqReview {
+    Save,
+    #[strum(serialize = "Edit question")]
+    EditQuestion,
+    #[strum(serialize = "Edit options")]
+    EditOptions,
+}
+
+impl McqReview {
+    /// Returns a list of all review choices.
+    fn all() -> Vec<McqReview> {
+        vec![Self::Save, Self::EditQuestion, Self::EditOptions]
+    }
+}
+
+/// The choices offered after a `RecognizeRoot` question has been entered.
+#[derive(strum_macros::Display)]
+enum RootReview {
+    Save,
+    #[strum(serialize = "Edit question")]
+    EditQuestion,
+    #[strum(serialize = "Edit example")]
+    EditExample,
+    #[strum(serialize = "Edit answer")]
+    EditAnswer,
+}
+
+impl RootReview {
+    /// Returns a list of all review choices.
+    fn all() -> Vec<RootReview> {
+        vec![
+            Self::Save,
+            Self::EditQuestion,
+            Self::EditExample,
+            Self::EditAnswer,
+        ]
+    }
+}
+
 /// Enum that represents the different exercise entry types available to the user.
 ///
 /// This enum is used in the interactive prompt to let the user choose the type of exercise
@@ -288,12 +993,39 @@ enum EntryOptions {
     YesNo,
     Recall,
     Mcq,
+    McqMulti,
     RecognizeRoot,
     FillInTheBlank,
     SameOrOpposite,
+    Ordering,
+    Cloze,
+    Etymology,
+    UseInSentence,
+    Spelling,
+    MatchingSet,
+    OddOneOut,
+    Analogy,
+    Custom,
     SaveAndQuit,
 }
 
+/// Scorer used to narrow the exercise type menu as the user types.
+///
+/// Options are matched case-insensitively by substring, with options starting with the
+/// typed text ranked above those that merely contain it.
+const ENTRY_OPTION_SCORER: Scorer<EntryOptions> = &|input, _, value, _| {
+    let input = input.to_lowercase();
+    let value = value.to_lowercase();
+
+    if value.starts_with(&input) {
+        Some(2)
+    } else if value.contains(&input) {
+        Some(1)
+    } else {
+        None
+    }
+};
+
 impl EntryOptions {
     /// Returns a list of all exercise entry options.
     fn all() -> Vec<EntryOptions> {
@@ -302,43 +1034,161 @@ impl EntryOptions {
             Self::YesNo,
             Self::Recall,
             Self::Mcq,
+            Self::McqMulti,
             Self::RecognizeRoot,
             Self::FillInTheBlank,
             Self::SameOrOpposite,
+            Self::Ordering,
+            Self::Cloze,
+            Self::Etymology,
+            Self::UseInSentence,
+            Self::Spelling,
+            Self::MatchingSet,
+            Self::OddOneOut,
+            Self::Analogy,
+            Self::Custom,
             Self::SaveAndQuit,
         ]
     }
+
+    /// Reads a batch of exercises of the chosen type, followed by optional explanations,
+    /// pronunciations, mnemonics and attached media and the batch's source, tags, chapter
+    /// and difficulty.
+    ///
+    /// # Returns
+    ///
+    /// The entered exercise group, or `None` if the user chose to save and quit.
+    fn read(self, config: &EntryConfig) -> InquireResult<Option<Exercise>> {
+        let mut exercise = match self {
+            EntryOptions::Matching => Exercise::Matching(Matching::read(config)?),
+            EntryOptions::YesNo => Exercise::YesNo(YesNo::read(config)?),
+            EntryOptions::Recall => Exercise::Recall(Recall::read(config)?),
+            EntryOptions::Mcq => Exercise::Mcq(Mcq::read(config)?),
+            EntryOptions::McqMulti => Exercise::McqMulti(McqMulti::read(config)?),
+            EntryOptions::RecognizeRoot => Exercise::RecognizeRoot(RecognizeRoot::read(config)?),
+            EntryOptions::FillInTheBlank => Exercise::FillInTheBlank(FillInTheBlank::read(config)?),
+            EntryOptions::SameOrOpposite => Exercise::SameOrOpposite(SameOrOpposite::read(config)?),
+            EntryOptions::Ordering => Exercise::Ordering(Ordering::read(config)?),
+            EntryOptions::Cloze => Exercise::Cloze(Cloze::read(config)?),
+            EntryOptions::Etymology => Exercise::Etymology(Etymology::read(config)?),
+            EntryOptions::UseInSentence => Exercise::UseInSentence(UseInSentence::read(config)?),
+            EntryOptions::Spelling => Exercise::Spelling(Spelling::read(config)?),
+            EntryOptions::MatchingSet => Exercise::MatchingSet(MatchingSet::read(config)?),
+            EntryOptions::OddOneOut => Exercise::OddOneOut(OddOneOut::read(config)?),
+            EntryOptions::Analogy => Exercise::Analogy(Analogy::read(config)?),
+            EntryOptions::Custom => Exercise::Custom(Custom::read(config)?),
+            EntryOptions::SaveAndQuit => return Ok(None),
+        };
+
+        confirm_leaked_answers(&mut exercise)?;
+        read_notes(
+            &mut exercise,
+            "Explanation",
+            "why the answer is right or a usage example, shown after grading",
+            |meta| &mut meta.explanation,
+        )?;
+        read_notes(
+            &mut exercise,
+            "Pronunciation",
+            "IPA or a respelling of the answer, e.g. EE-go-ist",
+            |meta| &mut meta.pronunciation,
+        )?;
+        read_notes(
+            &mut exercise,
+            "Mnemonic",
+            "a memory hook, shown after a wrong reply",
+            |meta| &mut meta.mnemonic,
+        )?;
+        read_media(&mut exercise, &config.media_dir)?;
+
+        let source = read_source()?;
+        let tags = read_tags()?;
+        let chapter = match &config.chapter {
+            Some(chapter) => Some(chapter.clone()),
+            None => read_chapter()?,
+        };
+        let difficulty = read_difficulty()?;
+        for question in exercise.questions_mut() {
+            let meta = question.meta_mut();
+            meta.source = source.clone();
+            meta.tags = tags.clone();
+            meta.chapter = chapter.clone();
+            meta.difficulty = difficulty;
+        }
+        Ok(Some(exercise))
+    }
 }
 
 /// Implementation of the `Entry` trait for the overall `Exercise` enum.
 ///
 /// This method continuously prompts the user to choose an exercise type, reads the
 /// corresponding exercise data, and returns a vector of all exercises entered until
-/// the user selects "SaveAndQuit".
+/// the user selects "SaveAndQuit". The most recently chosen type is highlighted by default
+/// so that several batches of the same type can be entered quickly.
+///
+/// If a batch is interrupted (Ctrl-C or Esc), the user is asked whether the batches entered
+/// so far should be saved. Declining, or interrupting again, returns `OperationInterrupted`
+/// so the caller can quit without saving.
 impl Entry for Exercise {
-    fn read() -> Vec<Self> {
-        (1..)
-            .map_while(|_| {
-                let tp = Select::new("Exercise type", EntryOptions::all())
-                    .prompt()
-                    .unwrap_or(EntryOptions::SaveAndQuit);
-                match tp {
-                    EntryOptions::Matching => Some(Exercise::Matching(Matching::read())),
-                    EntryOptions::YesNo => Some(Exercise::YesNo(YesNo::read())),
-                    EntryOptions::Recall => Some(Exercise::Recall(Recall::read())),
-                    EntryOptions::Mcq => Some(Exercise::Mcq(Mcq::read())),
-                    EntryOptions::RecognizeRoot => {
-                        Some(Exercise::RecognizeRoot(RecognizeRoot::read()))
-                    }
-                    EntryOptions::FillInTheBlank => {
-                        Some(Exercise::FillInTheBlank(FillInTheBlank::read()))
-                    }
-                    EntryOptions::SameOrOpposite => {
-                        Some(Exercise::SameOrOpposite(SameOrOpposite::read()))
+    fn read(config: &EntryConfig) -> InquireResult<Vec<Self>> {
+        let mut last_choice = 0;
+        let mut exercises = Vec::new();
+
+        loop {
+            let group = Select::new("Exercise type", EntryOptions::all())
+                .with_help_message("type to filter, ↑↓ to move, enter to select")
+                .with_scorer(ENTRY_OPTION_SCORER)
+                .with_starting_cursor(last_choice)
+                .raw_prompt()
+                .map(|choice| {
+                    last_choice = choice.index;
+                    choice.value
+                })
+                .or_else(|e| match e {
+                    InquireError::OperationCanceled => Ok(EntryOptions::SaveAndQuit),
+                    e => Err(e),
+                })
+                .and_then(|tp| tp.read(config));
+
+            match group {
+                Ok(Some(exercise)) => {
+                    exercises.push(exercise);
+                    if let Some(draft) = &config.draft {
+                        if let Err(e) = draft::save(draft, &exercises) {
+                            eprintln!("Could not save a draft of the entries: {}", e);
+                        }
                     }
-                    EntryOptions::SaveAndQuit => None,
                 }
-            })
-            .collect()
+                Ok(None) => return Ok(exercises),
+                Err(InquireError::OperationInterrupted | InquireError::OperationCanceled) => {
+                    return confirm_save(exercises)
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+/// Asks whether the exercises entered before an interrupt should be kept.
+///
+/// # Returns
+///
+/// The exercises if the user wants to save them, or `OperationInterrupted` if nothing was
+/// entered, the user declined, or the confirmation itself was interrupted.
+fn confirm_save(exercises: Vec<Exercise>) -> InquireResult<Vec<Exercise>> {
+    let n: usize = exercises.iter().map(Exercise::question_count).sum();
+    if n == 0 {
+        return Err(InquireError::OperationInterrupted);
+    }
+
+    let save = Confirm::new(&format!("Save the {} exercises entered so far?", n))
+        .with_default(true)
+        .with_help_message("press Ctrl-C again to quit without saving")
+        .prompt();
+
+    match save {
+        Ok(true) => Ok(exercises),
+        Ok(false) | Err(InquireError::OperationCanceled) => Err(InquireError::OperationInterrupted),
+        Err(e) => Err(e),
     }
 }
@@ -1,26 +1,644 @@
-use serde::{Deserialize, Serialize};
+use crate::date::Date;
+use crate::history::{unix_now, Attempt};
+use crate::markdown::render as md;
+use crate::pattern::Pattern;
+use crate::rng::Rng;
+use crate::scheduler::Schedule;
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::process;
+use std::str::FromStr;
+use std::sync::{Mutex, OnceLock};
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, strum_macros::IntoStaticStr, strum_macros::EnumVariantNames)]
 #[serde(tag = "type", content = "data")]
 pub enum Exercise {
     Matching(Vec<Matching>),
     YesNo(Vec<YesNo>),
     Recall(Vec<Recall>),
     Mcq(Vec<Mcq>),
+    McqMulti(Vec<McqMulti>),
     RecognizeRoot(Vec<RecognizeRoot>),
     FillInTheBlank(Vec<FillInTheBlank>),
     SameOrOpposite(Vec<SameOrOpposite>),
+    Ordering(Vec<Ordering>),
+    Cloze(Vec<Cloze>),
+    Etymology(Vec<Etymology>),
+    UseInSentence(Vec<UseInSentence>),
+    Spelling(Vec<Spelling>),
+    MatchingSet(Vec<MatchingSet>),
+    OddOneOut(Vec<OddOneOut>),
+    Analogy(Vec<Analogy>),
+    Custom(Vec<Custom>),
+}
+
+/// Returns a new random question ID of 16 hex digits.
+///
+/// IDs come from one generator per run, seeded from the clock and the process ID, so IDs
+/// made in the same run never repeat and IDs from different runs almost surely don't.
+pub fn new_id() -> String {
+    static RNG: OnceLock<Mutex<Rng>> = OnceLock::new();
+    let rng = RNG.get_or_init(|| {
+        let seed = Rng::from_time().next_u64() ^ u64::from(process::id()).rotate_left(32);
+        Mutex::new(Rng::new(seed))
+    });
+    let value = rng.lock().map(|mut r| r.next_u64()).unwrap_or_default();
+    format!("{:016x}", value)
+}
+
+/// Text that text formats can't write as it is, because it would read as another value,
+/// as syntax or not at all, for round-trip tests.
+#[cfg(test)]
+pub const AWKWARD_TEXT: [&str; 27] = [
+    "",
+    " ",
+    "yes",
+    "No",
+    "null",
+    "~",
+    "true",
+    "1e3",
+    "0x1f",
+    "-1",
+    "-",
+    "- item",
+    "--- ",
+    "key: value",
+    "ends with:",
+    "# not a comment",
+    "a #b",
+    "[list]",
+    "{map}",
+    "*alias &anchor !tag",
+    "'single' \"double\" \\ `back`",
+    "| > @ %",
+    "two\nlines",
+    "trailing newline\n",
+    "  leading and trailing spaces  ",
+    "tab\tand bell\u{7}",
+    "naïve café — ☃ 日本語 🎉",
+];
+
+/// Metadata shared by every kind of question.
+///
+/// It is flattened into each question's JSON object, and every field is optional so that
+/// files written before a field existed keep loading.
+#[derive(Serialize, Deserialize)]
+pub struct Meta {
+    /// Identifies the question across edits and rewrites of the data file. Questions from
+    /// files written before IDs existed are given one derived from their content when the
+    /// file is upgraded, see `migrate`; a question added to a file by hand without an ID
+    /// gets a random one, which is kept the next time the deck is saved.
+    #[serde(default = "new_id")]
+    pub id: String,
+    /// When the question was entered, in seconds since the Unix epoch; unknown for
+    /// questions from files written before this was recorded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created: Option<u64>,
+    /// When the question's content or metadata was last edited, if ever.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub modified: Option<u64>,
+    /// Where the word was encountered (book, page, article).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    /// Free-form labels for grouping questions, e.g. a topic or a word root.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// The chapter or study session the question was entered for.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chapter: Option<String>,
+    /// The author's note on why the answer is right, e.g. a usage example.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub explanation: Option<String>,
+    /// How the answer is pronounced, in IPA or a respelling such as "EE-go-ist".
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pronunciation: Option<String>,
+    /// A memory hook for the answer, shown after a wrong reply.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mnemonic: Option<String>,
+    /// The IDs of related questions, e.g. one for a word that is often confused with this
+    /// one.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub links: Vec<String>,
+    /// Parks the question: it stays in the deck but is never picked for practice.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub suspended: bool,
+    /// Marks the question for revisiting, see `--starred`.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub starred: bool,
+    /// An image or other file shown with the question, named relative to the media
+    /// directory next to the data file.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub media: Option<String>,
+    /// How hard the question is, as rated by its author.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub difficulty: Option<Difficulty>,
+    /// When the question is next due for review, once it has been reviewed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schedule: Option<Schedule>,
+    /// Every reply given to the question in quizzes and reviews, oldest first.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub history: Vec<Attempt>,
+}
+
+impl Default for Meta {
+    fn default() -> Meta {
+        Meta {
+            id: new_id(),
+            created: Some(unix_now()),
+            modified: None,
+            source: None,
+            tags: Vec::new(),
+            chapter: None,
+            explanation: None,
+            pronunciation: None,
+            mnemonic: None,
+            links: Vec::new(),
+            suspended: false,
+            starred: false,
+            media: None,
+            difficulty: None,
+            schedule: None,
+            history: Vec::new(),
+        }
+    }
+}
+
+/// How hard a question is, as rated when it is entered or edited.
+#[derive(Serialize, Deserialize, strum_macros::Display, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub enum Difficulty {
+    Easy,
+    Medium,
+    Hard,
+}
+
+impl Difficulty {
+    /// Returns a list of all difficulties, from easiest to hardest.
+    pub fn all() -> Vec<Difficulty> {
+        vec![Self::Easy, Self::Medium, Self::Hard]
+    }
+}
+
+impl FromStr for Difficulty {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        Difficulty::all()
+            .into_iter()
+            .find(|d| d.to_string().eq_ignore_ascii_case(text.trim()))
+            .ok_or_else(|| {
+                format!(
+                    "Unknown difficulty '{}' (valid difficulties: easy, medium, hard)",
+                    text
+                )
+            })
+    }
+}
+
+/// Common access to the parts every question type has.
+pub trait Question: fmt::Display {
+    /// Returns the text the learner is prompted with, without any answer.
+    fn text(&self) -> String;
+
+    /// Returns the question's metadata.
+    fn meta(&self) -> &Meta;
+
+    /// Returns the question's metadata for modification.
+    fn meta_mut(&mut self) -> &mut Meta;
+
+    /// Checks whether the answer appears verbatim in the prompt, giving it away.
+    ///
+    /// Only question types where the learner has to produce the answer themselves can
+    /// leak it, so the default is `false`.
+    fn gives_away_answer(&self) -> bool {
+        false
+    }
+
+    /// Returns the `level`-th hint towards the answer, starting at 1, or `None` when there
+    /// are no more hints.
+    ///
+    /// Only question types where the learner types the answer have hints.
+    fn hint(&self, _level: usize) -> Option<String> {
+        None
+    }
+}
+
+/// Builds successive hints for a typed answer.
+///
+/// The first hint shows the answer's shape with every letter blanked out, and every later
+/// hint reveals one more letter, stopping before the whole answer is given away.
+fn hint_for(answer: &str, level: usize) -> Option<String> {
+    let answer = answer.trim();
+    let letters = answer.chars().filter(|c| c.is_alphanumeric()).count();
+    let revealed = level.checked_sub(1)?;
+    if revealed >= letters {
+        return None;
+    }
+
+    let mut shown = 0;
+    let mask: String = answer
+        .chars()
+        .map(|c| {
+            if !c.is_alphanumeric() {
+                c
+            } else if shown < revealed {
+                shown += 1;
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    Some(format!("{} ({} letters)", mask, letters))
+}
+
+/// Checks whether `needle` occurs in `haystack` as a whole word, ignoring case.
+fn contains_word(haystack: &str, needle: &str) -> bool {
+    let haystack = haystack.to_lowercase();
+    let needle = needle.trim().to_lowercase();
+    if needle.is_empty() {
+        return false;
+    }
+
+    haystack.match_indices(&needle).any(|(start, _)| {
+        let before = haystack[..start].chars().next_back();
+        let after = haystack[start + needle.len()..].chars().next();
+        !before.is_some_and(char::is_alphanumeric) && !after.is_some_and(char::is_alphanumeric)
+    })
+}
+
+/// Returns the position of the question whose ID is `id` or starts with it.
+///
+/// # Returns
+///
+/// The question's group and index, or an error if no question or more than one matches.
+pub fn find_question(exercises: &[Exercise], id: &str) -> Result<(usize, usize), String> {
+    let matches: Vec<(usize, usize)> = exercises
+        .iter()
+        .enumerate()
+        .flat_map(|(g, e)| {
+            e.questions()
+                .into_iter()
+                .enumerate()
+                .filter(|(_, q)| q.meta().id.starts_with(id))
+                .map(move |(q, _)| (g, q))
+                .collect::<Vec<_>>()
+        })
+        .collect();
+
+    match matches.as_slice() {
+        [] => Err(format!("No question has the ID {}", id)),
+        [position] => Ok(*position),
+        _ => Err(format!(
+            "{} questions have IDs starting with {}, give more of the ID",
+            matches.len(),
+            id
+        )),
+    }
+}
+
+/// Returns every question connected to the one at `start` through links, in either
+/// direction, starting with `start` itself and ordered by distance from it.
+pub fn related_questions(exercises: &[Exercise], start: (usize, usize)) -> Vec<(usize, usize)> {
+    let id_of = |(g, q): (usize, usize)| exercises[g].questions()[q].meta().id.clone();
+    let mut found = vec![start];
+    let mut next = 0;
+
+    while let Some(&position) = found.get(next) {
+        next += 1;
+        let id = id_of(position);
+        let linked = exercises[position.0].questions()[position.1]
+            .meta()
+            .links
+            .clone();
+        for (g, exercise) in exercises.iter().enumerate() {
+            for (q, question) in exercise.questions().iter().enumerate() {
+                let meta = question.meta();
+                let connected = linked.contains(&meta.id) || meta.links.contains(&id);
+                if connected && !found.contains(&(g, q)) {
+                    found.push((g, q));
+                }
+            }
+        }
+    }
+    found
+}
+
+impl Exercise {
+    /// Returns the number of questions in this exercise group.
+    pub fn question_count(&self) -> usize {
+        match self {
+            Exercise::Matching(items) => items.len(),
+            Exercise::YesNo(items) => items.len(),
+            Exercise::Recall(items) => items.len(),
+            Exercise::Mcq(items) => items.len(),
+            Exercise::McqMulti(items) => items.len(),
+            Exercise::RecognizeRoot(items) => items.len(),
+            Exercise::FillInTheBlank(items) => items.len(),
+            Exercise::SameOrOpposite(items) => items.len(),
+            Exercise::Ordering(items) => items.len(),
+            Exercise::Cloze(items) => items.len(),
+            Exercise::Etymology(items) => items.len(),
+            Exercise::UseInSentence(items) => items.len(),
+            Exercise::Spelling(items) => items.len(),
+            Exercise::MatchingSet(items) => items.len(),
+            Exercise::OddOneOut(items) => items.len(),
+            Exercise::Analogy(items) => items.len(),
+            Exercise::Custom(items) => items.len(),
+        }
+    }
+
+    /// Returns every question in this group.
+    pub fn questions(&self) -> Vec<&dyn Question> {
+        match self {
+            Exercise::Matching(items) => as_questions(items),
+            Exercise::YesNo(items) => as_questions(items),
+            Exercise::Recall(items) => as_questions(items),
+            Exercise::Mcq(items) => as_questions(items),
+            Exercise::McqMulti(items) => as_questions(items),
+            Exercise::RecognizeRoot(items) => as_questions(items),
+            Exercise::FillInTheBlank(items) => as_questions(items),
+            Exercise::SameOrOpposite(items) => as_questions(items),
+            Exercise::Ordering(items) => as_questions(items),
+            Exercise::Cloze(items) => as_questions(items),
+            Exercise::Etymology(items) => as_questions(items),
+            Exercise::UseInSentence(items) => as_questions(items),
+            Exercise::Spelling(items) => as_questions(items),
+            Exercise::MatchingSet(items) => as_questions(items),
+            Exercise::OddOneOut(items) => as_questions(items),
+            Exercise::Analogy(items) => as_questions(items),
+            Exercise::Custom(items) => as_questions(items),
+        }
+    }
+
+    /// Keeps only the questions for which `keep` returns `true`.
+    pub fn retain_questions(&mut self, mut keep: impl FnMut(&dyn Question) -> bool) {
+        match self {
+            Exercise::Matching(items) => items.retain(|q| keep(q)),
+            Exercise::YesNo(items) => items.retain(|q| keep(q)),
+            Exercise::Recall(items) => items.retain(|q| keep(q)),
+            Exercise::Mcq(items) => items.retain(|q| keep(q)),
+            Exercise::McqMulti(items) => items.retain(|q| keep(q)),
+            Exercise::RecognizeRoot(items) => items.retain(|q| keep(q)),
+            Exercise::FillInTheBlank(items) => items.retain(|q| keep(q)),
+            Exercise::SameOrOpposite(items) => items.retain(|q| keep(q)),
+            Exercise::Ordering(items) => items.retain(|q| keep(q)),
+            Exercise::Cloze(items) => items.retain(|q| keep(q)),
+            Exercise::Etymology(items) => items.retain(|q| keep(q)),
+            Exercise::UseInSentence(items) => items.retain(|q| keep(q)),
+            Exercise::Spelling(items) => items.retain(|q| keep(q)),
+            Exercise::MatchingSet(items) => items.retain(|q| keep(q)),
+            Exercise::OddOneOut(items) => items.retain(|q| keep(q)),
+            Exercise::Analogy(items) => items.retain(|q| keep(q)),
+            Exercise::Custom(items) => items.retain(|q| keep(q)),
+        }
+    }
+
+    /// Returns every question in this group for modification.
+    pub fn questions_mut(&mut self) -> Vec<&mut dyn Question> {
+        match self {
+            Exercise::Matching(items) => as_questions_mut(items),
+            Exercise::YesNo(items) => as_questions_mut(items),
+            Exercise::Recall(items) => as_questions_mut(items),
+            Exercise::Mcq(items) => as_questions_mut(items),
+            Exercise::McqMulti(items) => as_questions_mut(items),
+            Exercise::RecognizeRoot(items) => as_questions_mut(items),
+            Exercise::FillInTheBlank(items) => as_questions_mut(items),
+            Exercise::SameOrOpposite(items) => as_questions_mut(items),
+            Exercise::Ordering(items) => as_questions_mut(items),
+            Exercise::Cloze(items) => as_questions_mut(items),
+            Exercise::Etymology(items) => as_questions_mut(items),
+            Exercise::UseInSentence(items) => as_questions_mut(items),
+            Exercise::Spelling(items) => as_questions_mut(items),
+            Exercise::MatchingSet(items) => as_questions_mut(items),
+            Exercise::OddOneOut(items) => as_questions_mut(items),
+            Exercise::Analogy(items) => as_questions_mut(items),
+            Exercise::Custom(items) => as_questions_mut(items),
+        }
+    }
+
+    /// Returns one small example group for every exercise type.
+    ///
+    /// These are used by `--preview` to show what each type looks like without touching
+    /// any stored data.
+    pub fn examples() -> Vec<Exercise> {
+        vec![
+            Exercise::Matching(vec![Matching::new(
+                "egoist".to_string(),
+                "believes in self advancement".to_string(),
+            )]),
+            Exercise::YesNo(vec![YesNo::new("Is an egoist selfish?".to_string(), true)]),
+            Exercise::Recall(vec![Recall::new(
+                "one's other self".to_string(),
+                vec!["alter ego".to_string()],
+                false,
+            )]),
+            Exercise::Mcq(vec![Mcq::new(
+                "Puts selfish desires first".to_string(),
+                "egoist".to_string(),
+                vec![
+                    "egoist".to_string(),
+                    "egotist".to_string(),
+                    "altruist".to_string(),
+                ],
+                vec![
+                    None,
+                    Some(
+                        "An egotist boasts about accomplishments but isn't necessarily selfish"
+                            .to_string(),
+                    ),
+                    Some("An altruist puts others first".to_string()),
+                ],
+            )]),
+            Exercise::McqMulti(vec![McqMulti::new(
+                "Which words describe a focus on oneself?".to_string(),
+                vec!["egoist".to_string(), "egotist".to_string()],
+                vec![
+                    "egoist".to_string(),
+                    "egotist".to_string(),
+                    "altruist".to_string(),
+                ],
+            )]),
+            Exercise::RecognizeRoot(vec![RecognizeRoot::new(
+                "ego".to_string(),
+                "self".to_string(),
+                "egoist".to_string(),
+            )]),
+            Exercise::FillInTheBlank(vec![FillInTheBlank::new(
+                "This person has gambled for as long as anyone can remember.".to_string(),
+                "inveterate".to_string(),
+                "An _ gambler".to_string(),
+            )]),
+            Exercise::SameOrOpposite(vec![SameOrOpposite::new(
+                "egoistic".to_string(),
+                "altruistic".to_string(),
+                false,
+            )]),
+            Exercise::Ordering(vec![Ordering::new(
+                "Order by intensity, mildest first".to_string(),
+                vec![
+                    "warm".to_string(),
+                    "hot".to_string(),
+                    "scalding".to_string(),
+                ],
+            )]),
+            Exercise::Cloze(vec![Cloze::new(
+                "An {{1}} talks about himself; an {{2}} thinks only of himself.".to_string(),
+                vec!["egotist".to_string(), "egoist".to_string()],
+            )]),
+            Exercise::Etymology(vec![Etymology::new(
+                "egocentric".to_string(),
+                vec!["ego".to_string(), "centrum".to_string()],
+                "Latin".to_string(),
+                "I, center".to_string(),
+            )]),
+            Exercise::UseInSentence(vec![UseInSentence::new(
+                "altruistic".to_string(),
+                "Her altruistic gift paid for the whole village's well.".to_string(),
+            )]),
+            Exercise::Spelling(vec![Spelling::new(
+                "Talks constantly about oneself".to_string(),
+                "egotist".to_string(),
+            )]),
+            Exercise::MatchingSet(vec![MatchingSet::new(
+                "Match each word with its meaning".to_string(),
+                vec![
+                    "egoist".to_string(),
+                    "egotist".to_string(),
+                    "altruist".to_string(),
+                ],
+                vec![
+                    "puts others first".to_string(),
+                    "puts self first".to_string(),
+                    "boasts about oneself".to_string(),
+                ],
+                vec![1, 2, 0],
+            )]),
+            Exercise::OddOneOut(vec![{
+                let mut odd = OddOneOut::new(
+                    vec![
+                        "egoist".to_string(),
+                        "egotist".to_string(),
+                        "altruist".to_string(),
+                        "egocentric".to_string(),
+                    ],
+                    "altruist".to_string(),
+                );
+                odd.meta.explanation = Some("The others are all about oneself".to_string());
+                odd
+            }]),
+            Exercise::Analogy(vec![Analogy::new(
+                [
+                    "egoist".to_string(),
+                    "self".to_string(),
+                    "altruist".to_string(),
+                ],
+                "others".to_string(),
+                vec!["money".to_string(), "fame".to_string()],
+            )]),
+            Exercise::Custom(vec![Custom::new(
+                "synonym".to_string(),
+                BTreeMap::from([
+                    ("word".to_string(), "egoistic".to_string()),
+                    ("synonym".to_string(), "selfish".to_string()),
+                ]),
+            )]),
+        ]
+    }
+    /// Returns `examples` with every metadata field of every question filled in, plus a
+    /// group of questions made of `AWKWARD_TEXT`, for testing that the file formats keep
+    /// a deck exactly as it was.
+    #[cfg(test)]
+    pub fn examples_with_meta() -> Vec<Exercise> {
+        use crate::history::Confidence;
+        use crate::scheduler::Grade;
+
+        let mut exercises = Exercise::examples();
+        exercises.push(Exercise::YesNo(
+            AWKWARD_TEXT
+                .iter()
+                .map(|text| YesNo::new(text.to_string(), false))
+                .collect(),
+        ));
+        // Template fields are the only keys that aren't fixed names.
+        exercises.push(Exercise::Custom(vec![Custom::new(
+            "awkward".to_string(),
+            AWKWARD_TEXT
+                .iter()
+                .map(|text| (text.to_string(), text.to_string()))
+                .collect(),
+        )]));
+        let questions = exercises.iter_mut().flat_map(|e| e.questions_mut());
+        for (i, question) in questions.enumerate() {
+            *question.meta_mut() = Meta {
+                id: format!("{:016x}", i),
+                created: Some(1_700_000_000 + i as u64),
+                modified: Some(1_800_000_000),
+                source: Some("Word Power Made Easy: p. 12 # chapter 1".to_string()),
+                tags: AWKWARD_TEXT.iter().map(|t| t.to_string()).collect(),
+                chapter: Some("3".to_string()),
+                explanation: Some("First line\n  indented: second line\n\nlast".to_string()),
+                pronunciation: Some("ˈiːɡəʊɪst".to_string()),
+                mnemonic: Some("- ego, \"I\" in Latin".to_string()),
+                links: vec!["0000000000000000".to_string(), "null".to_string()],
+                suspended: i % 2 == 0,
+                starred: i % 3 == 0,
+                media: Some("images/ego ist.png".to_string()),
+                difficulty: Some(Difficulty::Hard),
+                schedule: Some(Schedule {
+                    ease: 2.36,
+                    interval: 6,
+                    repetitions: 2,
+                    due: "2026-10-20".parse().unwrap(),
+                    leitner_box: 3,
+                }),
+                history: vec![
+                    Attempt {
+                        time: 1_750_000_000,
+                        correct: false,
+                        millis: 10_250,
+                        confidence: Some(Confidence::Guess),
+                        scheduled: false,
+                        grade: None,
+                    },
+                    Attempt {
+                        time: 1_750_000_100,
+                        correct: true,
+                        millis: 900,
+                        confidence: None,
+                        scheduled: true,
+                        grade: Some(Grade::Easy),
+                    },
+                ],
+            };
+        }
+        exercises
+    }
 }
 
 #[derive(Serialize, Deserialize)]
 pub struct Matching {
     question: String,
     answer: String,
+    #[serde(flatten)]
+    meta: Meta,
 }
 
 impl Matching {
     pub fn new(question: String, answer: String) -> Matching {
-        Matching { question, answer }
+        Matching {
+            question,
+            answer,
+            meta: Meta::default(),
+        }
+    }
+
+    pub fn answer(&self) -> &str {
+        &self.answer
     }
 }
 
@@ -28,24 +646,129 @@ impl Matching {
 pub struct YesNo {
     question: String,
     answer: bool,
+    #[serde(flatten)]
+    meta: Meta,
 }
 
 impl YesNo {
     pub fn new(question: String, answer: bool) -> YesNo {
-        YesNo { question, answer }
+        YesNo {
+            question,
+            answer,
+            meta: Meta::default(),
+        }
+    }
+
+    pub fn answer(&self) -> bool {
+        self.answer
     }
 }
 
+/// Reads either a single string or a list of strings, for fields that used to hold one.
+fn one_or_many<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<String>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(String),
+        Many(Vec<String>),
+    }
+
+    Ok(match OneOrMany::deserialize(deserializer)? {
+        OneOrMany::One(answer) => vec![answer],
+        OneOrMany::Many(answers) => answers,
+    })
+}
+
+/// Reads the accepted answers of a `Recall` question, rejecting any pattern that doesn't
+/// compile so a bad one is reported when the deck is loaded rather than mid-quiz.
+fn accepted_answers<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<String>, D::Error> {
+    let answers = one_or_many(deserializer)?;
+    for answer in &answers {
+        Pattern::parse(answer).map_err(D::Error::custom)?;
+    }
+    Ok(answers)
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct Recall {
     question: String,
-    answer: String,
+    /// Every accepted answer, the first one being the one shown. Answers starting with `re:`
+    /// or `glob:` are patterns, see `Pattern`. Older data files store a single `answer`
+    /// string.
+    #[serde(alias = "answer", deserialize_with = "accepted_answers")]
+    answers: Vec<String>,
+    /// Whether a typed answer must match the capitalization of `answers`.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    case_sensitive: bool,
+    #[serde(flatten)]
+    meta: Meta,
 }
 
 impl Recall {
-    pub fn new(question: String, answer: String) -> Recall {
-        Recall { question, answer }
+    pub fn new(question: String, answers: Vec<String>, case_sensitive: bool) -> Recall {
+        Recall {
+            question,
+            answers,
+            case_sensitive,
+            meta: Meta::default(),
+        }
+    }
+
+    pub fn question(&self) -> &str {
+        &self.question
+    }
+
+    /// Returns the main answer, the one shown after a wrong reply: the first plain answer,
+    /// or the first pattern if every answer is one.
+    pub fn answer(&self) -> &str {
+        self.answers
+            .iter()
+            .find(|a| matches!(Pattern::parse(a), Ok(None)))
+            .or(self.answers.first())
+            .map(String::as_str)
+            .unwrap_or("")
     }
+
+    pub fn answers(&self) -> &[String] {
+        &self.answers
+    }
+
+    pub fn is_case_sensitive(&self) -> bool {
+        self.case_sensitive
+    }
+
+    /// Checks a typed answer against each accepted one, folding case unless the card is
+    /// case-sensitive. Surrounding whitespace is ignored either way.
+    pub fn accepts(&self, typed: &str) -> bool {
+        let typed = typed.trim();
+        self.answers
+            .iter()
+            .any(|answer| match Pattern::parse(answer) {
+                Ok(Some(pattern)) => pattern.is_match(typed, !self.case_sensitive),
+                _ if self.case_sensitive => typed == answer.trim(),
+                _ => typed.to_lowercase() == answer.trim().to_lowercase(),
+            })
+    }
+}
+
+/// Writes per-option feedback with a blank string for options without any, since some
+/// file formats, like TOML, can't hold a null in a list.
+fn write_feedback<S: Serializer>(
+    feedback: &[Option<String>],
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_seq(feedback.iter().map(|f| f.as_deref().unwrap_or_default()))
+}
+
+/// Reads per-option feedback, where both a blank string and null mean no feedback.
+fn read_feedback<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<Option<String>>, D::Error> {
+    let feedback = Vec::<Option<String>>::deserialize(deserializer)?;
+    Ok(feedback
+        .into_iter()
+        .map(|f| f.filter(|f| !f.is_empty()))
+        .collect())
 }
 
 #[derive(Serialize, Deserialize)]
@@ -53,16 +776,81 @@ pub struct Mcq {
     question: String,
     answer: String,
     options: Vec<String>,
+    /// Explanations shown when the matching option is picked, parallel to `options`.
+    #[serde(
+        default,
+        skip_serializing_if = "Vec::is_empty",
+        serialize_with = "write_feedback",
+        deserialize_with = "read_feedback"
+    )]
+    feedback: Vec<Option<String>>,
+    #[serde(flatten)]
+    meta: Meta,
 }
 
 impl Mcq {
-    pub fn new(question: String, answer: String, options: Vec<String>) -> Mcq {
+    pub fn new(
+        question: String,
+        answer: String,
+        options: Vec<String>,
+        feedback: Vec<Option<String>>,
+    ) -> Mcq {
+        // Don't store a list of blanks when no option has any feedback.
+        let feedback = if feedback.iter().all(Option::is_none) {
+            Vec::new()
+        } else {
+            feedback
+        };
+
         Mcq {
             question,
             answer,
             options,
+            feedback,
+            meta: Meta::default(),
         }
     }
+
+    pub fn answer(&self) -> &str {
+        &self.answer
+    }
+
+    pub fn options(&self) -> &[String] {
+        &self.options
+    }
+
+    pub fn feedback(&self) -> &[Option<String>] {
+        &self.feedback
+    }
+}
+
+/// A multiple choice question with more than one correct option ("select all that apply").
+#[derive(Serialize, Deserialize)]
+pub struct McqMulti {
+    question: String,
+    answers: Vec<String>,
+    options: Vec<String>,
+    #[serde(flatten)]
+    meta: Meta,
+}
+
+impl McqMulti {
+    pub fn new(question: String, answers: Vec<String>, options: Vec<String>) -> McqMulti {
+        McqMulti {
+            question,
+            answers,
+            options,
+            meta: Meta::default(),
+        }
+    }
+
+    pub fn answers(&self) -> &[String] {
+        &self.answers
+    }
+
+    pub fn options(&self) -> &[String] {
+        &self.options
+    }
 }
 
 #[derive(Serialize, Deserialize)]
@@ -70,6 +858,8 @@ pub struct RecognizeRoot {
     question: String,
     answer: String,
     example: String,
+    #[serde(flatten)]
+    meta: Meta,
 }
 
 impl RecognizeRoot {
@@ -78,8 +868,17 @@ impl RecognizeRoot {
             question,
             answer,
             example,
+            meta: Meta::default(),
         }
     }
+
+    pub fn answer(&self) -> &str {
+        &self.answer
+    }
+
+    pub fn example(&self) -> &str {
+        &self.example
+    }
 }
 
 #[derive(Serialize, Deserialize)]
@@ -87,6 +886,8 @@ pub struct FillInTheBlank {
     question: String,
     answer: String,
     blank: String,
+    #[serde(flatten)]
+    meta: Meta,
 }
 
 impl FillInTheBlank {
@@ -95,8 +896,17 @@ impl FillInTheBlank {
             question,
             answer,
             blank,
+            meta: Meta::default(),
         }
     }
+
+    pub fn answer(&self) -> &str {
+        &self.answer
+    }
+
+    pub fn blank(&self) -> &str {
+        &self.blank
+    }
 }
 
 #[derive(Serialize, Deserialize)]
@@ -104,6 +914,8 @@ pub struct SameOrOpposite {
     first_word: String,
     second_word: String,
     answer: bool,
+    #[serde(flatten)]
+    meta: Meta,
 }
 
 impl SameOrOpposite {
@@ -112,6 +924,904 @@ impl SameOrOpposite {
             first_word,
             second_word,
             answer,
+            meta: Meta::default(),
+        }
+    }
+
+    pub fn first_word(&self) -> &str {
+        &self.first_word
+    }
+
+    pub fn second_word(&self) -> &str {
+        &self.second_word
+    }
+
+    /// Returns `true` if the two words mean the same, `false` if they are opposites.
+    pub fn answer(&self) -> bool {
+        self.answer
+    }
+}
+
+/// A set of items to be put in sequence, e.g. words by intensity.
+#[derive(Serialize, Deserialize)]
+pub struct Ordering {
+    question: String,
+    /// The items in their correct order.
+    items: Vec<String>,
+    #[serde(flatten)]
+    meta: Meta,
+}
+
+impl Ordering {
+    pub fn new(question: String, items: Vec<String>) -> Ordering {
+        Ordering {
+            question,
+            items,
+            meta: Meta::default(),
+        }
+    }
+
+    pub fn items(&self) -> &[String] {
+        &self.items
+    }
+}
+
+/// A text with several numbered blanks, written `{{1}}`, `{{2}}` and so on.
+#[derive(Serialize, Deserialize)]
+pub struct Cloze {
+    text: String,
+    /// The answer for each blank, the first one filling `{{1}}`.
+    answers: Vec<String>,
+    #[serde(flatten)]
+    meta: Meta,
+}
+
+impl Cloze {
+    pub fn new(text: String, answers: Vec<String>) -> Cloze {
+        Cloze {
+            text,
+            answers,
+            meta: Meta::default(),
+        }
+    }
+
+    pub fn answers(&self) -> &[String] {
+        &self.answers
+    }
+
+    /// Counts the blanks in a cloze text.
+    ///
+    /// # Returns
+    ///
+    /// The number of blanks, or an error if there are none, a placeholder is malformed or
+    /// the numbers skip one (`{{1}}` and `{{3}}` without `{{2}}`). A blank may appear more
+    /// than once.
+    pub fn count_blanks(text: &str) -> Result<usize, String> {
+        let mut numbers = Vec::new();
+        let mut rest = text;
+        while let Some(start) = rest.find("{{") {
+            let after = &rest[start + 2..];
+            let end = after
+                .find("}}")
+                .ok_or_else(|| format!("Unclosed blank in '{}'", &rest[start..]))?;
+            let number: usize = after[..end].trim().parse().map_err(|_| {
+                format!("Expected a blank number, found '{{{{{}}}}}'", &after[..end])
+            })?;
+            numbers.push(number);
+            rest = &after[end + 2..];
+        }
+
+        let count = numbers.iter().copied().max().unwrap_or(0);
+        if count == 0 {
+            return Err("Mark each blank with {{1}}, {{2}} and so on".to_string());
+        }
+        match (1..=count).find(|n| !numbers.contains(n)) {
+            Some(missing) => Err(format!("Blank {{{{{}}}}} is missing", missing)),
+            None => Ok(count),
+        }
+    }
+
+    /// Returns the text with every blank replaced by `blank(number)`, numbers starting at 1.
+    pub fn fill(&self, blank: impl Fn(usize) -> String) -> String {
+        (1..=self.answers.len()).fold(self.text.clone(), |text, n| {
+            text.replace(&format!("{{{{{}}}}}", n), &blank(n))
+        })
+    }
+}
+
+/// A word broken into its roots, asking for what the roots literally mean.
+#[derive(Serialize, Deserialize)]
+pub struct Etymology {
+    word: String,
+    roots: Vec<String>,
+    /// The language the roots come from, e.g. Latin.
+    language: String,
+    /// The literal meaning of the roots, e.g. "I, center" for egocentric.
+    meaning: String,
+    #[serde(flatten)]
+    meta: Meta,
+}
+
+impl Etymology {
+    pub fn new(word: String, roots: Vec<String>, language: String, meaning: String) -> Etymology {
+        Etymology {
+            word,
+            roots,
+            language,
+            meaning,
+            meta: Meta::default(),
+        }
+    }
+
+    pub fn roots(&self) -> &[String] {
+        &self.roots
+    }
+
+    pub fn language(&self) -> &str {
+        &self.language
+    }
+
+    pub fn meaning(&self) -> &str {
+        &self.meaning
+    }
+}
+
+/// A word to be used in a sentence of the learner's own, graded by the learner against a
+/// model sentence.
+#[derive(Serialize, Deserialize)]
+pub struct UseInSentence {
+    word: String,
+    /// An example of the word used correctly.
+    model: String,
+    #[serde(flatten)]
+    meta: Meta,
+}
+
+impl UseInSentence {
+    pub fn new(word: String, model: String) -> UseInSentence {
+        UseInSentence {
+            word,
+            model,
+            meta: Meta::default(),
+        }
+    }
+
+    pub fn model(&self) -> &str {
+        &self.model
+    }
+}
+
+/// A definition to which the learner types the word, spelled exactly.
+#[derive(Serialize, Deserialize)]
+pub struct Spelling {
+    definition: String,
+    word: String,
+    #[serde(flatten)]
+    meta: Meta,
+}
+
+impl Spelling {
+    pub fn new(definition: String, word: String) -> Spelling {
+        Spelling {
+            definition,
+            word,
+            meta: Meta::default(),
+        }
+    }
+
+    pub fn word(&self) -> &str {
+        &self.word
+    }
+}
+
+/// A whole matching exercise: a left column, a right column and which items belong
+/// together. The right column may have more items than the left, as distractors.
+#[derive(Serialize, Deserialize)]
+pub struct MatchingSet {
+    question: String,
+    left: Vec<String>,
+    right: Vec<String>,
+    /// The index into `right` of the match for each item of `left`.
+    pairs: Vec<usize>,
+    #[serde(flatten)]
+    meta: Meta,
+}
+
+impl MatchingSet {
+    pub fn new(
+        question: String,
+        left: Vec<String>,
+        right: Vec<String>,
+        pairs: Vec<usize>,
+    ) -> MatchingSet {
+        MatchingSet {
+            question,
+            left,
+            right,
+            pairs,
+            meta: Meta::default(),
+        }
+    }
+
+    pub fn left(&self) -> &[String] {
+        &self.left
+    }
+
+    pub fn right(&self) -> &[String] {
+        &self.right
+    }
+
+    /// Returns the right-hand item matching `left[i]`.
+    pub fn match_for(&self, i: usize) -> &str {
+        self.pairs
+            .get(i)
+            .and_then(|&j| self.right.get(j))
+            .map(String::as_str)
+            .unwrap_or("")
+    }
+}
+
+/// A list of words where one doesn't belong. Why it doesn't is kept as the question's
+/// explanation.
+#[derive(Serialize, Deserialize)]
+pub struct OddOneOut {
+    words: Vec<String>,
+    /// The word that doesn't belong, one of `words`.
+    answer: String,
+    #[serde(flatten)]
+    meta: Meta,
+}
+
+impl OddOneOut {
+    pub fn new(words: Vec<String>, answer: String) -> OddOneOut {
+        OddOneOut {
+            words,
+            answer,
+            meta: Meta::default(),
         }
     }
+
+    pub fn words(&self) -> &[String] {
+        &self.words
+    }
+
+    pub fn answer(&self) -> &str {
+        &self.answer
+    }
+}
+
+/// A verbal analogy, "A is to B as C is to ?".
+///
+/// With distractors the answer is picked from them as a multiple choice question, without
+/// it is typed.
+#[derive(Serialize, Deserialize)]
+pub struct Analogy {
+    /// The given terms A, B and C.
+    terms: [String; 3],
+    answer: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    distractors: Vec<String>,
+    #[serde(flatten)]
+    meta: Meta,
+}
+
+impl Analogy {
+    pub fn new(terms: [String; 3], answer: String, distractors: Vec<String>) -> Analogy {
+        Analogy {
+            terms,
+            answer,
+            distractors,
+            meta: Meta::default(),
+        }
+    }
+
+    pub fn answer(&self) -> &str {
+        &self.answer
+    }
+
+    pub fn distractors(&self) -> &[String] {
+        &self.distractors
+    }
+}
+
+/// A question in a user-defined format, see `Template`.
+///
+/// Only the template's name and the values of its fields are stored; the prompt and answer
+/// are produced by filling the template's placeholders with the fields.
+#[derive(Serialize, Deserialize)]
+pub struct Custom {
+    template: String,
+    fields: BTreeMap<String, String>,
+    #[serde(flatten)]
+    meta: Meta,
+}
+
+impl Custom {
+    pub fn new(template: String, fields: BTreeMap<String, String>) -> Custom {
+        Custom {
+            template,
+            fields,
+            meta: Meta::default(),
+        }
+    }
+
+    pub fn template(&self) -> &str {
+        &self.template
+    }
+
+    pub fn fields(&self) -> &BTreeMap<String, String> {
+        &self.fields
+    }
+}
+
+impl Question for Matching {
+    fn text(&self) -> String {
+        self.question.clone()
+    }
+
+    fn meta(&self) -> &Meta {
+        &self.meta
+    }
+
+    fn meta_mut(&mut self) -> &mut Meta {
+        &mut self.meta
+    }
+}
+
+impl Question for YesNo {
+    fn text(&self) -> String {
+        self.question.clone()
+    }
+
+    fn meta(&self) -> &Meta {
+        &self.meta
+    }
+
+    fn meta_mut(&mut self) -> &mut Meta {
+        &mut self.meta
+    }
+}
+
+impl Question for Recall {
+    fn text(&self) -> String {
+        self.question.clone()
+    }
+
+    fn meta(&self) -> &Meta {
+        &self.meta
+    }
+
+    fn meta_mut(&mut self) -> &mut Meta {
+        &mut self.meta
+    }
+
+    fn gives_away_answer(&self) -> bool {
+        self.answers
+            .iter()
+            .any(|a| contains_word(&self.question, a))
+    }
+
+    fn hint(&self, level: usize) -> Option<String> {
+        hint_for(self.answer(), level)
+    }
+}
+
+impl Question for Mcq {
+    fn text(&self) -> String {
+        self.question.clone()
+    }
+
+    fn meta(&self) -> &Meta {
+        &self.meta
+    }
+
+    fn meta_mut(&mut self) -> &mut Meta {
+        &mut self.meta
+    }
+}
+
+impl Question for McqMulti {
+    fn text(&self) -> String {
+        self.question.clone()
+    }
+
+    fn meta(&self) -> &Meta {
+        &self.meta
+    }
+
+    fn meta_mut(&mut self) -> &mut Meta {
+        &mut self.meta
+    }
+}
+
+impl Question for RecognizeRoot {
+    fn text(&self) -> String {
+        self.question.clone()
+    }
+
+    fn meta(&self) -> &Meta {
+        &self.meta
+    }
+
+    fn meta_mut(&mut self) -> &mut Meta {
+        &mut self.meta
+    }
+}
+
+impl Question for FillInTheBlank {
+    fn text(&self) -> String {
+        self.question.clone()
+    }
+
+    fn meta(&self) -> &Meta {
+        &self.meta
+    }
+
+    fn meta_mut(&mut self) -> &mut Meta {
+        &mut self.meta
+    }
+
+    fn gives_away_answer(&self) -> bool {
+        contains_word(&self.question, &self.answer) || contains_word(&self.blank, &self.answer)
+    }
+
+    fn hint(&self, level: usize) -> Option<String> {
+        hint_for(&self.answer, level)
+    }
+}
+
+impl Question for Custom {
+    fn text(&self) -> String {
+        self.fields
+            .values()
+            .cloned()
+            .collect::<Vec<_>>()
+            .join(" / ")
+    }
+
+    fn meta(&self) -> &Meta {
+        &self.meta
+    }
+
+    fn meta_mut(&mut self) -> &mut Meta {
+        &mut self.meta
+    }
+}
+
+impl Question for SameOrOpposite {
+    fn text(&self) -> String {
+        format!("{} / {}", self.first_word, self.second_word)
+    }
+
+    fn meta(&self) -> &Meta {
+        &self.meta
+    }
+
+    fn meta_mut(&mut self) -> &mut Meta {
+        &mut self.meta
+    }
+}
+
+impl Question for Ordering {
+    fn text(&self) -> String {
+        self.question.clone()
+    }
+
+    fn meta(&self) -> &Meta {
+        &self.meta
+    }
+
+    fn meta_mut(&mut self) -> &mut Meta {
+        &mut self.meta
+    }
+}
+
+impl Question for Cloze {
+    fn text(&self) -> String {
+        self.fill(|_| "_".to_string())
+    }
+
+    fn meta(&self) -> &Meta {
+        &self.meta
+    }
+
+    fn meta_mut(&mut self) -> &mut Meta {
+        &mut self.meta
+    }
+
+    fn gives_away_answer(&self) -> bool {
+        let text = self.text();
+        self.answers.iter().any(|a| contains_word(&text, a))
+    }
+}
+
+impl Question for Etymology {
+    fn text(&self) -> String {
+        self.word.clone()
+    }
+
+    fn meta(&self) -> &Meta {
+        &self.meta
+    }
+
+    fn meta_mut(&mut self) -> &mut Meta {
+        &mut self.meta
+    }
+
+    fn hint(&self, level: usize) -> Option<String> {
+        hint_for(&self.meaning, level)
+    }
+}
+
+impl Question for UseInSentence {
+    fn text(&self) -> String {
+        self.word.clone()
+    }
+
+    fn meta(&self) -> &Meta {
+        &self.meta
+    }
+
+    fn meta_mut(&mut self) -> &mut Meta {
+        &mut self.meta
+    }
+}
+
+impl Question for Spelling {
+    fn text(&self) -> String {
+        self.definition.clone()
+    }
+
+    fn meta(&self) -> &Meta {
+        &self.meta
+    }
+
+    fn meta_mut(&mut self) -> &mut Meta {
+        &mut self.meta
+    }
+
+    fn gives_away_answer(&self) -> bool {
+        contains_word(&self.definition, &self.word)
+    }
+}
+
+impl Question for MatchingSet {
+    fn text(&self) -> String {
+        self.question.clone()
+    }
+
+    fn meta(&self) -> &Meta {
+        &self.meta
+    }
+
+    fn meta_mut(&mut self) -> &mut Meta {
+        &mut self.meta
+    }
+}
+
+impl Question for OddOneOut {
+    fn text(&self) -> String {
+        self.words.join(", ")
+    }
+
+    fn meta(&self) -> &Meta {
+        &self.meta
+    }
+
+    fn meta_mut(&mut self) -> &mut Meta {
+        &mut self.meta
+    }
+}
+
+impl Question for Analogy {
+    fn text(&self) -> String {
+        let [a, b, c] = &self.terms;
+        format!("{} is to {} as {} is to ?", a, b, c)
+    }
+
+    fn meta(&self) -> &Meta {
+        &self.meta
+    }
+
+    fn meta_mut(&mut self) -> &mut Meta {
+        &mut self.meta
+    }
+
+    fn gives_away_answer(&self) -> bool {
+        self.distractors.is_empty() && contains_word(&self.text(), &self.answer)
+    }
+
+    fn hint(&self, level: usize) -> Option<String> {
+        hint_for(&self.answer, level)
+    }
+}
+
+/// Converts a slice of concrete questions into trait objects.
+fn as_questions<T: Question>(items: &[T]) -> Vec<&dyn Question> {
+    items.iter().map(|q| q as &dyn Question).collect()
+}
+
+/// Converts a slice of concrete questions into mutable trait objects.
+fn as_questions_mut<T: Question>(items: &mut [T]) -> Vec<&mut dyn Question> {
+    items.iter_mut().map(|q| q as &mut dyn Question).collect()
+}
+
+/// Writes a numbered list of items, one per line, indented under the group header.
+///
+/// Lines after the first line of an item (options, line breaks in the text) are indented
+/// to line up with it. Suspended items are marked as such and starred items with `*`. With the alternate flag (`{:#}`) each item's ID, dates, source,
+/// chapter, notes, media, difficulty, tags and links are shown underneath it.
+fn write_items<T: fmt::Display + Question>(f: &mut fmt::Formatter, items: &[T]) -> fmt::Result {
+    items.iter().enumerate().try_for_each(|(i, item)| {
+        let marker = match (item.meta().suspended, item.meta().starred) {
+            (true, _) => "(suspended) ",
+            (false, true) => "* ",
+            (false, false) => "",
+        };
+        writeln!(
+            f,
+            "  {}. {}{}",
+            i + 1,
+            marker,
+            item.to_string().replace('\n', "\n     ")
+        )?;
+        if !f.alternate() {
+            return Ok(());
+        }
+        writeln!(f, "     id: {}", item.meta().id)?;
+        if let Some(created) = item.meta().created {
+            writeln!(f, "     created: {}", Date::from_unix(created))?;
+        }
+        if let Some(modified) = item.meta().modified {
+            writeln!(f, "     modified: {}", Date::from_unix(modified))?;
+        }
+        if let Some(source) = &item.meta().source {
+            writeln!(f, "     source: {}", md(source))?;
+        }
+        if let Some(chapter) = &item.meta().chapter {
+            writeln!(f, "     chapter: {}", chapter)?;
+        }
+        if let Some(explanation) = &item.meta().explanation {
+            writeln!(f, "     explanation: {}", md(explanation))?;
+        }
+        if let Some(pronunciation) = &item.meta().pronunciation {
+            writeln!(f, "     pronunciation: {}", pronunciation)?;
+        }
+        if let Some(mnemonic) = &item.meta().mnemonic {
+            writeln!(f, "     mnemonic: {}", md(mnemonic))?;
+        }
+        if let Some(media) = &item.meta().media {
+            writeln!(f, "     media: {}", media)?;
+        }
+        if let Some(difficulty) = item.meta().difficulty {
+            writeln!(f, "     difficulty: {}", difficulty)?;
+        }
+        if !item.meta().tags.is_empty() {
+            writeln!(f, "     tags: {}", item.meta().tags.join(", "))?;
+        }
+        if !item.meta().links.is_empty() {
+            writeln!(f, "     see also: {}", item.meta().links.join(", "))?;
+        }
+        Ok(())
+    })
+}
+
+impl fmt::Display for Exercise {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name: &'static str = self.into();
+        writeln!(f, "{}", name)?;
+        match self {
+            Exercise::Matching(items) => write_items(f, items),
+            Exercise::YesNo(items) => write_items(f, items),
+            Exercise::Recall(items) => write_items(f, items),
+            Exercise::Mcq(items) => write_items(f, items),
+            Exercise::McqMulti(items) => write_items(f, items),
+            Exercise::RecognizeRoot(items) => write_items(f, items),
+            Exercise::FillInTheBlank(items) => write_items(f, items),
+            Exercise::SameOrOpposite(items) => write_items(f, items),
+            Exercise::Ordering(items) => write_items(f, items),
+            Exercise::Cloze(items) => write_items(f, items),
+            Exercise::Etymology(items) => write_items(f, items),
+            Exercise::UseInSentence(items) => write_items(f, items),
+            Exercise::Spelling(items) => write_items(f, items),
+            Exercise::MatchingSet(items) => write_items(f, items),
+            Exercise::OddOneOut(items) => write_items(f, items),
+            Exercise::Analogy(items) => write_items(f, items),
+            Exercise::Custom(items) => write_items(f, items),
+        }
+    }
+}
+
+/// Returns the letter an option is shown with, `a` for the first option.
+pub fn option_letter(index: usize) -> char {
+    ('a'..).nth(index).unwrap()
+}
+
+/// Returns the position of the option shown with `letter`, the inverse of `option_letter`.
+pub fn option_index(letter: char) -> Option<usize> {
+    ('a'..).position(|c| c == letter)
+}
+
+/// Writes lettered options, each on its own line.
+fn write_options(f: &mut fmt::Formatter, options: &[String]) -> fmt::Result {
+    options
+        .iter()
+        .enumerate()
+        .try_for_each(|(i, option)| write!(f, "\n({}) {}", option_letter(i), md(option)))
+}
+
+impl fmt::Display for Matching {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} -> {}", md(&self.question), md(&self.answer))
+    }
+}
+
+impl fmt::Display for YesNo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let answer = if self.answer { "yes" } else { "no" };
+        write!(f, "{} ({})", md(&self.question), answer)
+    }
+}
+
+impl fmt::Display for Recall {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} -> {}",
+            md(&self.question),
+            md(&self.answers.join(" / "))
+        )?;
+        if self.case_sensitive {
+            write!(f, " (case-sensitive)")?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Mcq {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} -> {}", md(&self.question), md(&self.answer))?;
+        self.options.iter().enumerate().try_for_each(|(i, option)| {
+            write!(f, "\n({}) {}", option_letter(i), md(option))?;
+            match self.feedback.get(i) {
+                Some(Some(feedback)) => write!(f, " - {}", md(feedback)),
+                _ => Ok(()),
+            }
+        })
+    }
+}
+
+impl fmt::Display for McqMulti {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let answers: Vec<String> = self.answers.iter().map(|a| md(a)).collect();
+        write!(f, "{} -> {}", md(&self.question), answers.join(", "))?;
+        write_options(f, &self.options)
+    }
+}
+
+impl fmt::Display for RecognizeRoot {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} (e.g. {}) -> {}",
+            md(&self.question),
+            md(&self.example),
+            md(&self.answer)
+        )
+    }
+}
+
+impl fmt::Display for FillInTheBlank {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}\n{} -> {}",
+            md(&self.question),
+            md(&self.blank),
+            md(&self.answer)
+        )
+    }
+}
+
+impl fmt::Display for SameOrOpposite {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let answer = if self.answer { "same" } else { "opposite" };
+        write!(
+            f,
+            "{} / {} ({})",
+            md(&self.first_word),
+            md(&self.second_word),
+            answer
+        )
+    }
+}
+
+impl fmt::Display for Ordering {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let items: Vec<String> = self.items.iter().map(|i| md(i)).collect();
+        write!(f, "{} -> {}", md(&self.question), items.join(", "))
+    }
+}
+
+impl fmt::Display for Cloze {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let filled = self.fill(|n| format!("[{}]", self.answers[n - 1]));
+        write!(f, "{}", md(&filled))
+    }
+}
+
+impl fmt::Display for Etymology {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}: {} ({}) -> {}",
+            md(&self.word),
+            self.roots.join(" + "),
+            self.language,
+            md(&self.meaning)
+        )
+    }
+}
+
+impl fmt::Display for UseInSentence {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} -> {}", md(&self.word), md(&self.model))
+    }
+}
+
+impl fmt::Display for Spelling {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} -> {}", md(&self.definition), md(&self.word))
+    }
+}
+
+impl fmt::Display for MatchingSet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", md(&self.question))?;
+        (0..self.left.len()).try_for_each(|i| {
+            write!(
+                f,
+                "\n({}) {} -> {}",
+                option_letter(i),
+                md(&self.left[i]),
+                md(self.match_for(i))
+            )
+        })?;
+        let unused: Vec<String> = (0..self.right.len())
+            .filter(|j| !self.pairs.contains(j))
+            .map(|j| md(&self.right[j]))
+            .collect();
+        if !unused.is_empty() {
+            write!(f, "\nunused: {}", unused.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for OddOneOut {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let words: Vec<String> = self.words.iter().map(|w| md(w)).collect();
+        write!(f, "{} -> {}", words.join(", "), md(&self.answer))
+    }
+}
+
+impl fmt::Display for Analogy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} -> {}", md(&self.text()), md(&self.answer))?;
+        if !self.distractors.is_empty() {
+            write!(f, " (not {})", self.distractors.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Custom {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let fields: Vec<String> = self
+            .fields
+            .iter()
+            .map(|(name, value)| format!("{}={}", name, md(value)))
+            .collect();
+        write!(f, "[{}] {}", self.template, fields.join(", "))
+    }
 }
@@ -0,0 +1,198 @@
+use crate::date::Date;
+use crate::exercise::{Difficulty, Exercise, Question};
+use crate::history::unix_now;
+use crate::rng::Rng;
+use std::collections::HashSet;
+use std::str::FromStr;
+use strum::VariantNames;
+
+/// The keys understood by the `--filter` expression parser.
+const VALID_KEYS: [&str; 6] = ["type", "tag", "chapter", "difficulty", "added", "due"];
+
+/// A single `key=value` condition of a filter expression.
+enum Condition {
+    /// Keeps only exercises of the named variant (matched case-insensitively).
+    Type(String),
+    /// Keeps only questions with the given tag (matched case-insensitively).
+    Tag(String),
+    /// Keeps only questions of the given chapter (matched case-insensitively).
+    Chapter(String),
+    /// Keeps only questions rated with the given difficulty.
+    Difficulty(Difficulty),
+    /// Keeps only questions entered within the given number of days. Questions from before
+    /// creation times were recorded never match.
+    Added(u64),
+    /// Keeps only questions due for review within the given number of days, counting
+    /// questions never reviewed as due.
+    Due(i64),
+}
+
+impl Condition {
+    /// Checks whether a question of an exercise group satisfies this condition.
+    fn matches(&self, exercise: &Exercise, question: &dyn Question) -> bool {
+        match self {
+            Condition::Type(name) => {
+                let variant: &'static str = exercise.into();
+                variant.eq_ignore_ascii_case(name)
+            }
+            Condition::Tag(tag) => question
+                .meta()
+                .tags
+                .iter()
+                .any(|t| t.eq_ignore_ascii_case(tag)),
+            Condition::Chapter(chapter) => question
+                .meta()
+                .chapter
+                .as_ref()
+                .is_some_and(|c| c.eq_ignore_ascii_case(chapter)),
+            Condition::Difficulty(difficulty) => question.meta().difficulty == Some(*difficulty),
+            Condition::Added(days) => {
+                let since = unix_now().saturating_sub(days * 86_400);
+                question.meta().created.is_some_and(|c| c >= since)
+            }
+            Condition::Due(days) => {
+                let by = Date::today().add_days(*days);
+                question
+                    .meta()
+                    .schedule
+                    .as_ref()
+                    .is_none_or(|s| s.is_due(by))
+            }
+        }
+    }
+}
+
+/// A parsed `--filter` expression such as `type=mcq`, `tag=roots`, `chapter=3`, `added=7`
+/// (entered in the last 7 days) or `due=0` (due today).
+///
+/// Conditions are separated by commas and combined with AND semantics, so an exercise
+/// is kept only when it satisfies every condition.
+pub struct Filter {
+    conditions: Vec<Condition>,
+}
+
+impl Filter {
+    /// Checks whether a question of an exercise group satisfies every condition of the
+    /// filter.
+    pub fn matches(&self, exercise: &Exercise, question: &dyn Question) -> bool {
+        self.conditions
+            .iter()
+            .all(|c| c.matches(exercise, question))
+    }
+
+    /// Applies the filter to a loaded deck.
+    ///
+    /// # Arguments
+    ///
+    /// * `exercises` - The exercises to filter.
+    ///
+    /// # Returns
+    ///
+    /// The questions that satisfy every condition of the filter, still in their groups;
+    /// groups left without any question are dropped.
+    pub fn apply(&self, exercises: Vec<Exercise>) -> Vec<Exercise> {
+        exercises
+            .into_iter()
+            .filter_map(|mut exercise| {
+                let keep: Vec<bool> = exercise
+                    .questions()
+                    .into_iter()
+                    .map(|q| self.matches(&exercise, q))
+                    .collect();
+                let mut keep = keep.into_iter();
+                exercise.retain_questions(|_| keep.next().unwrap_or(false));
+                (exercise.question_count() > 0).then_some(exercise)
+            })
+            .collect()
+    }
+}
+
+impl FromStr for Filter {
+    type Err = String;
+
+    fn from_str(expr: &str) -> Result<Self, Self::Err> {
+        let conditions = expr
+            .split(',')
+            .map(str::trim)
+            .filter(|part| !part.is_empty())
+            .map(|part| {
+                let (key, value) = part
+                    .split_once('=')
+                    .ok_or_else(|| format!("Expected key=value in filter, found '{}'", part))?;
+                let value = value.trim();
+
+                match key.trim().to_lowercase().as_str() {
+                    "type" => {
+                        if !Exercise::VARIANTS
+                            .iter()
+                            .any(|v| v.eq_ignore_ascii_case(value))
+                        {
+                            return Err(format!(
+                                "Unknown exercise type '{}' (valid types: {})",
+                                value,
+                                Exercise::VARIANTS.join(", ")
+                            ));
+                        }
+                        Ok(Condition::Type(value.to_string()))
+                    }
+                    "tag" => Ok(Condition::Tag(value.to_string())),
+                    "chapter" => Ok(Condition::Chapter(value.to_string())),
+                    "difficulty" => Ok(Condition::Difficulty(value.parse()?)),
+                    "added" => value.parse().map(Condition::Added).map_err(|_| {
+                        format!("Expected a number of days for added, found '{}'", value)
+                    }),
+                    "due" => value.parse().map(Condition::Due).map_err(|_| {
+                        format!("Expected a number of days for due, found '{}'", value)
+                    }),
+                    other => Err(format!(
+                        "Unknown filter key '{}' (valid keys: {})",
+                        other,
+                        VALID_KEYS.join(", ")
+                    )),
+                }
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Filter { conditions })
+    }
+}
+
+/// Picks `n` questions at random from a deck.
+///
+/// The chosen questions keep their exercise groups so they can be shown like the rest of
+/// the deck; groups left without any chosen question are dropped. If `n` exceeds the
+/// number of questions, the whole deck is returned.
+///
+/// # Arguments
+///
+/// * `exercises` - The deck to sample from.
+/// * `n` - How many questions to pick.
+/// * `rng` - The random number generator used to pick the questions.
+pub fn sample(exercises: Vec<Exercise>, n: usize, rng: &mut Rng) -> Vec<Exercise> {
+    // Every question is identified by its group and its position within that group.
+    let mut positions: Vec<(usize, usize)> = exercises
+        .iter()
+        .enumerate()
+        .flat_map(|(g, e)| (0..e.question_count()).map(move |q| (g, q)))
+        .collect();
+    rng.shuffle(&mut positions);
+    let chosen: HashSet<(usize, usize)> = positions.into_iter().take(n).collect();
+    pick(exercises, &chosen)
+}
+
+/// Keeps the questions of a deck at the `chosen` positions, each a group and an index
+/// within it, in their exercise groups; groups left without any question are dropped.
+pub fn pick(exercises: Vec<Exercise>, chosen: &HashSet<(usize, usize)>) -> Vec<Exercise> {
+    exercises
+        .into_iter()
+        .enumerate()
+        .filter_map(|(g, mut exercise)| {
+            let mut q = 0;
+            exercise.retain_questions(|_| {
+                q += 1;
+                chosen.contains(&(g, q - 1))
+            });
+            (exercise.question_count() > 0).then_some(exercise)
+        })
+        .collect()
+}
@@ -0,0 +1,95 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// The file the daily goal is kept in, next to the data file.
+pub const GOAL_FILE: &str = "goal.json";
+
+/// Returns the goal file belonging to `data_file`.
+pub fn goal_file(data_file: &str) -> PathBuf {
+    Path::new(data_file)
+        .parent()
+        .unwrap_or(Path::new(""))
+        .join(GOAL_FILE)
+}
+
+/// How much practice a day should include.
+#[derive(Serialize, Deserialize, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum Goal {
+    /// A number of replies, right or wrong.
+    Replies(u64),
+    /// Minutes spent answering, summed over the replies.
+    Minutes(u64),
+}
+
+impl Goal {
+    /// Loads the goal from `file_path`, or `None` if none has been set.
+    pub fn load(file_path: &Path) -> Result<Option<Goal>, Box<dyn std::error::Error>> {
+        if !file_path.exists() {
+            return Ok(None);
+        }
+        Ok(Some(serde_json::from_str(&fs::read_to_string(file_path)?)?))
+    }
+
+    /// Writes the goal to `file_path`.
+    pub fn save(&self, file_path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        fs::write(file_path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Returns how far a day with `replies` replies taking `millis` in total has come, in
+    /// the goal's own unit.
+    pub fn progress(&self, replies: usize, millis: u64) -> u64 {
+        match self {
+            Goal::Replies(_) => replies as u64,
+            Goal::Minutes(_) => millis / 60_000,
+        }
+    }
+
+    /// Returns the amount the goal asks for, in its own unit.
+    pub fn target(&self) -> u64 {
+        match *self {
+            Goal::Replies(n) | Goal::Minutes(n) => n,
+        }
+    }
+
+    /// Checks whether a day with `replies` replies taking `millis` in total met the goal.
+    pub fn is_met(&self, replies: usize, millis: u64) -> bool {
+        self.progress(replies, millis) >= self.target()
+    }
+}
+
+impl fmt::Display for Goal {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Goal::Replies(n) => write!(f, "{} replies", n),
+            Goal::Minutes(n) => write!(f, "{} minutes", n),
+        }
+    }
+}
+
+impl FromStr for Goal {
+    type Err = String;
+
+    /// Parses `30` as 30 replies and `15m` as 15 minutes.
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            format!(
+                "Expected a number of replies like 30 or of minutes like 15m, found '{}'",
+                text
+            )
+        };
+        let text = text.trim();
+        let goal = match text.strip_suffix('m') {
+            Some(minutes) => Goal::Minutes(minutes.parse().map_err(|_| invalid())?),
+            None => Goal::Replies(text.parse().map_err(|_| invalid())?),
+        };
+        if goal.target() == 0 {
+            return Err(invalid());
+        }
+        Ok(goal)
+    }
+}
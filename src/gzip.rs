@@ -0,0 +1,607 @@
+//! Reading and writing gzip files (RFC 1952) holding a single DEFLATE stream (RFC 1951).
+//!
+//! Compression uses LZ77 matching with the fixed Huffman codes, which is simple and already
+//! shrinks repetitive JSON several times over. Decompression understands every kind of
+//! block, so files compressed by other tools can be read too.
+
+/// The first bytes of every gzip file.
+const MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// The compression method byte for DEFLATE, the only one defined.
+const DEFLATE: u8 = 8;
+
+/// How far back matches may reach.
+const WINDOW: usize = 32 * 1024;
+
+/// The shortest and longest matches DEFLATE can encode.
+const MIN_MATCH: usize = 3;
+const MAX_MATCH: usize = 258;
+
+/// How many earlier positions with the same hash are tried for each match.
+const MAX_CHAIN: usize = 64;
+
+/// The number of bits of the hash of the next three bytes.
+const HASH_BITS: u32 = 15;
+
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+const DIST_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DIST_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+
+/// The order code length code lengths are stored in by dynamic blocks.
+const CODE_LENGTH_ORDER: [usize; 19] = [
+    16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+];
+
+/// Checks whether `bytes` start like a gzip file.
+pub fn is_gzip(bytes: &[u8]) -> bool {
+    bytes.starts_with(&MAGIC)
+}
+
+/// Compresses `data` into a gzip file.
+pub fn compress(data: &[u8]) -> Vec<u8> {
+    // No flags, no modification time, no extra flags, unknown operating system.
+    let mut out = vec![MAGIC[0], MAGIC[1], DEFLATE, 0, 0, 0, 0, 0, 0, 255];
+    let mut writer = BitWriter {
+        out: &mut out,
+        acc: 0,
+        bits: 0,
+    };
+    deflate(data, &mut writer);
+    writer.finish();
+    out.extend_from_slice(&crc32(data).to_le_bytes());
+    out.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    out
+}
+
+/// Decompresses a gzip file.
+///
+/// # Returns
+///
+/// The original data, or an error if the file is not gzip or is damaged.
+pub fn decompress(bytes: &[u8]) -> Result<Vec<u8>, String> {
+    const FHCRC: u8 = 2;
+    const FEXTRA: u8 = 4;
+    const FNAME: u8 = 8;
+    const FCOMMENT: u8 = 16;
+
+    if !is_gzip(bytes) || bytes.len() < 18 {
+        return Err("not a gzip file".to_string());
+    }
+    if bytes[2] != DEFLATE {
+        return Err(format!("unknown gzip compression method {}", bytes[2]));
+    }
+    let flags = bytes[3];
+    let mut pos = 10;
+    let truncated = || "the gzip header is truncated".to_string();
+    if flags & FEXTRA != 0 {
+        let len = bytes
+            .get(pos..pos + 2)
+            .ok_or_else(truncated)
+            .map(|b| usize::from(u16::from_le_bytes([b[0], b[1]])))?;
+        pos += 2 + len;
+    }
+    for flag in [FNAME, FCOMMENT] {
+        if flags & flag != 0 {
+            let end = bytes[pos.min(bytes.len())..]
+                .iter()
+                .position(|&b| b == 0)
+                .ok_or_else(truncated)?;
+            pos += end + 1;
+        }
+    }
+    if flags & FHCRC != 0 {
+        pos += 2;
+    }
+    if pos > bytes.len() {
+        return Err(truncated());
+    }
+
+    let mut reader = BitReader {
+        bytes,
+        pos,
+        acc: 0,
+        bits: 0,
+    };
+    let data = inflate(&mut reader)?;
+    let end = reader.byte_end();
+    let trailer = bytes
+        .get(end..end + 8)
+        .ok_or("the gzip file is truncated")?;
+    let crc = u32::from_le_bytes([trailer[0], trailer[1], trailer[2], trailer[3]]);
+    let size = u32::from_le_bytes([trailer[4], trailer[5], trailer[6], trailer[7]]);
+    if crc != crc32(&data) || size != data.len() as u32 {
+        return Err("the gzip file is damaged (checksum mismatch)".to_string());
+    }
+    Ok(data)
+}
+
+/// Computes the CRC-32 checksum gzip stores for the uncompressed data.
+fn crc32(data: &[u8]) -> u32 {
+    let mut table = [0u32; 256];
+    for (n, entry) in table.iter_mut().enumerate() {
+        let mut c = n as u32;
+        for _ in 0..8 {
+            c = if c & 1 != 0 {
+                0xEDB8_8320 ^ (c >> 1)
+            } else {
+                c >> 1
+            };
+        }
+        *entry = c;
+    }
+    !data.iter().fold(!0u32, |crc, &b| {
+        table[((crc ^ u32::from(b)) & 0xff) as usize] ^ (crc >> 8)
+    })
+}
+
+/// Writes bits least significant first, as DEFLATE stores everything but Huffman codes.
+struct BitWriter<'a> {
+    out: &'a mut Vec<u8>,
+    acc: u64,
+    bits: u32,
+}
+
+impl BitWriter<'_> {
+    fn write(&mut self, value: u32, count: u32) {
+        self.acc |= u64::from(value) << self.bits;
+        self.bits += count;
+        while self.bits >= 8 {
+            self.out.push(self.acc as u8);
+            self.acc >>= 8;
+            self.bits -= 8;
+        }
+    }
+
+    /// Writes a Huffman code, which DEFLATE stores most significant bit first.
+    fn write_code(&mut self, code: u32, len: u32) {
+        self.write(code.reverse_bits() >> (32 - len), len);
+    }
+
+    /// Pads the last byte with zero bits.
+    fn finish(&mut self) {
+        if self.bits > 0 {
+            self.out.push(self.acc as u8);
+        }
+        self.acc = 0;
+        self.bits = 0;
+    }
+}
+
+/// Writes a literal or length symbol with its fixed Huffman code.
+fn write_fixed_symbol(writer: &mut BitWriter, symbol: u32) {
+    match symbol {
+        0..=143 => writer.write_code(0x30 + symbol, 8),
+        144..=255 => writer.write_code(0x190 + symbol - 144, 9),
+        256..=279 => writer.write_code(symbol - 256, 7),
+        _ => writer.write_code(0xc0 + symbol - 280, 8),
+    }
+}
+
+/// Returns the index of the last base in `bases` not above `value`.
+fn code_for(bases: &[u16], value: usize) -> usize {
+    bases
+        .iter()
+        .rposition(|&b| usize::from(b) <= value)
+        .unwrap_or(0)
+}
+
+/// Compresses `data` into a single final block using the fixed Huffman codes.
+fn deflate(data: &[u8], writer: &mut BitWriter) {
+    // BFINAL = 1, BTYPE = 01 (fixed Huffman codes).
+    writer.write(1, 1);
+    writer.write(1, 2);
+
+    // `head` holds the last position whose next three bytes have each hash, and `prev` the
+    // position before that with the same hash, so they chain all earlier candidates.
+    let mut head = vec![usize::MAX; 1 << HASH_BITS];
+    let mut prev = vec![usize::MAX; data.len()];
+    let insert = |i: usize, head: &mut Vec<usize>, prev: &mut Vec<usize>| {
+        if i + MIN_MATCH <= data.len() {
+            let h = hash(data, i);
+            prev[i] = head[h];
+            head[h] = i;
+        }
+    };
+
+    let mut i = 0;
+    while i < data.len() {
+        let mut best_len = 0;
+        let mut best_dist = 0;
+        if i + MIN_MATCH <= data.len() {
+            let max_len = MAX_MATCH.min(data.len() - i);
+            let mut candidate = head[hash(data, i)];
+            let mut chain = 0;
+            while candidate != usize::MAX && i - candidate <= WINDOW && chain < MAX_CHAIN {
+                let len = data[candidate..]
+                    .iter()
+                    .zip(&data[i..i + max_len])
+                    .take_while(|(a, b)| a == b)
+                    .count();
+                if len > best_len {
+                    best_len = len;
+                    best_dist = i - candidate;
+                    if len == max_len {
+                        break;
+                    }
+                }
+                candidate = prev[candidate];
+                chain += 1;
+            }
+        }
+
+        if best_len >= MIN_MATCH {
+            let code = code_for(&LENGTH_BASE, best_len);
+            write_fixed_symbol(writer, 257 + code as u32);
+            writer.write(
+                (best_len - usize::from(LENGTH_BASE[code])) as u32,
+                u32::from(LENGTH_EXTRA[code]),
+            );
+            let code = code_for(&DIST_BASE, best_dist);
+            writer.write_code(code as u32, 5);
+            writer.write(
+                (best_dist - usize::from(DIST_BASE[code])) as u32,
+                u32::from(DIST_EXTRA[code]),
+            );
+            for j in i..i + best_len {
+                insert(j, &mut head, &mut prev);
+            }
+            i += best_len;
+        } else {
+            write_fixed_symbol(writer, u32::from(data[i]));
+            insert(i, &mut head, &mut prev);
+            i += 1;
+        }
+    }
+    write_fixed_symbol(writer, 256);
+}
+
+/// Hashes the three bytes at `i`, to find earlier positions that may start a match.
+fn hash(data: &[u8], i: usize) -> usize {
+    let v = u32::from(data[i]) << 16 | u32::from(data[i + 1]) << 8 | u32::from(data[i + 2]);
+    (v.wrapping_mul(2_654_435_761) >> (32 - HASH_BITS)) as usize
+}
+
+/// Reads bits least significant first.
+struct BitReader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+    acc: u32,
+    bits: u32,
+}
+
+impl BitReader<'_> {
+    fn bit(&mut self) -> Result<u32, String> {
+        if self.bits == 0 {
+            self.acc = u32::from(
+                *self
+                    .bytes
+                    .get(self.pos)
+                    .ok_or("the compressed data is truncated")?,
+            );
+            self.pos += 1;
+            self.bits = 8;
+        }
+        let bit = self.acc & 1;
+        self.acc >>= 1;
+        self.bits -= 1;
+        Ok(bit)
+    }
+
+    fn bits(&mut self, count: u8) -> Result<u32, String> {
+        let mut value = 0;
+        for i in 0..count {
+            value |= self.bit()? << i;
+        }
+        Ok(value)
+    }
+
+    /// Drops the rest of the current byte.
+    fn align(&mut self) {
+        self.bits = 0;
+    }
+
+    /// Returns the position of the first byte after the compressed data.
+    fn byte_end(&self) -> usize {
+        self.pos
+    }
+}
+
+/// A canonical Huffman code, decoded one bit at a time.
+struct Huffman {
+    /// How many codes have each length from 0 to 15.
+    counts: [u16; 16],
+    /// The symbols, ordered by code.
+    symbols: Vec<u16>,
+}
+
+impl Huffman {
+    /// Builds the code from the code length of every symbol, 0 for unused symbols.
+    fn new(lengths: &[u8]) -> Result<Huffman, String> {
+        let mut counts = [0u16; 16];
+        for &len in lengths {
+            counts[usize::from(len)] += 1;
+        }
+        counts[0] = 0;
+
+        let mut left: i32 = 1;
+        for &count in &counts[1..] {
+            left = left * 2 - i32::from(count);
+            if left < 0 {
+                return Err("the compressed data has an invalid Huffman code".to_string());
+            }
+        }
+
+        let mut offsets = [0u16; 16];
+        for len in 1..15 {
+            offsets[len + 1] = offsets[len] + counts[len];
+        }
+        let mut symbols = vec![0; lengths.len()];
+        for (symbol, &len) in lengths.iter().enumerate() {
+            if len != 0 {
+                symbols[usize::from(offsets[usize::from(len)])] = symbol as u16;
+                offsets[usize::from(len)] += 1;
+            }
+        }
+        Ok(Huffman { counts, symbols })
+    }
+
+    fn decode(&self, reader: &mut BitReader) -> Result<u16, String> {
+        let mut code: i32 = 0;
+        let mut first: i32 = 0;
+        let mut index: i32 = 0;
+        for &count in &self.counts[1..] {
+            code |= reader.bit()? as i32;
+            let count = i32::from(count);
+            if code - first < count {
+                return Ok(self.symbols[(index + code - first) as usize]);
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        Err("the compressed data has an invalid Huffman code".to_string())
+    }
+}
+
+/// Decompresses a DEFLATE stream.
+fn inflate(reader: &mut BitReader) -> Result<Vec<u8>, String> {
+    let mut out = Vec::new();
+    loop {
+        let last = reader.bit()? == 1;
+        match reader.bits(2)? {
+            0 => {
+                reader.align();
+                let header = reader
+                    .bytes
+                    .get(reader.pos..reader.pos + 4)
+                    .ok_or("the compressed data is truncated")?;
+                let len = usize::from(u16::from_le_bytes([header[0], header[1]]));
+                let check = u16::from_le_bytes([header[2], header[3]]);
+                if check != !(len as u16) {
+                    return Err("the compressed data has a damaged stored block".to_string());
+                }
+                reader.pos += 4;
+                let stored = reader
+                    .bytes
+                    .get(reader.pos..reader.pos + len)
+                    .ok_or("the compressed data is truncated")?;
+                out.extend_from_slice(stored);
+                reader.pos += len;
+            }
+            1 => {
+                let mut lengths = [0u8; 288];
+                lengths[..144].fill(8);
+                lengths[144..256].fill(9);
+                lengths[256..280].fill(7);
+                lengths[280..].fill(8);
+                let literals = Huffman::new(&lengths)?;
+                let distances = Huffman::new(&[5; 30])?;
+                inflate_block(reader, &mut out, &literals, &distances)?;
+            }
+            2 => {
+                let (literals, distances) = read_dynamic_codes(reader)?;
+                inflate_block(reader, &mut out, &literals, &distances)?;
+            }
+            _ => return Err("the compressed data has an invalid block type".to_string()),
+        }
+        if last {
+            return Ok(out);
+        }
+    }
+}
+
+/// Reads the code lengths a dynamic block starts with and builds its two codes.
+fn read_dynamic_codes(reader: &mut BitReader) -> Result<(Huffman, Huffman), String> {
+    let literal_count = reader.bits(5)? as usize + 257;
+    let distance_count = reader.bits(5)? as usize + 1;
+    let code_length_count = reader.bits(4)? as usize + 4;
+
+    let mut code_lengths = [0u8; 19];
+    for &i in &CODE_LENGTH_ORDER[..code_length_count] {
+        code_lengths[i] = reader.bits(3)? as u8;
+    }
+    let code_lengths = Huffman::new(&code_lengths)?;
+
+    let mut lengths = Vec::with_capacity(literal_count + distance_count);
+    while lengths.len() < literal_count + distance_count {
+        let (value, repeat) = match code_lengths.decode(reader)? {
+            symbol @ 0..=15 => (symbol as u8, 1),
+            16 => {
+                let previous = *lengths
+                    .last()
+                    .ok_or("the compressed data repeats a missing code length")?;
+                (previous, 3 + reader.bits(2)?)
+            }
+            17 => (0, 3 + reader.bits(3)?),
+            _ => (0, 11 + reader.bits(7)?),
+        };
+        lengths.extend(std::iter::repeat_n(value, repeat as usize));
+    }
+    if lengths.len() > literal_count + distance_count {
+        return Err("the compressed data has too many code lengths".to_string());
+    }
+    Ok((
+        Huffman::new(&lengths[..literal_count])?,
+        Huffman::new(&lengths[literal_count..])?,
+    ))
+}
+
+/// Decodes the symbols of a Huffman coded block up to its end of block symbol.
+fn inflate_block(
+    reader: &mut BitReader,
+    out: &mut Vec<u8>,
+    literals: &Huffman,
+    distances: &Huffman,
+) -> Result<(), String> {
+    loop {
+        let symbol = usize::from(literals.decode(reader)?);
+        match symbol {
+            0..=255 => out.push(symbol as u8),
+            256 => return Ok(()),
+            _ => {
+                let code = symbol - 257;
+                let base = *LENGTH_BASE
+                    .get(code)
+                    .ok_or("the compressed data has an invalid length")?;
+                let len = usize::from(base) + reader.bits(LENGTH_EXTRA[code])? as usize;
+                let code = usize::from(distances.decode(reader)?);
+                let base = *DIST_BASE
+                    .get(code)
+                    .ok_or("the compressed data has an invalid distance")?;
+                let dist = usize::from(base) + reader.bits(DIST_EXTRA[code])? as usize;
+                if dist > out.len() {
+                    return Err("the compressed data refers back too far".to_string());
+                }
+                let start = out.len() - dist;
+                for i in 0..len {
+                    out.push(out[start + i]);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::Rng;
+
+    /// A small deck as `gzip -n -9` compresses it, which uses a dynamic Huffman block.
+    const GZIP_OUTPUT: [u8; 200] = [
+        0x1f, 0x8b, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0x03, 0x8d, 0x4e, 0xbb, 0x0e, 0xc2,
+        0x30, 0x10, 0xdb, 0xf3, 0x15, 0xa7, 0xcc, 0x19, 0x80, 0x91, 0x8d, 0x85, 0x0f, 0x60, 0x45,
+        0x1d, 0x8e, 0xf6, 0xa0, 0x11, 0x21, 0x17, 0x72, 0xe1, 0x51, 0x55, 0xfd, 0x77, 0x12, 0x5e,
+        0x2a, 0x0b, 0x90, 0x21, 0xb2, 0x7d, 0xb6, 0xec, 0x5e, 0x01, 0xe8, 0x33, 0x45, 0xb1, 0xec,
+        0xf5, 0x1c, 0x66, 0xa6, 0x70, 0xba, 0x52, 0xac, 0xad, 0x90, 0x64, 0x65, 0x9d, 0x05, 0x80,
+        0xfe, 0xfe, 0xe7, 0x53, 0xea, 0x02, 0x65, 0x55, 0xaf, 0xa8, 0x46, 0xe7, 0xb4, 0x79, 0xe9,
+        0x0d, 0x26, 0x7c, 0xbb, 0xc7, 0x89, 0xfb, 0xf5, 0x78, 0x22, 0x49, 0x8f, 0x06, 0xbd, 0x80,
+        0x90, 0xeb, 0xd8, 0xc3, 0xa5, 0x65, 0x48, 0xad, 0xf5, 0x7b, 0x01, 0xf6, 0xae, 0x03, 0xde,
+        0x66, 0x4a, 0x07, 0x21, 0x77, 0xce, 0xcd, 0x66, 0x9c, 0x47, 0x2f, 0x97, 0x1c, 0x2a, 0x05,
+        0x9a, 0x76, 0x6c, 0x25, 0x69, 0x03, 0x05, 0xa5, 0x02, 0xab, 0x0f, 0xaf, 0x6d, 0x4a, 0xcb,
+        0xe4, 0xe3, 0xe1, 0x54, 0xbf, 0x2d, 0x83, 0xf9, 0xbd, 0x71, 0x49, 0x18, 0xcb, 0x1e, 0x0e,
+        0xe4, 0x41, 0x02, 0xd6, 0xdf, 0x06, 0xe1, 0x8e, 0x23, 0x86, 0x96, 0x37, 0x16, 0xff, 0x99,
+        0x32, 0x1b, 0x4d, 0x79, 0xa2, 0x4a, 0xbd, 0x58, 0xa5, 0x06, 0x75, 0x03, 0x27, 0xed, 0x95,
+        0x24, 0x93, 0x01, 0x00, 0x00,
+    ];
+
+    /// What `GZIP_OUTPUT` decompresses to.
+    const GZIP_INPUT: &str = r#"{
+  "version": 2,
+  "exercises": [
+    {
+      "type": "Recall",
+      "data": [
+        {
+          "question": "A person who thinks only of themselves",
+          "answers": ["egoist", "egotist"],
+          "id": "00000000000000a1"
+        },
+        {
+          "question": "Fear of open spaces",
+          "answers": ["agoraphobia"],
+          "id": "00000000000000a2"
+        }
+      ]
+    }
+  ]
+}
+"#;
+
+    #[test]
+    fn round_trip() {
+        let mut rng = Rng::new(7);
+        let noise: Vec<u8> = (0..5000).map(|_| rng.next_u64() as u8).collect();
+        let repetitive = GZIP_INPUT.repeat(200).into_bytes();
+        for data in [&b""[..], b"a", GZIP_INPUT.as_bytes(), &repetitive, &noise] {
+            let compressed = compress(data);
+            assert!(is_gzip(&compressed));
+            assert_eq!(decompress(&compressed).unwrap(), data);
+        }
+        assert!(compress(&repetitive).len() < repetitive.len() / 10);
+    }
+
+    #[test]
+    fn decodes_the_output_of_gzip() {
+        assert!(is_gzip(&GZIP_OUTPUT));
+        assert_eq!(
+            (GZIP_OUTPUT[10] >> 1) & 3,
+            2,
+            "expected a dynamic Huffman block"
+        );
+        assert_eq!(decompress(&GZIP_OUTPUT).unwrap(), GZIP_INPUT.as_bytes());
+    }
+
+    #[test]
+    fn skips_the_file_name() {
+        let mut named = GZIP_OUTPUT[..10].to_vec();
+        named[3] |= 8; // FNAME
+        named.extend_from_slice(b"data.json\0");
+        named.extend_from_slice(&GZIP_OUTPUT[10..]);
+        assert_eq!(decompress(&named).unwrap(), GZIP_INPUT.as_bytes());
+    }
+
+    #[test]
+    fn rejects_a_bad_checksum() {
+        let mut damaged = GZIP_OUTPUT;
+        damaged[GZIP_OUTPUT.len() - 8] ^= 1;
+        assert!(decompress(&damaged).is_err());
+    }
+
+    #[test]
+    fn rejects_a_bad_length() {
+        let mut damaged = compress(b"some text");
+        let len = damaged.len();
+        damaged[len - 4] += 1;
+        assert!(decompress(&damaged).is_err());
+    }
+
+    #[test]
+    fn rejects_truncated_input() {
+        for data in [GZIP_OUTPUT.to_vec(), compress(GZIP_INPUT.as_bytes())] {
+            for len in 0..data.len() {
+                assert!(decompress(&data[..len]).is_err(), "accepted {} bytes", len);
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_other_files() {
+        assert!(!is_gzip(b"{}"));
+        assert!(decompress(b"{}").is_err());
+        let mut other_method = GZIP_OUTPUT;
+        other_method[2] = 7;
+        assert!(decompress(&other_method).is_err());
+    }
+}
@@ -0,0 +1,62 @@
+use crate::scheduler::Grade;
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// How sure the learner was of a reply, asked before it is graded.
+#[derive(Serialize, Deserialize, strum_macros::Display, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub enum Confidence {
+    Guess,
+    Unsure,
+    Sure,
+}
+
+impl Confidence {
+    /// Returns a list of all confidence levels, from least to most sure.
+    pub fn all() -> Vec<Confidence> {
+        vec![Self::Guess, Self::Unsure, Self::Sure]
+    }
+}
+
+/// One reply to a question, kept in the question's history.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Attempt {
+    /// When the reply was given, in seconds since the Unix epoch.
+    pub time: u64,
+    pub correct: bool,
+    /// How long the reply took, in milliseconds.
+    pub millis: u64,
+    /// How sure the learner was, if they were asked.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub confidence: Option<Confidence>,
+    /// Whether the reply was given in a scheduled review, which counts towards the daily
+    /// review limits; quiz and cram replies don't.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub scheduled: bool,
+    /// The grade the question was rescheduled with, for scheduled reviews.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub grade: Option<Grade>,
+}
+
+/// Returns the current time in seconds since the Unix epoch.
+pub fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+impl Attempt {
+    /// Creates an attempt for a reply given just now.
+    pub fn new(correct: bool, elapsed: Duration, confidence: Option<Confidence>) -> Attempt {
+        Attempt {
+            time: unix_now(),
+            correct,
+            millis: elapsed.as_millis() as u64,
+            confidence,
+            scheduled: false,
+            grade: None,
+        }
+    }
+}
@@ -0,0 +1,146 @@
+use std::fs::{self, File, OpenOptions, TryLockError};
+use std::io;
+
+/// An advisory lock on a data file, released when it is dropped or the process exits.
+///
+/// The lock is taken on a `<data file>.lock` file next to it rather than on the data file
+/// itself, because saves replace the data file with a new one. The last process to let go
+/// of the lock removes that file again.
+pub struct Lock {
+    file: File,
+    path: String,
+}
+
+impl Drop for Lock {
+    /// Removes the lock file unless another process holds a lock on it too.
+    ///
+    /// Only removing it while holding it exclusively keeps this safe: a process that opened
+    /// the file just before sees that it was removed once it gets the lock, and starts over
+    /// with a new one, see `acquire`.
+    fn drop(&mut self) {
+        if cfg!(unix) && self.file.try_lock().is_ok() {
+            let _ = fs::remove_file(&self.path);
+        }
+    }
+}
+
+/// Returns the file that locks on `data_file` are taken on.
+fn lock_path(data_file: &str) -> String {
+    format!("{}.lock", data_file)
+}
+
+/// Locks `data_file` for the rest of the command. Other files a command writes, such as
+/// an `--output` file, are locked the same way.
+///
+/// A command that changes the deck needs an `exclusive` lock, so its save can't undo the
+/// changes of another process that loaded the deck at the same time. Commands that only
+/// read it take a shared lock, which any number of them can hold together.
+///
+/// # Arguments
+///
+/// * `data_file` - The data file to lock.
+/// * `exclusive` - Whether the deck will be changed.
+/// * `wait` - Whether to wait for other processes to release the file instead of failing.
+///
+/// # Returns
+///
+/// The lock, or an error if another process holds a conflicting lock and `wait` is false.
+/// On file systems without locking the command runs unlocked.
+pub fn acquire(data_file: &str, exclusive: bool, wait: bool) -> Result<Option<Lock>, String> {
+    let path = lock_path(data_file);
+    loop {
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&path)
+            .map_err(|e| format!("Cannot lock {}: {}", data_file, e))?;
+
+        let attempt = if exclusive {
+            file.try_lock()
+        } else {
+            file.try_lock_shared()
+        };
+        let result = match attempt {
+            Err(TryLockError::WouldBlock) if wait => {
+                eprintln!(
+                    "Waiting for another word_power process to finish with {}...",
+                    data_file
+                );
+                if exclusive {
+                    file.lock()
+                } else {
+                    file.lock_shared()
+                }
+            }
+            Err(TryLockError::WouldBlock) => {
+                return Err(format!(
+                    "{} is in use by another word_power process; finish that one first, \
+                     or pass --wait to wait for it",
+                    data_file
+                ))
+            }
+            Err(TryLockError::Error(e)) => Err(e),
+            Ok(()) => Ok(()),
+        };
+        match result {
+            // The lock file was removed by its last holder while this process waited.
+            Ok(()) if !is_current(&file, &path) => continue,
+            Ok(()) => return Ok(Some(Lock { file, path })),
+            Err(e) if e.kind() == io::ErrorKind::Unsupported => return Ok(None),
+            Err(e) => return Err(format!("Cannot lock {}: {}", data_file, e)),
+        }
+    }
+}
+
+/// Checks whether `file` is still the file at `path`, and not one that has since been
+/// removed from there.
+#[cfg(unix)]
+fn is_current(file: &File, path: &str) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    match (file.metadata(), fs::metadata(path)) {
+        (Ok(open), Ok(named)) => open.dev() == named.dev() && open.ino() == named.ino(),
+        _ => false,
+    }
+}
+
+/// Lock files are only removed on Unix, so the open one is always current elsewhere.
+#[cfg(not(unix))]
+fn is_current(_file: &File, _path: &str) -> bool {
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    fn data_file(name: &str) -> String {
+        let dir = std::env::temp_dir().join(format!("word_power-lock-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        dir.join(name).to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn exclusive_locks_conflict() {
+        let file = data_file("exclusive.json");
+        let lock = acquire(&file, true, false).unwrap();
+        assert!(acquire(&file, false, false).is_err());
+        assert!(acquire(&file, true, false).is_err());
+        drop(lock);
+        assert!(acquire(&file, true, false).is_ok());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn the_last_lock_removes_the_lock_file() {
+        let file = data_file("shared.json");
+        let first = acquire(&file, false, false).unwrap();
+        let second = acquire(&file, false, false).unwrap();
+        assert!(Path::new(&lock_path(&file)).exists());
+        drop(first);
+        assert!(Path::new(&lock_path(&file)).exists());
+        drop(second);
+        assert!(!Path::new(&lock_path(&file)).exists());
+    }
+}
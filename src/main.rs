@@ -1,45 +1,1747 @@
-use crate::entry::Entry;
-use serde_json;
-use std::path::Path;
-use std::{env, fs};
+use crate::cli::Args;
+use crate::date::Date;
+use crate::entry::{Entry, EntryConfig};
+use crate::exercise::{Difficulty, Exercise};
+use crate::filter::Filter;
+use crate::goal::Goal;
+use crate::history::{unix_now, Attempt};
+use crate::lock::Lock;
+use crate::quiz::{Matcher, QuizConfig};
+use crate::rng::Rng;
+use crate::scheduler::{DailyLimits, Grade, Schedule};
+use crate::score::{SessionResult, Weights};
+use crate::session_log::SessionRecord;
+use crate::storage::Storage;
+use crate::streak::Streak;
+use inquire::error::InquireResult;
+use inquire::{Confirm, InquireError, Select};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::env;
+use std::fs;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::time::Duration;
+use strum::VariantNames;
 
+mod backup;
+mod chart;
+mod cli;
+mod convert;
+mod crypt;
+mod datadir;
+mod date;
+mod deck;
+mod dedupe;
+mod draft;
+mod edit;
 mod entry;
 mod exercise;
+mod filter;
+mod goal;
+mod gzip;
+mod history;
+mod lock;
+mod markdown;
+mod media;
+mod merge;
+mod migrate;
+mod msgpack;
+mod node;
+mod pattern;
+mod quiz;
+mod rng;
+mod scheduler;
+mod score;
+mod session_log;
+mod similar;
+mod speech;
+mod stats;
+mod storage;
+mod streak;
+mod template;
+mod toml;
+mod verify;
+mod yaml;
+
+/// The file in the data directory that exercises are loaded from and saved to, unless
+/// overridden.
+const DATA_FILE: &str = "data.json";
+
+/// The environment variable naming the data file when `--file` isn't given.
+const DATA_FILE_VAR: &str = "WORD_POWER_FILE";
 
 fn main() {
-    // Collect command line arguments into a vector.
-    let args: Vec<String> = env::args().collect();
+    // Collect command line arguments.
+    let args = Args::from_env();
+
+    // Keep other processes from saving over the changes of this one, and the reverse.
+    let _lock = match data_file(&args) {
+        Ok(file) => match lock::acquire(&file, writes_data_file(&args), args.flag("--wait")) {
+            Ok(lock) => lock,
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                return;
+            }
+        },
+        Err(_) => None,
+    };
+
+    // Files used to be kept in the working directory; offer to move one found there.
+    if let Err(e) = offer_data_dir_move(&args) {
+        eprintln!("Error: {}", e);
+    }
+
+    // Finish or clean up a save that a crash interrupted before touching the deck.
+    if let Ok(file) = data_file(&args) {
+        match storage::recover(&file) {
+            Ok(Some(message)) => eprintln!("{}", message),
+            Ok(None) => {}
+            Err(e) => eprintln!("Error: {}", e),
+        }
+    }
+
+    // Dispatch on the requested mode.
+    let result = if args.positional(0) == Some("deck") {
+        execute_deck(&args)
+    } else if args.positional(0) == Some("backup") {
+        execute_backup(&args)
+    } else if args.positional(0) == Some("validate") {
+        execute_validate(&args)
+    } else if args.positional(0) == Some("merge") {
+        execute_merge(&args)
+    } else if args.positional(0) == Some("dedupe") {
+        execute_dedupe(&args)
+    } else if args.positional(0) == Some("compact") {
+        execute_compact(&args)
+    } else if args.positional(0) == Some("convert") {
+        execute_convert(&args)
+    } else if args.flag("--input") {
+        execute_data(&args)
+    } else if args.flag("--quiz") {
+        execute_quiz(&args)
+    } else if args.flag("--review") {
+        execute_review(&args)
+    } else if args.flag("--list") {
+        execute_list(&args)
+    } else if args.flag("--recall-to-mcq") {
+        execute_recall_to_mcq(&args)
+    } else if args.flag("--sample") {
+        execute_sample(&args)
+    } else if args.flag("--find-similar") {
+        execute_find_similar(&args)
+    } else if args.flag("--preview") {
+        execute_preview()
+    } else if args.flag("--verify") {
+        execute_verify(&args)
+    } else if args.flag("--stats") {
+        execute_stats(&args)
+    } else if args.flag("--dashboard") {
+        execute_dashboard(&args)
+    } else if args.flag("--set-goal") {
+        execute_set_goal(&args)
+    } else if args.flag("--set-difficulty") {
+        execute_set_difficulty(&args)
+    } else if args.flag("--edit") {
+        execute_edit(&args)
+    } else if args.flag("--related") {
+        execute_related(&args)
+    } else if args.flag("--pack") {
+        execute_pack(&args)
+    } else if args.flag("--encrypt") {
+        execute_encrypt(&args)
+    } else if args.flag("--decrypt") {
+        execute_decrypt(&args)
+    } else if args.flag("--merge-stats") {
+        execute_merge_stats(&args)
+    } else {
+        print_usage(args.program());
+        Ok(())
+    };
+
+    if let Err(e) = result {
+        eprintln!("Error: {}", e);
+    }
+}
+
+/// Prints the available modes and their options.
+fn print_usage(program: &str) {
+    println!("Usage: {} <mode> [options]", program);
+    println!();
+    println!("Options for every mode:");
+    println!(
+        "      --file <path>       Read and write this data file instead of {} in",
+        DATA_FILE
+    );
+    println!(
+        "                          {} (or set {});",
+        datadir::data_dir().map_or("the data directory".to_string(), |d| d
+            .display()
+            .to_string()),
+        DATA_FILE_VAR
+    );
+    println!(
+        "                          a directory means its {}, e.g. --file . for the",
+        DATA_FILE
+    );
+    println!("                          working directory; names ending in .yaml, .yml,");
+    println!("                          .toml, .msgpack or .mpk are stored in that format,");
+    println!("                          and names ending in .gz are gzip compressed");
+    println!("      --deck <name>       Read and write the named deck instead");
+    println!("      --storage <kind>    How to store the deck: json (the default) saves it");
+    println!("                          whole in the format its file name calls for, jsonl");
+    println!("                          appends new exercises to a log next to the file, one");
+    println!("                          line per batch, until `compact`, and msgpack saves");
+    println!("                          it as smaller, faster MessagePack. sqlite, picked");
+    println!("                          for .db, .sqlite and .sqlite3 files, is unsupported");
+    println!("                          as this build has no SQLite driver");
+    println!("      --wait              Wait for another process using the data file to finish");
+    println!("                          instead of stopping with an error");
+    println!(
+        "      --keep-backups <n>  Earlier versions to keep in {}/ next to the data file",
+        backup::BACKUP_DIR
+    );
+    println!(
+        "                          (default {}; 0 for none)",
+        backup::DEFAULT_KEEP
+    );
+    println!();
+    println!("Modes:");
+    println!("  --input                 Enter new exercises interactively");
+    println!("      --no-number         Don't prefix prompts with the question number");
+    println!("      --max-count <n>     Most questions or options per batch (default 50)");
+    println!("      --chapter <label>   File every batch under this chapter without asking");
+    println!("  --quiz                  Practice the stored exercises");
+    println!("      --type [type]       Only practice one exercise type, chosen if omitted");
+    println!("      --filter <expr>     Only practice questions matching e.g. chapter=3");
+    println!("      --difficulty <d>    Only practice easy, medium or hard questions");
+    println!("      --starred           Only practice the questions starred after a quiz");
+    println!("      --limit <n>         Practice n questions, favouring often missed ones");
+    println!("      --uniform           Pick the --limit questions uniformly at random");
+    println!("      --results <file>    Also write the session's results as JSON");
+    println!("      --save-missed <file> Write the missed questions to a deck to drill with");
+    println!("                          --file, replacing it unless --append-missed is given");
+    println!("      --no-shuffle        Keep the stored question and option order");
+    println!("      --seed <n>          Seed for a reproducible order");
+    println!("      --timed <seconds>   Time allowed per question; unanswered ones are wrong");
+    println!("      --match-case        Typed answers must match capitalization");
+    println!("      --match-punctuation Typed answers must match punctuation");
+    println!("      --exam              Grade all replies at the end instead of one by one");
+    println!("      --interleave        Take turns between exercise types");
+    println!("      --confidence        Rate how sure you were of each reply");
+    println!("      --weights <e,m,h>   Points for easy, medium and hard questions in the");
+    println!("                          weighted score (default 1,2,3; unrated count as medium)");
+    println!(
+        "      --tts               Read every question aloud with the command in {}",
+        speech::TTS_VAR
+    );
+    println!("  --review                Practice the questions that are due, like --quiz");
+    println!("      --scheduler <name>  How to reschedule: sm2 (default) or leitner");
+    println!("      --max-new <n>       Most new questions per day (default 20)");
+    println!("      --max-reviews <n>   Most due questions per day (default 200)");
+    println!("      --grade             Rate each reply from Again to Easy");
+    println!("      --cram              Practice everything without rescheduling");
+    println!("      --type, --filter    Pick the questions to cram, like --quiz");
+    println!("      --difficulty <d>    Only cram easy, medium or hard questions");
+    println!("  --list                  Print the stored exercises");
+    println!("      --filter <expr>     Only keep questions matching e.g. added=7 or due=0");
+    println!("      --show-source       Show the ID, dates, source, chapter and other details");
+    println!("      --json              Print the exercises as JSON");
+    println!("  --sample <n>            Print n random questions, like --list");
+    println!("      --seed <n>          Seed for a reproducible sample");
+    println!("      --json              Print the sample as JSON");
+    println!("  --recall-to-mcq         Turn recall cards into multiple choice questions");
+    println!("      --distractors <n>   Number of wrong options per question (default 3)");
+    println!("      --seed <n>          Seed for reproducible distractor choice");
+    println!("      --output <file>     Write to a separate deck instead of appending");
+    println!("  --find-similar          Report pairs of nearly identical questions");
+    println!("      --threshold <x>     Minimum similarity from 0 to 1 (default 0.8)");
+    println!("      --json              Print the pairs as JSON");
+    println!("  --preview               Show an example of every exercise type, listed and as");
+    println!("                          a quiz asks it");
+    println!("  --verify                Check that every answer maps to a lettered option,");
+    println!("                          IDs are unique and every link and media file exists");
+    println!("      --json              Print the problems as JSON");
+    println!("  --stats                 Chart accuracy and response times so far");
+    println!("      --json              Print the totals as JSON");
+    println!("      --csv <file>        Export every attempt as CSV instead");
+    println!("      --hardest           List the most often missed questions instead");
+    println!("      --limit <n>         How many to list (default 20)");
+    println!("      --min-attempts <n>  Skip questions tried, or with --slowest answered right,");
+    println!("                          fewer times (default 3)");
+    println!("      --slowest           List the slowest correct replies instead");
+    println!("      --bank              Count questions per type, chapter and tag instead");
+    println!("  --dashboard             Show what's due, the streak, trends and weak spots");
+    println!("      --max-new <n>       Daily new question limit, like --review");
+    println!("      --max-reviews <n>   Daily due question limit, like --review");
+    println!("  --set-goal <goal>       Aim for e.g. 30 replies or 15m a day; off to clear");
+    println!("  --set-difficulty <d>    Rate questions easy, medium or hard; none to clear");
+    println!("      --filter <expr>     Only rate questions matching e.g. chapter=3");
+    println!("      --type <type>       Only rate questions of one exercise type");
+    println!("  --edit <id>             Change the notes or links of the question with this ID,");
+    println!("                          or suspend it from practice");
+    println!("  --related <id>          Show every question linked to this one, directly or not");
+    println!("  --pack <dir>            Copy the deck and its media files into a directory");
+    println!("  --encrypt               Encrypt the data file and its backups with a passphrase,");
+    println!(
+        "                          asked for whenever it is loaded (or set {})",
+        crypt::PASSPHRASE_VAR
+    );
+    println!("  --decrypt               Store the data file and its backups as plain text again");
+    println!(
+        "  deck new <name>         Create an empty named deck in {}",
+        deck::deck_dir().display()
+    );
+    println!("  deck list               List the named decks and their sizes");
+    println!("  deck delete <name>      Delete a named deck after confirming");
+    println!("  deck rename <old> <new> Rename a named deck");
+    println!("  backup list             List the saved earlier versions of the data file");
+    println!("  validate [file]         Check a data file's structure, IDs and links, and");
+    println!("                          exit with an error status if anything is wrong");
+    println!("      --json              Print the problems as JSON");
+    println!("  merge <file>            Add the questions of another data file, skipping");
+    println!("                          those with a known ID or the same question text");
+    println!("      --json              Print what was merged and skipped as JSON");
+    println!("      --strict            Merge nothing if any question is invalid or would");
+    println!("                          be skipped, and report the first one");
+    println!("  --merge-stats <a> <b>   Combine the history and schedules of the questions of");
+    println!("                          two copies of a deck, matched by ID");
+    println!("      --output <file>     The file to write the combined deck to");
+    println!("      --json              Print what was combined as JSON");
+    println!("  dedupe                  Remove repeated questions of the same type, keeping");
+    println!("                          the copy with the most history; asks when they differ");
+    println!("  compact                 Fold the exercises appended by --storage jsonl into");
+    println!("                          the data file");
+    println!("  convert <file>          Write the deck to a new file in the format its name");
+    println!("                          calls for, e.g. deck.msgpack or deck.json");
+}
+
+/// Loads the deck like `load_deck`, narrowed down by the `--filter` expression if one was
+/// given. The filter is passed to the storage as a query.
+fn load_filtered_deck(args: &Args) -> Result<Option<Vec<Exercise>>, Box<dyn std::error::Error>> {
+    match args.value("--filter") {
+        Some(expr) => Ok(Some(open_storage(args)?.query(&expr.parse::<Filter>()?)?)),
+        None => load_deck(args),
+    }
+}
+
+/// Lets the user type in new exercises and adds them to the deck.
+///
+/// Every finished batch is also saved to a draft next to the data file. A draft left by a
+/// session that crashed is offered for restoring before new entries are asked for.
+fn execute_data(args: &Args) -> Result<(), Box<dyn std::error::Error>> {
+    let file = data_file(args)?;
+    let draft = draft::path(&file);
+    let config = EntryConfig {
+        numbered: !args.flag("--no-number"),
+        max_count: match args.value("--max-count") {
+            Some(n) => n.parse()?,
+            None => 50,
+        },
+        chapter: args.value("--chapter").map(String::from),
+        media_dir: media::media_dir(&file),
+        draft: Some(draft.clone()),
+    };
+
+    // The draft of an encrypted deck is encrypted too, so the passphrase is asked for first.
+    crypt::share(&file, &draft)?;
+    if let Some(recovered) = draft::load(&draft)? {
+        let n: usize = recovered.iter().map(Exercise::question_count).sum();
+        let restore = n > 0
+            && Confirm::new(&format!(
+                "Restore the {} questions entered in a session that didn't finish?",
+                n
+            ))
+            .with_default(true)
+            .with_help_message("they are added to the deck; answer no to discard them")
+            .prompt()?;
+        if restore {
+            open_storage(args)?.append(recovered)?;
+            println!("Restored {} questions", n);
+        }
+        draft::discard(&draft)?;
+    }
+
+    let result = match Exercise::read(&config) {
+        Ok(new_exercises) => open_storage(args)?.append(new_exercises),
+        Err(InquireError::OperationInterrupted) => {
+            println!("Quit without saving");
+            Ok(())
+        }
+        Err(e) => return Err(e.into()),
+    };
+    // The draft is only kept when the entries couldn't be saved, to be restored next time.
+    if result.is_ok() {
+        draft::discard(&draft)?;
+    }
+    result
+}
+
+/// Quizzes the learner on every stored question and prints a summary of the session.
+///
+/// The summary covers the questions answered so far when the quiz is ended early, and is
+/// also written to `--results` as JSON when that option is given. Every reply is added to
+/// its question's history in the data file.
+///
+/// `--limit` picks its questions at random, weighted towards those most often answered
+/// wrongly, unless `--uniform` is given. Afterwards the learner can star questions to
+/// revisit with `--starred`.
+///
+/// With `--save-missed`, the questions answered wrongly are written to a deck of their own
+/// to drill with `--file`, replacing what it held unless `--append-missed` is given.
+fn execute_quiz(args: &Args) -> Result<(), Box<dyn std::error::Error>> {
+    let Some(mut exercises) = load_deck(args)? else {
+        return Ok(());
+    };
+
+    let mut positions = selected_positions(args, &exercises)?;
+    if let Some(n) = args.value("--limit") {
+        let mut rng = rng_from_args(args)?;
+        if args.flag("--uniform") {
+            rng.shuffle(&mut positions);
+            positions.truncate(n.parse()?);
+            positions.sort_unstable();
+        } else {
+            positions = quiz::weighted_sample(&exercises, positions, n.parse()?, &mut rng);
+        }
+    }
+    if positions.is_empty() {
+        println!("No exercises match");
+        return Ok(());
+    }
+
+    let started = unix_now();
+    let mut config = quiz_config(args)?;
+    let mut session = SessionResult::default();
+    let mut attempts = Vec::new();
+    let result = quiz::run(
+        &exercises,
+        positions,
+        &mut config,
+        &mut session,
+        |position, outcome| {
+            attempts.push((
+                position,
+                Attempt::new(outcome.correct, outcome.elapsed, outcome.confidence),
+            ))
+        },
+    );
+
+    let asked: Vec<(usize, usize)> = attempts.iter().map(|&(position, _)| position).collect();
+    let missed: HashSet<(usize, usize)> = attempts
+        .iter()
+        .filter(|(_, attempt)| !attempt.correct)
+        .map(|&(position, _)| position)
+        .collect();
+    for ((g, q), attempt) in attempts {
+        exercises[g].questions_mut()[q]
+            .meta_mut()
+            .history
+            .push(attempt);
+    }
+    if result.is_ok() && !asked.is_empty() {
+        for (g, q) in quiz::choose_stars(&exercises, &asked)? {
+            exercises[g].questions_mut()[q].meta_mut().starred = true;
+        }
+    }
+    open_storage(args)?.save(&exercises)?;
+
+    finish_session(args, "quiz", started, result, &session)?;
+    print_goal_progress(args, &exercises)?;
+    if let Some(path) = args.value("--save-missed") {
+        let missed_count = missed.len();
+        let missed = filter::pick(exercises, &missed);
+        let _lock = lock_output(args, path)?;
+        let storage = storage::open(path, None, 0)?;
+        if args.flag("--append-missed") {
+            storage.append(missed)?;
+        } else {
+            storage.save(&missed)?;
+        }
+        println!("Saved {} missed questions to {}", missed_count, path);
+    }
+    Ok(())
+}
+
+/// Returns the positions of the questions picked by `--filter`, `--type`, `--difficulty` and
+/// `--starred`. Suspended questions are never picked.
+///
+/// Questions are picked by position so that the whole deck can be saved afterwards.
+fn selected_positions(
+    args: &Args,
+    exercises: &[Exercise],
+) -> Result<Vec<(usize, usize)>, Box<dyn std::error::Error>> {
+    let mut filters = Vec::new();
+    if let Some(expr) = args.value("--filter") {
+        filters.push(expr.parse::<Filter>()?);
+    }
+    // `--type` on its own asks which type to practice.
+    if args.flag("--type") {
+        let kind = match args.value("--type") {
+            Some(kind) => kind.to_string(),
+            None => Select::new("Exercise type", Exercise::VARIANTS.to_vec())
+                .prompt()?
+                .to_string(),
+        };
+        filters.push(format!("type={}", kind).parse::<Filter>()?);
+    }
+    if let Some(difficulty) = args.value("--difficulty") {
+        filters.push(format!("difficulty={}", difficulty).parse::<Filter>()?);
+    }
+    let starred = args.flag("--starred");
+    Ok(quiz::positions(exercises)
+        .into_iter()
+        .filter(|&(g, q)| {
+            let question = exercises[g].questions()[q];
+            let meta = question.meta();
+            !meta.suspended
+                && (meta.starred || !starred)
+                && filters.iter().all(|f| f.matches(&exercises[g], question))
+        })
+        .collect())
+}
+
+/// Builds the quiz settings shared by `--quiz` and `--review` from the command line.
+fn quiz_config(args: &Args) -> Result<QuizConfig, Box<dyn std::error::Error>> {
+    Ok(QuizConfig {
+        shuffle: if args.flag("--no-shuffle") {
+            None
+        } else {
+            Some(rng_from_args(args)?)
+        },
+        time_limit: match args.value("--timed") {
+            Some(seconds) => Some(Duration::from_secs(seconds.parse()?)),
+            None => None,
+        },
+        matcher: Matcher {
+            ignore_case: !args.flag("--match-case"),
+            ignore_punctuation: !args.flag("--match-punctuation"),
+        },
+        exam: args.flag("--exam"),
+        self_grade: false,
+        interleave: args.flag("--interleave"),
+        ask_confidence: args.flag("--confidence"),
+        media_dir: media::media_dir(&data_file(args)?),
+        weights: match args.value("--weights") {
+            Some(weights) => weights.parse()?,
+            None => Weights::default(),
+        },
+        tts: if args.flag("--tts") {
+            let command = env::var(speech::TTS_VAR)
+                .ok()
+                .filter(|c| !c.trim().is_empty());
+            if command.is_none() {
+                println!(
+                    "Warning: --tts needs a text-to-speech command in {}, e.g. espeak; \
+                     questions won't be read aloud",
+                    speech::TTS_VAR
+                );
+            }
+            command
+        } else {
+            None
+        },
+    })
+}
+
+/// Prints the summary of a quiz session and writes it to `--results` if requested.
+///
+/// A session ended with Ctrl-C or Esc still gets its summary. Every session with at least
+/// one reply is also appended to the session log.
+///
+/// # Arguments
+///
+/// * `mode` - The command that ran the session, as recorded in the log.
+/// * `started` - When the session started, in seconds since the Unix epoch.
+fn finish_session(
+    args: &Args,
+    mode: &'static str,
+    started: u64,
+    result: InquireResult<()>,
+    session: &SessionResult,
+) -> Result<(), Box<dyn std::error::Error>> {
+    match result {
+        Ok(()) => {}
+        Err(InquireError::OperationInterrupted | InquireError::OperationCanceled) => {
+            println!("Quiz ended");
+        }
+        Err(e) => return Err(e.into()),
+    }
+
+    if session.total() > 0 {
+        let record = SessionRecord {
+            mode,
+            started,
+            ended: unix_now(),
+            result: session,
+        };
+        session_log::append(&session_log::log_file(&data_file(args)?), &record)?;
+    }
+
+    print!("{}", session);
+    if let Some(path) = args.value("--results") {
+        let _lock = lock_output(args, path)?;
+        fs::write(path, serde_json::to_string_pretty(session)?)?;
+    }
+    Ok(())
+}
+
+/// Prints how much of the daily goal today's replies have covered, if a goal is set.
+fn print_goal_progress(
+    args: &Args,
+    exercises: &[Exercise],
+) -> Result<(), Box<dyn std::error::Error>> {
+    let Some(goal) = Goal::load(&goal::goal_file(&data_file(args)?))? else {
+        return Ok(());
+    };
+    let today = Date::today();
+    let attempts: Vec<&Attempt> = exercises
+        .iter()
+        .flat_map(|e| e.questions())
+        .flat_map(|q| &q.meta().history)
+        .filter(|a| Date::from_unix(a.time) == today)
+        .collect();
+    let millis = attempts.iter().map(|a| a.millis).sum();
+
+    let progress = goal.progress(attempts.len(), millis);
+    if goal.is_met(attempts.len(), millis) {
+        println!("Daily goal of {} reached", goal);
+    } else {
+        println!("Daily goal: {} of {}", progress, goal);
+    }
+    Ok(())
+}
+
+/// Quizzes the learner on the questions that are due and reschedules them.
+///
+/// Questions are rescheduled with SM-2, or with the strategy named by `--scheduler`. The
+/// number of new and due questions per day is capped by `--max-new` and `--max-reviews`.
+///
+/// Questions that have never been reviewed are always due. The updated schedules are saved
+/// even when the review is ended early.
+///
+/// With `--grade`, the learner rates each reply as Again, Hard, Good or Easy, which sets
+/// how far the question is pushed back; otherwise a right reply counts as Good and a wrong
+/// one as Again.
+///
+/// Every day with at least one reviewed question counts towards the streak shown by
+/// `--stats`.
+///
+/// With `--cram`, every question picked by `--filter` and `--type` is asked regardless of
+/// its due date, and the schedules are left alone; the replies still go into the history.
+fn execute_review(args: &Args) -> Result<(), Box<dyn std::error::Error>> {
+    let strategy = scheduler::strategy(args.value("--scheduler").unwrap_or("sm2"))?;
+    let Some(mut exercises) = load_deck(args)? else {
+        return Ok(());
+    };
+    let today = Date::today();
+    let cram = args.flag("--cram");
+
+    let positions = if cram {
+        let positions = selected_positions(args, &exercises)?;
+        if positions.is_empty() {
+            println!("No exercises match");
+            return Ok(());
+        }
+        positions
+    } else {
+        let positions = due_positions(args, &exercises, today)?;
+        if positions.is_empty() {
+            return Ok(());
+        }
+        positions
+    };
+
+    let started = unix_now();
+    let mut config = quiz_config(args)?;
+    config.self_grade = args.flag("--grade") && !cram;
+    let mut session = SessionResult::default();
+    let mut attempts = Vec::new();
+    let result = quiz::run(
+        &exercises,
+        positions,
+        &mut config,
+        &mut session,
+        |position, outcome| {
+            let grade = outcome
+                .grade
+                .unwrap_or_else(|| Grade::from_correct(outcome.correct));
+            let mut attempt = Attempt::new(outcome.correct, outcome.elapsed, outcome.confidence);
+            attempt.scheduled = !cram;
+            attempt.grade = attempt.scheduled.then_some(grade);
+            attempts.push((position, attempt, grade));
+        },
+    );
+
+    for ((g, q), attempt, grade) in attempts {
+        let mut questions = exercises[g].questions_mut();
+        let meta = questions[q].meta_mut();
+        if !cram {
+            let schedule = meta.schedule.get_or_insert_with(Schedule::default);
+            strategy.review(schedule, grade.quality(), today);
+        }
+        meta.history.push(attempt);
+    }
+    open_storage(args)?.save(&exercises)?;
+    if session.total() > 0 {
+        let streak_file = streak::streak_file(&data_file(args)?);
+        let _lock = lock_output(args, &streak_file.to_string_lossy())?;
+        let mut streak = Streak::load(&streak_file)?;
+        streak.record(today);
+        streak.save(&streak_file)?;
+    }
+
+    finish_session(args, "review", started, result, &session)?;
+    print_goal_progress(args, &exercises)
+}
+
+/// Reads the daily limits of `--review` from `--max-new` and `--max-reviews`.
+fn daily_limits(args: &Args) -> Result<DailyLimits, Box<dyn std::error::Error>> {
+    Ok(DailyLimits {
+        new: match args.value("--max-new") {
+            Some(n) => n.parse()?,
+            None => 20,
+        },
+        reviews: match args.value("--max-reviews") {
+            Some(n) => n.parse()?,
+            None => 200,
+        },
+    })
+}
+
+/// Returns the positions of the questions to review on `today` within the daily limits.
+///
+/// Tells the user why when the list comes back empty.
+fn due_positions(
+    args: &Args,
+    exercises: &[Exercise],
+    today: Date,
+) -> Result<Vec<(usize, usize)>, Box<dyn std::error::Error>> {
+    let queue = scheduler::review_queue(exercises, today, &daily_limits(args)?);
+
+    if queue.positions.is_empty() {
+        let next = exercises
+            .iter()
+            .flat_map(|e| e.questions())
+            .filter_map(|q| q.meta().schedule.as_ref().map(|s| s.due))
+            .min();
+        if queue.carried_over > 0 {
+            println!(
+                "Daily limit reached, {} questions carried over",
+                queue.carried_over
+            );
+        } else {
+            match next {
+                Some(next) => println!("Nothing due, next review on {}", next),
+                None => println!("Nothing due"),
+            }
+        }
+        return Ok(Vec::new());
+    }
+    if queue.carried_over > 0 {
+        println!(
+            "{} due questions are carried over by the daily limits",
+            queue.carried_over
+        );
+    }
+    Ok(queue.positions)
+}
 
-    // Check if the arguments contain "--input"
-    if args.contains(&"--input".to_string()) {
-        execute_data().unwrap_or_else(|e| eprintln!("Error: {}", e));
+/// Loads the stored deck for a command that needs at least one exercise.
+///
+/// Messages go to stderr so that `--json` output stays parseable.
+///
+/// # Returns
+///
+/// The exercises, or `None` after telling the user how to add some if the deck is missing
+/// or empty.
+fn load_deck(args: &Args) -> Result<Option<Vec<Exercise>>, Box<dyn std::error::Error>> {
+    let file = data_file(args)?;
+    if !Path::new(&file).exists() {
+        eprintln!("{} does not exist yet", file);
+    }
+    let exercises = open_storage(args)?.load()?;
+    if exercises.is_empty() {
+        eprintln!("No exercises found \u{2014} add some with --input");
+        return Ok(None);
+    }
+    Ok(Some(exercises))
+}
+
+/// Opens the storage of the data file, using the backend named by `--storage` if given.
+fn open_storage(args: &Args) -> Result<Box<dyn Storage>, Box<dyn std::error::Error>> {
+    open_storage_at(args, &data_file(args)?)
+}
+
+/// Opens the storage of `file`, honouring `--storage` and `--keep-backups`.
+fn open_storage_at(
+    args: &Args,
+    file: &str,
+) -> Result<Box<dyn Storage>, Box<dyn std::error::Error>> {
+    let keep_backups = match args.value("--keep-backups") {
+        Some(n) => n.parse()?,
+        None => backup::DEFAULT_KEEP,
+    };
+    Ok(storage::open(file, args.value("--storage"), keep_backups)?)
+}
+
+/// Returns the data file to read and write: the `--file` option, else the deck named by
+/// `--deck`, else the file named by `DATA_FILE_VAR`, else `DATA_FILE` in the data
+/// directory, which is created if needed.
+///
+/// A directory given as the file stands for the `DATA_FILE` in it, so `--file .` uses the
+/// working directory.
+fn data_file(args: &Args) -> Result<String, String> {
+    if let Some(name) = args.value("--deck") {
+        if args.value("--file").is_none() {
+            return deck::existing(name);
+        }
+    }
+    let file = match args
+        .value("--file")
+        .map(String::from)
+        .or_else(|| env::var(DATA_FILE_VAR).ok().filter(|f| !f.is_empty()))
+    {
+        Some(file) => PathBuf::from(file),
+        None => {
+            let Some(dir) = datadir::data_dir() else {
+                return Ok(DATA_FILE.to_string());
+            };
+            fs::create_dir_all(&dir)
+                .map_err(|e| format!("Cannot create {}: {}", dir.display(), e))?;
+            dir.join(DATA_FILE)
+        }
+    };
+    let file = if file.is_dir() {
+        file.join(DATA_FILE)
     } else {
+        file
+    };
+    Ok(file.to_string_lossy().into_owned())
+}
+
+/// Returns whether the requested mode changes the data file, and so has to lock it
+/// exclusively.
+fn writes_data_file(args: &Args) -> bool {
+    matches!(args.positional(0), Some("merge" | "dedupe" | "compact"))
+        || [
+            "--input",
+            "--quiz",
+            "--review",
+            "--recall-to-mcq",
+            "--set-difficulty",
+            "--edit",
+            "--encrypt",
+            "--decrypt",
+        ]
+        .iter()
+        .any(|mode| args.flag(mode))
+}
+
+/// Locks a file other than the data file that the command is about to write, such as an
+/// `--output` file or the streak file, for as long as the returned lock is kept.
+///
+/// `main` already holds the lock on the data file, so naming the data file locks nothing
+/// more; locking it again would wait for this process itself.
+fn lock_output(args: &Args, file: &str) -> Result<Option<Lock>, Box<dyn std::error::Error>> {
+    if data_file(args).is_ok_and(|data| same_file(&data, file)) {
+        return Ok(None);
+    }
+    Ok(lock::acquire(file, true, args.flag("--wait"))?)
+}
+
+/// Checks whether two paths name the same file, which need not exist yet.
+fn same_file(a: &str, b: &str) -> bool {
+    match (fs::canonicalize(a), fs::canonicalize(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => std::path::absolute(a).ok() == std::path::absolute(b).ok(),
+    }
+}
+
+/// Offers, once, to move a `DATA_FILE` left in the working directory by earlier versions
+/// into the data directory, where it is now looked for.
+///
+/// Nothing is asked when a data file is chosen explicitly, the data directory already has
+/// a data file, or the user declined before; declining is remembered in the data
+/// directory.
+fn offer_data_dir_move(args: &Args) -> Result<(), Box<dyn std::error::Error>> {
+    let local = Path::new(DATA_FILE);
+    let explicit = args.value("--file").is_some()
+        || args.value("--deck").is_some()
+        || env::var(DATA_FILE_VAR).is_ok_and(|f| !f.is_empty());
+    let Some(dir) = datadir::data_dir() else {
+        return Ok(());
+    };
+    if explicit
+        || !local.is_file()
+        || dir.join(DATA_FILE).exists()
+        || datadir::kept_local(&dir)
+        || env::current_dir()? == dir
+    {
+        return Ok(());
+    }
+
+    let answer = Confirm::new(&format!(
+        "Move {} from this directory to {}?",
+        DATA_FILE,
+        dir.display()
+    ))
+    .with_default(true)
+    .with_help_message("it is now kept there; answer no to keep using it here with --file .")
+    .prompt();
+    match answer {
+        Ok(true) => {
+            // Another process may still be using the file here, e.g. with --file .
+            let _lock = lock::acquire(DATA_FILE, true, args.flag("--wait"))?;
+            datadir::move_into(local, &dir)?;
+            println!("Moved {} to {}", DATA_FILE, dir.display());
+        }
+        Ok(false) => datadir::keep_local(&dir)?,
+        // Ask again next time rather than deciding for the user.
+        Err(_) => {}
+    }
+    Ok(())
+}
+
+/// Creates the random number generator for a command, seeded by `--seed` if given.
+fn rng_from_args(args: &Args) -> Result<Rng, Box<dyn std::error::Error>> {
+    Ok(match args.value("--seed") {
+        Some(seed) => Rng::new(seed.parse()?),
+        None => Rng::from_time(),
+    })
+}
+
+/// An exercise group as `--json` prints it, numbered from 1 like the text output.
+#[derive(Serialize)]
+struct ListedExercise<'a> {
+    index: usize,
+    #[serde(flatten)]
+    exercise: &'a Exercise,
+}
+
+/// Prints a command's result as pretty JSON, for `--json`.
+fn print_json(value: &impl Serialize) -> Result<(), Box<dyn std::error::Error>> {
+    // Write through the lock so a closed pipe (e.g. `| head`) is an error, not a panic.
+    let mut stdout = std::io::stdout().lock();
+    let written = serde_json::to_writer_pretty(&mut stdout, value)
+        .map_err(std::io::Error::from)
+        .and_then(|()| writeln!(stdout));
+    ignore_broken_pipe(written)
+}
+
+/// Treats a closed pipe on stdout as success: the reader (e.g. `head`) has all it wants.
+fn ignore_broken_pipe(written: std::io::Result<()>) -> Result<(), Box<dyn std::error::Error>> {
+    match written {
+        Err(e) if e.kind() == std::io::ErrorKind::BrokenPipe => Ok(()),
+        written => Ok(written?),
+    }
+}
+
+/// Prints exercise groups the way `--list` shows them, or as JSON with `--json`.
+fn print_exercises(args: &Args, exercises: &[Exercise]) -> Result<(), Box<dyn std::error::Error>> {
+    if args.flag("--json") {
+        let listed: Vec<ListedExercise> = exercises
+            .iter()
+            .enumerate()
+            .map(|(i, exercise)| ListedExercise {
+                index: i + 1,
+                exercise,
+            })
+            .collect();
+        return print_json(&listed);
+    }
+
+    let mut stdout = std::io::stdout().lock();
+    let written = exercises.iter().enumerate().try_for_each(|(i, exercise)| {
+        if args.flag("--show-source") {
+            writeln!(stdout, "[{}] {:#}", i + 1, exercise)
+        } else {
+            writeln!(stdout, "[{}] {}", i + 1, exercise)
+        }
+    });
+    ignore_broken_pipe(written)
+}
+
+/// Prints every stored exercise, optionally narrowed down by `--filter`.
+fn execute_list(args: &Args) -> Result<(), Box<dyn std::error::Error>> {
+    let Some(exercises) = load_filtered_deck(args)? else {
+        return Ok(());
+    };
+    print_exercises(args, &exercises)
+}
+
+/// Prints a random sample of the stored questions, e.g. `--sample 5`.
+fn execute_sample(args: &Args) -> Result<(), Box<dyn std::error::Error>> {
+    let n = args
+        .value("--sample")
+        .ok_or("--sample needs the number of questions to show")?
+        .parse()?;
+    let mut rng = rng_from_args(args)?;
+
+    let Some(exercises) = load_filtered_deck(args)? else {
+        return Ok(());
+    };
+    print_exercises(args, &filter::sample(exercises, n, &mut rng))
+}
+
+/// Generates multiple choice questions from the stored recall cards.
+///
+/// The generated questions are appended to the data file, or written to `--output` as a
+/// separate deck when that option is given.
+fn execute_recall_to_mcq(args: &Args) -> Result<(), Box<dyn std::error::Error>> {
+    let distractors = match args.value("--distractors") {
+        Some(n) => n.parse()?,
+        None => 3,
+    };
+    let mut rng = rng_from_args(args)?;
+
+    let Some(exercises) = load_deck(args)? else {
+        return Ok(());
+    };
+    let generated = convert::recall_to_mcq(&exercises, distractors, &mut rng);
+    let count: usize = generated.iter().map(Exercise::question_count).sum();
+
+    match args.value("--output") {
+        Some(output) => {
+            let _lock = lock_output(args, output)?;
+            open_storage_at(args, output)?.save(&generated)?
+        }
+        None => open_storage(args)?.append(generated)?,
+    }
+    println!("Generated {} multiple choice questions", count);
+    Ok(())
+}
+
+/// Prints one example of every exercise type, first as `--list` shows it and then as a
+/// quiz first prompts for it, without reading the data file.
+fn execute_preview() -> Result<(), Box<dyn std::error::Error>> {
+    let mut config = QuizConfig {
+        shuffle: None,
+        time_limit: None,
+        matcher: Matcher {
+            ignore_case: true,
+            ignore_punctuation: true,
+        },
+        exam: false,
+        self_grade: false,
+        interleave: false,
+        ask_confidence: false,
+        media_dir: PathBuf::new(),
+        weights: Weights::default(),
+        tts: None,
+    };
+    for exercise in Exercise::examples() {
+        print!("{}", exercise);
+        println!("  In a quiz:");
+        let prompt = quiz::preview(&exercise, 0, &mut config);
+        println!("    {}", prompt.replace('\n', "\n    "));
+        println!();
+    }
+    Ok(())
+}
+
+/// Reports pairs of questions whose texts are nearly identical so they can be merged.
+fn execute_find_similar(args: &Args) -> Result<(), Box<dyn std::error::Error>> {
+    let threshold: f64 = match args.value("--threshold") {
+        Some(t) => t.parse()?,
+        None => 0.8,
+    };
+    if !(0.0..=1.0).contains(&threshold) {
+        return Err("--threshold must be between 0 and 1".into());
+    }
+
+    let Some(exercises) = load_deck(args)? else {
+        return Ok(());
+    };
+    let pairs = similar::find_similar(&exercises, threshold);
+    if args.flag("--json") {
+        return print_json(&pairs);
+    }
+
+    for pair in &pairs {
+        println!("{:.2}", pair.similarity);
+        for location in [&pair.first, &pair.second] {
+            println!(
+                "  [{}] {}. {}",
+                location.group, location.question, location.text
+            );
+        }
+    }
+    println!("{} similar pairs found", pairs.len());
+    Ok(())
+}
+
+/// Checks the option lettering of every stored multiple choice and matching question.
+fn execute_verify(args: &Args) -> Result<(), Box<dyn std::error::Error>> {
+    let Some(exercises) = load_deck(args)? else {
+        return Ok(());
+    };
+    let mut problems = verify::verify(&exercises);
+    problems.extend(verify::verify_questions(&exercises));
+    problems.extend(verify::verify_links(&exercises));
+    problems.extend(verify::verify_media(
+        &exercises,
+        &media::media_dir(&data_file(args)?),
+    ));
+    problems.sort_by_key(|p| (p.group, p.question));
+    if args.flag("--json") {
+        return print_json(&problems);
+    }
+
+    for problem in &problems {
         println!(
-            "Usage: {} --input",
-            args.get(0).unwrap_or(&"program".to_string())
+            "[{}] {}. {}",
+            problem.group, problem.question, problem.message
         );
     }
+    println!("{} problems found", problems.len());
+    Ok(())
 }
 
-fn execute_data() -> Result<(), Box<dyn std::error::Error>> {
-    let file_path = "data.json";
-    let new_exercises = exercise::Exercise::read();
+/// Checks a data file, the one given after `validate` or else the usual one, and reports
+/// every problem with its JSON path.
+///
+/// The process exits with status 1 if the file can't be read or has any problem, so that
+/// scripts can use the check.
+fn execute_validate(args: &Args) -> Result<(), Box<dyn std::error::Error>> {
+    /// A problem as printed by `--json`.
+    #[derive(Serialize)]
+    struct Located {
+        path: String,
+        message: String,
+    }
+
+    let file = match args.positional(1) {
+        Some(file) => file.to_string(),
+        None => data_file(args)?,
+    };
+    let problems = if !Path::new(&file).exists() {
+        vec![Located {
+            path: "$".to_string(),
+            message: format!("{} does not exist", file),
+        }]
+    } else {
+        match open_storage_at(args, &file)?.load() {
+            Err(e) => vec![Located {
+                path: "$".to_string(),
+                message: e.to_string(),
+            }],
+            Ok(exercises) => {
+                let mut problems = verify::verify(&exercises);
+                problems.extend(verify::verify_questions(&exercises));
+                problems.extend(verify::verify_links(&exercises));
+                problems.sort_by_key(|p| (p.group, p.question));
+                problems
+                    .into_iter()
+                    .map(|p| Located {
+                        path: p.json_path(),
+                        message: p.message,
+                    })
+                    .collect()
+            }
+        }
+    };
 
-    let all_exercises: Vec<exercise::Exercise> = if Path::new(file_path).exists() {
-        // Read the file contents
-        let file_content = fs::read_to_string(file_path)?;
-        // Deserialize existing data or propagate any serde errors
-        let mut existing: Vec<exercise::Exercise> = serde_json::from_str(&file_content)?;
-        // Append the new exercises
-        existing.extend(new_exercises);
-        existing
+    if args.flag("--json") {
+        print_json(&problems)?;
     } else {
-        new_exercises
+        for problem in &problems {
+            println!("{}: {}", problem.path, problem.message);
+        }
+        if problems.is_empty() {
+            println!("{} is valid", file);
+        } else {
+            println!("{} problems found in {}", problems.len(), file);
+        }
+    }
+    if !problems.is_empty() {
+        process::exit(1);
+    }
+    Ok(())
+}
+
+/// Merges the data file given after `merge` into the deck and saves the result.
+///
+/// With `--strict`, nothing is saved if any question of the merged file is invalid or
+/// would be skipped; the first such question is reported instead.
+fn execute_merge(args: &Args) -> Result<(), Box<dyn std::error::Error>> {
+    let other_file = args
+        .positional(1)
+        .ok_or("merge needs the data file to merge in")?;
+    if !Path::new(other_file).exists() {
+        return Err(format!("{} does not exist", other_file).into());
+    }
+    let other = open_storage_at(args, other_file)?.load()?;
+    let strict = args.flag("--strict");
+    if strict {
+        let mut problems = verify::verify(&other);
+        problems.extend(verify::verify_questions(&other));
+        if let Some(problem) = problems.iter().min_by_key(|p| (p.group, p.question)) {
+            return Err(format!(
+                "Nothing merged: [{}] {}. of {} is invalid: {}",
+                problem.group, problem.question, other_file, problem.message
+            )
+            .into());
+        }
+    }
+    let storage = open_storage(args)?;
+    let mut exercises = storage.load()?;
+
+    let report = merge::merge(&mut exercises, other);
+    if let Some(skipped) = report.skipped.first().filter(|_| strict) {
+        let question = &skipped.question;
+        return Err(format!(
+            "Nothing merged: [{}] {}. {} of {} would be skipped ({})",
+            question.group, question.question, question.text, other_file, skipped.reason
+        )
+        .into());
+    }
+    if report.merged > 0 {
+        storage.save(&exercises)?;
+    }
+    if args.flag("--json") {
+        return print_json(&report);
+    }
+
+    for skipped in &report.skipped {
+        let question = &skipped.question;
+        println!(
+            "Skipped [{}] {}. {} ({})",
+            question.group, question.question, question.text, skipped.reason
+        );
+    }
+    println!(
+        "Merged {} questions from {}, skipped {}",
+        report.merged,
+        other_file,
+        report.skipped.len()
+    );
+    Ok(())
+}
+
+/// Writes the deck of the first file given after `--merge-stats` to `--output`, with the
+/// stats of the same questions in the second file combined into it, for a deck studied
+/// on two devices.
+fn execute_merge_stats(args: &Args) -> Result<(), Box<dyn std::error::Error>> {
+    let [file, other_file] = args.values("--merge-stats")[..] else {
+        return Err("--merge-stats needs the two data files to combine".into());
+    };
+    let output = args
+        .value("--output")
+        .ok_or("--merge-stats needs --output <file> to write the combined deck to")?;
+    for path in [file, other_file] {
+        if !Path::new(path).exists() {
+            return Err(format!("{} does not exist", path).into());
+        }
+    }
+    let mut exercises = open_storage_at(args, file)?.load()?;
+    let other = open_storage_at(args, other_file)?.load()?;
+
+    let report = merge::merge_stats(&mut exercises, other);
+    let _lock = lock_output(args, output)?;
+    open_storage_at(args, output)?.save(&exercises)?;
+    if args.flag("--json") {
+        return print_json(&report);
+    }
+
+    for conflict in &report.conflicts {
+        println!(
+            "Warning: {} differs between the files; kept {} over {}",
+            conflict.id, conflict.kept, conflict.dropped
+        );
+    }
+    println!(
+        "Combined the stats of {} questions and added {} from {}; wrote {}",
+        report.combined, report.added, other_file, output
+    );
+    Ok(())
+}
+
+/// Removes repeated questions from the deck.
+///
+/// Of every set of duplicates the copy with the most history is kept. When the copies
+/// differ beyond their text, e.g. in their answers, the user picks the copy to keep or
+/// keeps them all.
+fn execute_dedupe(args: &Args) -> Result<(), Box<dyn std::error::Error>> {
+    let Some(mut exercises) = load_deck(args)? else {
+        return Ok(());
+    };
+
+    let mut removed = HashSet::new();
+    let mut replaced_by = HashMap::new();
+    for duplicates in dedupe::find(&exercises) {
+        let question = |i: usize| {
+            let (g, q) = duplicates.positions[i];
+            exercises[g].questions()[q]
+        };
+        let keep = if duplicates.ambiguous {
+            println!("These questions differ:");
+            let mut choices: Vec<String> = (0..duplicates.positions.len())
+                .map(|i| {
+                    let (g, q) = duplicates.positions[i];
+                    format!(
+                        "[{}] {}. {} ({} replies)",
+                        g + 1,
+                        q + 1,
+                        question(i),
+                        question(i).meta().history.len()
+                    )
+                })
+                .collect();
+            choices.push("Keep all of them".to_string());
+            let keep_all = choices.len() - 1;
+            match Select::new("Which one should be kept?", choices)
+                .with_starting_cursor(duplicates.keep)
+                .raw_prompt()?
+                .index
+            {
+                i if i == keep_all => continue,
+                i => i,
+            }
+        } else {
+            duplicates.keep
+        };
+
+        let (kept_group, kept_question) = duplicates.positions[keep];
+        let kept_id = question(keep).meta().id.clone();
+        for (i, &(g, q)) in duplicates.positions.iter().enumerate() {
+            if i != keep {
+                println!(
+                    "Removed [{}] {}. {} (a copy of [{}] {}.)",
+                    g + 1,
+                    q + 1,
+                    question(i),
+                    kept_group + 1,
+                    kept_question + 1
+                );
+                removed.insert((g, q));
+                replaced_by.insert(question(i).meta().id.clone(), kept_id.clone());
+            }
+        }
+    }
+
+    if removed.is_empty() {
+        println!("No duplicate questions to remove");
+        return Ok(());
+    }
+    dedupe::remove(&mut exercises, &removed, &replaced_by);
+    open_storage(args)?.save(&exercises)?;
+    println!("Removed {} duplicate questions", removed.len());
+    Ok(())
+}
+
+/// Folds the log of batches appended with `--storage jsonl` into the data file, leaving
+/// it in the same form as a deck that was saved whole.
+fn execute_compact(args: &Args) -> Result<(), Box<dyn std::error::Error>> {
+    let file = data_file(args)?;
+    let log = storage::log_path(&file);
+    if !Path::new(&log).exists() {
+        println!("{} has no appended exercises to fold", file);
+        return Ok(());
+    }
+    let batches = storage::logged_batches(&log)?;
+    let storage = open_storage(args)?;
+    storage.save(&storage.load()?)?;
+    println!("Folded {} batches from {} into {}", batches, log, file);
+    Ok(())
+}
+
+/// Writes the deck to another data file in the format its name calls for, e.g. `.msgpack`
+/// for MessagePack or `.json` for JSON, leaving the data file as it is.
+fn execute_convert(args: &Args) -> Result<(), Box<dyn std::error::Error>> {
+    let target = args
+        .positional(1)
+        .ok_or("convert needs the file to write the deck to")?;
+    if Path::new(target).exists() {
+        return Err(format!("{} already exists; convert to a new file", target).into());
+    }
+    let file = data_file(args)?;
+    let exercises = open_storage(args)?.load()?;
+    let _lock = lock_output(args, target)?;
+    storage::open(target, None, 0)?.save(&exercises)?;
+
+    let questions: usize = exercises.iter().map(|e| e.questions().len()).sum();
+    println!(
+        "Converted {} questions from {} to {}",
+        questions, file, target
+    );
+    Ok(())
+}
+
+/// Prints totals and charts gathered from the history of every stored question, and the
+/// review streak.
+///
+/// With `--csv`, the history is exported one attempt per row instead, and with `--hardest`
+/// the most often missed questions are listed. `--slowest` lists the questions that take
+/// longest to answer right, and `--bank` counts the questions in the deck.
+fn execute_stats(args: &Args) -> Result<(), Box<dyn std::error::Error>> {
+    let Some(exercises) = load_deck(args)? else {
+        return Ok(());
+    };
+    if args.flag("--hardest") {
+        return print_hardest(args, &exercises);
+    }
+    if args.flag("--slowest") {
+        return print_slowest(args, &exercises);
+    }
+    if args.flag("--bank") {
+        let bank = stats::bank(&exercises);
+        if args.flag("--json") {
+            return print_json(&bank);
+        }
+        print!("{}", bank);
+        return Ok(());
+    }
+    if let Some(path) = args.value("--csv") {
+        return Ok(stats::write_csv(
+            &exercises,
+            BufWriter::new(fs::File::create(path)?),
+        )?);
+    }
+    let today = Date::today();
+    let mut stats = stats::stats(&exercises, today);
+    let streak = Streak::load(&streak::streak_file(&data_file(args)?))?;
+    stats.current_streak = streak.current(today);
+    stats.longest_streak = streak.longest();
+    stats.goal = Goal::load(&goal::goal_file(&data_file(args)?))?;
+    if args.flag("--json") {
+        return print_json(&stats);
+    }
+    print!("{}", stats);
+    Ok(())
+}
+
+/// Prints the `--limit` questions with the highest miss rate, or JSON with `--json`.
+fn print_hardest(args: &Args, exercises: &[Exercise]) -> Result<(), Box<dyn std::error::Error>> {
+    let limit = match args.value("--limit") {
+        Some(n) => n.parse()?,
+        None => 20,
+    };
+    let min_attempts = match args.value("--min-attempts") {
+        Some(n) => n.parse()?,
+        None => 3,
+    };
+    let mut hardest = stats::hardest(exercises, min_attempts);
+    hardest.truncate(limit);
+    if args.flag("--json") {
+        return print_json(&hardest);
+    }
+
+    if hardest.is_empty() {
+        println!("No questions with at least {} attempts", min_attempts);
+    }
+    for hard in &hardest {
+        println!(
+            "{:>3.0}% [{}] {}. {} ({}, missed {} of {})",
+            hard.miss_rate(),
+            hard.location.group,
+            hard.location.question,
+            hard.location.text,
+            hard.kind,
+            hard.missed,
+            hard.attempts
+        );
+        if !hard.tags.is_empty() {
+            println!("     tags: {}", hard.tags.join(", "));
+        }
+    }
+    Ok(())
+}
+
+/// Prints a one-screen overview combining the review queue, the streak, the weekly
+/// accuracy, the hardest questions and the size of the deck.
+fn execute_dashboard(args: &Args) -> Result<(), Box<dyn std::error::Error>> {
+    let Some(exercises) = load_deck(args)? else {
+        return Ok(());
+    };
+    let today = Date::today();
+
+    let queue = scheduler::review_queue(&exercises, today, &daily_limits(args)?);
+    println!(
+        "Due today: {} questions, {} carried over",
+        queue.positions.len(),
+        queue.carried_over
+    );
+
+    let streak = Streak::load(&streak::streak_file(&data_file(args)?))?;
+    println!(
+        "Streak: {} days, longest {} days",
+        streak.current(today),
+        streak.longest()
+    );
+
+    let bank = stats::bank(&exercises);
+    println!(
+        "Deck: {} questions, {} never practiced",
+        bank.questions, bank.unpracticed
+    );
+
+    println!("Accuracy per week:");
+    let rows: Vec<(String, f64, String)> = stats::weekly_accuracy(&exercises, today, 8)
+        .iter()
+        .map(|week| match week.accuracy() {
+            Some(accuracy) => (
+                week.start.to_string(),
+                accuracy,
+                format!("{:.0}% of {}", accuracy, week.attempts),
+            ),
+            None => (week.start.to_string(), 0.0, "-".to_string()),
+        })
+        .collect();
+    print!("{}", chart::bar_chart(&rows, 100.0, 40));
+
+    let hardest = stats::hardest(&exercises, 3);
+    if !hardest.is_empty() {
+        println!("Hardest:");
+        for hard in hardest.iter().take(5) {
+            println!(
+                "  {:>3.0}% [{}] {}. {}",
+                hard.miss_rate(),
+                hard.location.group,
+                hard.location.question,
+                hard.location.text
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Prints the `--limit` questions with the slowest correct replies, or JSON with `--json`.
+fn print_slowest(args: &Args, exercises: &[Exercise]) -> Result<(), Box<dyn std::error::Error>> {
+    let limit = match args.value("--limit") {
+        Some(n) => n.parse()?,
+        None => 20,
+    };
+    let min_correct = match args.value("--min-attempts") {
+        Some(n) => n.parse()?,
+        None => 3,
+    };
+    let mut slowest = stats::slowest(exercises, min_correct);
+    slowest.truncate(limit);
+    if args.flag("--json") {
+        return print_json(&slowest);
+    }
+
+    if slowest.is_empty() {
+        println!("No questions answered right at least {} times", min_correct);
+    }
+    for latency in &slowest {
+        println!(
+            "{:>5.1}s median, {:.1}s p90, {:.1}s average [{}] {}. {} ({}, {} correct)",
+            latency.median_millis as f64 / 1000.0,
+            latency.p90_millis as f64 / 1000.0,
+            latency.average_millis as f64 / 1000.0,
+            latency.location.group,
+            latency.location.question,
+            latency.location.text,
+            latency.kind,
+            latency.correct
+        );
+    }
+    Ok(())
+}
+
+/// Sets the daily goal that quiz and review sessions report progress towards.
+fn execute_set_goal(args: &Args) -> Result<(), Box<dyn std::error::Error>> {
+    let Some(value) = args.value("--set-goal") else {
+        return Err("--set-goal needs a goal like 30 or 15m, or off".into());
+    };
+    let goal_file = goal::goal_file(&data_file(args)?);
+    let _lock = lock_output(args, &goal_file.to_string_lossy())?;
+    if value == "off" {
+        if goal_file.exists() {
+            fs::remove_file(&goal_file)?;
+        }
+        println!("Daily goal cleared");
+        return Ok(());
+    }
+
+    let goal: Goal = value.parse()?;
+    goal.save(&goal_file)?;
+    println!("Daily goal set to {} a day", goal);
+    Ok(())
+}
+
+/// Sets the difficulty of every question picked by `--filter` and `--type`, or clears it
+/// with `none`.
+fn execute_set_difficulty(args: &Args) -> Result<(), Box<dyn std::error::Error>> {
+    let difficulty = match args.value("--set-difficulty") {
+        Some("none") => None,
+        Some(value) => Some(value.parse::<Difficulty>()?),
+        None => return Err("--set-difficulty needs easy, medium, hard or none".into()),
+    };
+    let Some(mut exercises) = load_deck(args)? else {
+        return Ok(());
+    };
+
+    let positions = selected_positions(args, &exercises)?;
+    let now = unix_now();
+    for &(g, q) in &positions {
+        let mut questions = exercises[g].questions_mut();
+        let meta = questions[q].meta_mut();
+        meta.difficulty = difficulty;
+        meta.modified = Some(now);
+    }
+    open_storage(args)?.save(&exercises)?;
+
+    match difficulty {
+        Some(difficulty) => println!("Rated {} questions as {}", positions.len(), difficulty),
+        None => println!("Cleared the difficulty of {} questions", positions.len()),
+    }
+    Ok(())
+}
+
+/// Copies the deck and every media file it refers to into the directory given to `--pack`.
+fn execute_pack(args: &Args) -> Result<(), Box<dyn std::error::Error>> {
+    let target = args
+        .value("--pack")
+        .ok_or("--pack needs the directory to copy the deck into")?;
+    let Some(exercises) = load_deck(args)? else {
+        return Ok(());
     };
 
-    // Serialize the updated data into pretty JSON.
-    let json = serde_json::to_string_pretty(&all_exercises)?;
-    // Write the JSON data back to the file.
-    fs::write(file_path, json)?;
+    let file = data_file(args)?;
+    let copied = media::pack(&file, &exercises, Path::new(target))?;
+    println!("Packed {} and {} media files into {}", file, copied, target);
+    Ok(())
+}
+
+/// Lets the user change the explanation, pronunciation, mnemonic or links of one question,
+/// or suspend it, found by the ID `--list --show-source` shows or the start of it.
+fn execute_edit(args: &Args) -> Result<(), Box<dyn std::error::Error>> {
+    let id = args
+        .value("--edit")
+        .ok_or("--edit needs the ID of the question to change")?;
+    let Some(mut exercises) = load_deck(args)? else {
+        return Ok(());
+    };
+
+    let (g, q) = exercise::find_question(&exercises, id)?;
+    match edit::edit(exercises[g].questions_mut()[q]) {
+        Ok(true) => open_storage(args)?.save(&exercises)?,
+        Ok(false) => println!("Nothing changed"),
+        Err(InquireError::OperationCanceled | InquireError::OperationInterrupted) => {
+            println!("Quit without saving")
+        }
+        Err(e) => return Err(e.into()),
+    }
+    Ok(())
+}
+
+/// Runs one of the `deck` subcommands, which manage the named decks in `deck::deck_dir`.
+fn execute_deck(args: &Args) -> Result<(), Box<dyn std::error::Error>> {
+    let name = args.positional(2);
+    match (args.positional(1), name) {
+        (Some("new"), Some(name)) => {
+            let _lock = lock_output(args, &deck::path(name)?)?;
+            deck::create(name)?;
+            println!(
+                "Created deck '{}'; add to it with --input --deck {}",
+                name, name
+            );
+        }
+        (Some("list"), _) => {
+            let names = deck::names()?;
+            if names.is_empty() {
+                println!("No decks yet \u{2014} create one with `deck new <name>`");
+            }
+            for name in names {
+                let count: usize = storage::open(&deck::path(&name)?, None, 0)?
+                    .load()?
+                    .iter()
+                    .map(|e| e.questions().len())
+                    .sum();
+                println!("{:<20} {} questions", name, count);
+            }
+        }
+        (Some("delete"), Some(name)) => {
+            let _lock = lock_output(args, &deck::existing(name)?)?;
+            let prompt = format!("Delete deck '{}' and all its questions?", name);
+            if Confirm::new(&prompt).with_default(false).prompt()? {
+                deck::delete(name)?;
+                println!("Deleted deck '{}'", name);
+            }
+        }
+        (Some("rename"), Some(name)) => {
+            let to = args
+                .positional(3)
+                .ok_or("deck rename needs the old and the new name")?;
+            let _source_lock = lock_output(args, &deck::existing(name)?)?;
+            let _target_lock = lock_output(args, &deck::path(to)?)?;
+            deck::rename(name, to)?;
+            println!("Renamed deck '{}' to '{}'", name, to);
+        }
+        (Some(command @ ("new" | "delete" | "rename")), None) => {
+            return Err(format!("deck {} needs the name of the deck", command).into());
+        }
+        _ => return Err("Use deck new, deck list, deck delete or deck rename".into()),
+    }
+    Ok(())
+}
+
+/// Encrypts the data file and its backups with a new passphrase.
+fn execute_encrypt(args: &Args) -> Result<(), Box<dyn std::error::Error>> {
+    let file = data_file(args)?;
+    storage::encrypt(&file)?;
+    println!(
+        "Encrypted {}; keep the passphrase safe, the deck can't be read without it",
+        file
+    );
+    Ok(())
+}
+
+/// Decrypts the data file and its backups.
+fn execute_decrypt(args: &Args) -> Result<(), Box<dyn std::error::Error>> {
+    let file = data_file(args)?;
+    storage::decrypt(&file)?;
+    println!("Decrypted {}", file);
+    Ok(())
+}
+
+/// Runs one of the `backup` subcommands; `backup list` is the only one so far.
+fn execute_backup(args: &Args) -> Result<(), Box<dyn std::error::Error>> {
+    if args.positional(1) != Some("list") {
+        return Err("Use backup list".into());
+    }
+    let file = data_file(args)?;
+    let backups = backup::list(&file)?;
+    if backups.is_empty() {
+        println!("No backups of {} yet", file);
+    }
+    for path in backups {
+        let size = fs::metadata(&path)?.len();
+        println!("{}  ({} bytes)", path.display(), size);
+    }
+    Ok(())
+}
+
+/// Prints every question connected to the one with the ID given to `--related` through
+/// links, in either direction, each followed by the questions it links to.
+fn execute_related(args: &Args) -> Result<(), Box<dyn std::error::Error>> {
+    let id = args
+        .value("--related")
+        .ok_or("--related needs the ID of a question")?;
+    let Some(exercises) = load_deck(args)? else {
+        return Ok(());
+    };
+
+    let start = exercise::find_question(&exercises, id)?;
+    for (g, q) in exercise::related_questions(&exercises, start) {
+        let question = &exercises[g].questions()[q];
+        println!(
+            "[{}] {}. {} ({})",
+            g + 1,
+            q + 1,
+            question.text(),
+            question.meta().id
+        );
+        for link in &question.meta().links {
+            match exercise::find_question(&exercises, link) {
+                Ok((lg, lq)) => println!("    -> {}", exercises[lg].questions()[lq].text()),
+                Err(_) => println!("    -> {} (missing)", link),
+            }
+        }
+    }
     Ok(())
 }
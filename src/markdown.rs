@@ -0,0 +1,63 @@
+use std::env;
+use std::io::{self, IsTerminal};
+use std::sync::OnceLock;
+
+/// Terminal escape codes used for the supported Markdown styles.
+const BOLD: (&str, &str) = ("\x1b[1m", "\x1b[22m");
+const ITALIC: (&str, &str) = ("\x1b[3m", "\x1b[23m");
+
+/// Checks whether styled output should be produced.
+///
+/// Styling is only used when stdout is a terminal and `NO_COLOR` is not set, so that
+/// piped or redirected output stays plain text.
+fn styled() -> bool {
+    static STYLED: OnceLock<bool> = OnceLock::new();
+    *STYLED.get_or_init(|| io::stdout().is_terminal() && env::var_os("NO_COLOR").is_none())
+}
+
+/// Renders the small Markdown subset allowed in exercise text.
+///
+/// `**bold**` and `*italic*` become terminal styles (or are stripped when output is not
+/// a terminal), and `<br>` becomes a line break. Single underscores are left alone since
+/// they mark the blank in fill-in-the-blank sentences.
+pub fn render(text: &str) -> String {
+    render_with(text, styled()).replace("<br>", "\n")
+}
+
+/// Renders emphasis markers, either as terminal styles or by stripping them.
+fn render_with(text: &str, styled: bool) -> String {
+    let mut out = String::new();
+    let mut rest = text;
+
+    while let Some(pos) = rest.find('*') {
+        out.push_str(&rest[..pos]);
+        let (marker, style) = if rest[pos..].starts_with("**") {
+            ("**", BOLD)
+        } else {
+            ("*", ITALIC)
+        };
+        let after = &rest[pos + marker.len()..];
+
+        match after.find(marker) {
+            // Only treat the marker as emphasis when it is closed and encloses something.
+            Some(end) if end > 0 => {
+                let inner = render_with(&after[..end], styled);
+                if styled {
+                    out.push_str(style.0);
+                    out.push_str(&inner);
+                    out.push_str(style.1);
+                } else {
+                    out.push_str(&inner);
+                }
+                rest = &after[end + marker.len()..];
+            }
+            _ => {
+                out.push_str(marker);
+                rest = after;
+            }
+        }
+    }
+
+    out.push_str(rest);
+    out
+}
@@ -0,0 +1,100 @@
+use crate::exercise::Exercise;
+use crate::storage;
+use std::collections::BTreeSet;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// The directory, next to the data file, that attached images and other media are kept in.
+pub const MEDIA_DIR: &str = "media";
+
+/// Returns the media directory belonging to `data_file`.
+pub fn media_dir(data_file: &str) -> PathBuf {
+    Path::new(data_file)
+        .parent()
+        .unwrap_or(Path::new(""))
+        .join(MEDIA_DIR)
+}
+
+/// Copies a file into the media directory so the deck doesn't depend on where it was.
+///
+/// A file that is already in the media directory is used as it is. Otherwise it keeps its
+/// name, with a number added when a different file of that name is already stored.
+///
+/// # Returns
+///
+/// The name the file is stored under, relative to the media directory.
+pub fn import(dir: &Path, source: &Path) -> io::Result<String> {
+    let name = source
+        .file_name()
+        .and_then(|n| n.to_str())
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "not a file name"))?;
+    if source.parent() == Some(dir) && source.is_file() {
+        return Ok(name.to_string());
+    }
+
+    let content = fs::read(source)?;
+    fs::create_dir_all(dir)?;
+    let stem = Path::new(name)
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or(name);
+    let extension = Path::new(name).extension().and_then(|e| e.to_str());
+
+    for n in 1.. {
+        let candidate = match (n, extension) {
+            (1, _) => name.to_string(),
+            (_, Some(ext)) => format!("{}-{}.{}", stem, n, ext),
+            (_, None) => format!("{}-{}", stem, n),
+        };
+        let target = dir.join(&candidate);
+        match fs::read(&target) {
+            Ok(existing) if existing == content => return Ok(candidate),
+            Ok(_) => continue,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                fs::write(&target, &content)?;
+                return Ok(candidate);
+            }
+            Err(e) => return Err(e),
+        }
+    }
+    unreachable!("the candidate loop only ends by returning")
+}
+
+/// Returns the name of every media file the deck refers to, each once.
+pub fn referenced(exercises: &[Exercise]) -> BTreeSet<String> {
+    exercises
+        .iter()
+        .flat_map(|e| e.questions())
+        .filter_map(|q| q.meta().media.clone())
+        .collect()
+}
+
+/// Copies the data file and every media file it refers to into `target`, so the deck can
+/// be moved or shared as one directory.
+///
+/// # Returns
+///
+/// The number of media files copied, or an error naming the file that couldn't be copied.
+pub fn pack(data_file: &str, exercises: &[Exercise], target: &Path) -> Result<usize, String> {
+    let from = media_dir(data_file);
+    let to = target.join(MEDIA_DIR);
+    let names = referenced(exercises);
+    fs::create_dir_all(&to).map_err(|e| format!("{}: {}", to.display(), e))?;
+
+    let file_name = Path::new(data_file)
+        .file_name()
+        .unwrap_or(data_file.as_ref());
+    fs::copy(data_file, target.join(file_name)).map_err(|e| format!("{}: {}", data_file, e))?;
+    // Exercises not yet folded into the data file have to come along too.
+    let log = storage::log_path(data_file);
+    if Path::new(&log).exists() {
+        let log_name = Path::new(&log).file_name().unwrap_or(log.as_ref());
+        fs::copy(&log, target.join(log_name)).map_err(|e| format!("{}: {}", log, e))?;
+    }
+    for name in &names {
+        let source = from.join(name);
+        fs::copy(&source, to.join(name)).map_err(|e| format!("{}: {}", source.display(), e))?;
+    }
+    Ok(names.len())
+}
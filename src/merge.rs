@@ -0,0 +1,178 @@
+use crate::exercise::Exercise;
+use crate::history::Attempt;
+use crate::scheduler::Schedule;
+use crate::similar::{normalize, Location};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+
+/// A question of the merged file that was left out, and why.
+#[derive(Serialize)]
+pub struct Skipped {
+    /// Where the question is in the merged file.
+    pub question: Location,
+    pub reason: String,
+}
+
+/// What `merge` did.
+#[derive(Serialize)]
+pub struct MergeReport {
+    /// How many questions were added.
+    pub merged: usize,
+    pub skipped: Vec<Skipped>,
+}
+
+/// Adds the questions of another deck to `exercises`, leaving out those already in it.
+///
+/// A question counts as already present when a question has the same ID, or the same
+/// exercise type and the same text once case, punctuation and spacing are ignored. This
+/// also applies between the questions of `other` themselves. The questions that are added
+/// keep their groups, which are appended after the existing ones.
+pub fn merge(exercises: &mut Vec<Exercise>, other: Vec<Exercise>) -> MergeReport {
+    let mut by_id: HashMap<String, String> = HashMap::new();
+    let mut by_text: HashMap<(&'static str, String), String> = HashMap::new();
+    for (g, exercise) in exercises.iter().enumerate() {
+        let kind: &'static str = exercise.into();
+        for (q, question) in exercise.questions().into_iter().enumerate() {
+            let place = format!("[{}] {}.", g + 1, q + 1);
+            by_id.insert(question.meta().id.clone(), place.clone());
+            by_text.insert((kind, normalize(&question.text())), place);
+        }
+    }
+
+    let mut report = MergeReport {
+        merged: 0,
+        skipped: Vec::new(),
+    };
+    for (g, mut exercise) in other.into_iter().enumerate() {
+        let kind: &'static str = (&exercise).into();
+        // Added questions are numbered as they will be once merged.
+        let group_number = exercises.len() + 1;
+        let mut kept = 0;
+        let mut q = 0;
+        exercise.retain_questions(|question| {
+            q += 1;
+            let text = question.text();
+            let key = (kind, normalize(&text));
+            let reason = if let Some(place) = by_id.get(&question.meta().id) {
+                format!("same ID as {}", place)
+            } else if let Some(place) = by_text.get(&key) {
+                format!("same question as {}", place)
+            } else {
+                kept += 1;
+                let place = format!("[{}] {}.", group_number, kept);
+                by_id.insert(question.meta().id.clone(), place.clone());
+                by_text.insert(key, place);
+                report.merged += 1;
+                return true;
+            };
+            report.skipped.push(Skipped {
+                question: Location {
+                    group: g + 1,
+                    question: q,
+                    text,
+                },
+                reason,
+            });
+            false
+        });
+        if exercise.question_count() > 0 {
+            exercises.push(exercise);
+        }
+    }
+    report
+}
+
+/// A question found in both files of `merge_stats` whose content differs between them.
+#[derive(Serialize)]
+pub struct Conflict {
+    pub id: String,
+    /// The question as the first file has it, which is kept.
+    pub kept: String,
+    /// The question as the other file has it.
+    pub dropped: String,
+}
+
+/// What `merge_stats` did.
+#[derive(Serialize)]
+pub struct StatsReport {
+    /// How many questions were in both files and had their stats combined.
+    pub combined: usize,
+    /// How many questions only the other file had, which were added.
+    pub added: usize,
+    pub conflicts: Vec<Conflict>,
+}
+
+/// Combines the stats of the same deck studied in two places into `exercises`.
+///
+/// Questions are matched by ID. A matched question gets the replies of both histories,
+/// each reply counted once even if both copies have it, and the schedule that is due
+/// latest. Its content is taken from `exercises`, and reported as a conflict if the other
+/// copy differs. Questions only `other` has are added in their groups, after the existing
+/// ones.
+pub fn merge_stats(exercises: &mut Vec<Exercise>, other: Vec<Exercise>) -> StatsReport {
+    let mut by_id: HashMap<String, (usize, usize)> = HashMap::new();
+    for (g, exercise) in exercises.iter().enumerate() {
+        for (q, question) in exercise.questions().into_iter().enumerate() {
+            by_id.insert(question.meta().id.clone(), (g, q));
+        }
+    }
+
+    let mut report = StatsReport {
+        combined: 0,
+        added: 0,
+        conflicts: Vec::new(),
+    };
+    let mut stats: HashMap<(usize, usize), (Vec<Attempt>, Option<Schedule>)> = HashMap::new();
+    for mut exercise in other {
+        let kind: &'static str = (&exercise).into();
+        exercise.retain_questions(|question| {
+            let meta = question.meta();
+            let Some(&(g, q)) = by_id.get(&meta.id) else {
+                report.added += 1;
+                return true;
+            };
+            report.combined += 1;
+            let ours = &exercises[g];
+            let kept = format!("[{}] {}", <&'static str>::from(ours), ours.questions()[q]);
+            let dropped = format!("[{}] {}", kind, question);
+            if kept != dropped {
+                report.conflicts.push(Conflict {
+                    id: meta.id.clone(),
+                    kept,
+                    dropped,
+                });
+            }
+            stats.insert((g, q), (meta.history.clone(), meta.schedule.clone()));
+            false
+        });
+        if exercise.question_count() > 0 {
+            exercises.push(exercise);
+        }
+    }
+
+    for (g, exercise) in exercises.iter_mut().enumerate() {
+        for (q, question) in exercise.questions_mut().into_iter().enumerate() {
+            let Some((history, schedule)) = stats.remove(&(g, q)) else {
+                continue;
+            };
+            let meta = question.meta_mut();
+            let mut seen: HashSet<(u64, bool, u64)> = meta
+                .history
+                .iter()
+                .map(|a| (a.time, a.correct, a.millis))
+                .collect();
+            for attempt in history {
+                if seen.insert((attempt.time, attempt.correct, attempt.millis)) {
+                    meta.history.push(attempt);
+                }
+            }
+            meta.history.sort_by_key(|a| a.time);
+            if let Some(schedule) = schedule {
+                if meta.schedule.as_ref().is_none_or(|s| s.due < schedule.due) {
+                    meta.schedule = Some(schedule);
+                }
+            }
+        }
+    }
+    report
+}
@@ -0,0 +1,143 @@
+use serde_json::{json, Value};
+
+/// The layout version written to the `version` field of every saved data file.
+pub const CURRENT_VERSION: u64 = MIGRATIONS.len() as u64;
+
+/// Upgrades a data file from one layout version to the next; the function at index `i`
+/// turns version `i` into version `i + 1`.
+///
+/// Version 0 is the bare array of exercise groups written before files were versioned.
+/// A change to the saved layout adds a function here, which also raises `CURRENT_VERSION`.
+const MIGRATIONS: [fn(Value) -> Result<Value, String>; 2] = [wrap_bare_array, assign_ids];
+
+/// Version 0 to 1: moves the array of exercise groups into a versioned document.
+fn wrap_bare_array(doc: Value) -> Result<Value, String> {
+    match doc {
+        Value::Array(exercises) => Ok(json!({ "version": 1, "exercises": exercises })),
+        _ => Err("expected an array of exercises".to_string()),
+    }
+}
+
+/// Version 1 to 2: gives every question without an ID one derived from its content.
+///
+/// The ID is a hash of the question, its exercise type and its position, so that loading
+/// the same file twice gives the same IDs even before it has been saved again, while
+/// identical questions in different places still get different ones.
+fn assign_ids(mut doc: Value) -> Result<Value, String> {
+    let groups = doc
+        .get_mut("exercises")
+        .and_then(Value::as_array_mut)
+        .ok_or("expected an array of exercises")?;
+    for (g, group) in groups.iter_mut().enumerate() {
+        let kind = group
+            .get("type")
+            .and_then(Value::as_str)
+            .unwrap_or_default();
+        let kind = kind.to_string();
+        let Some(questions) = group.get_mut("data").and_then(Value::as_array_mut) else {
+            continue;
+        };
+        for (q, question) in questions.iter_mut().enumerate() {
+            let Some(fields) = question.as_object_mut() else {
+                continue;
+            };
+            if fields.contains_key("id") {
+                continue;
+            }
+            let content = serde_json::to_string(&fields).map_err(|e| e.to_string())?;
+            let key = format!("{}:{}:{}:{}", kind, g, q, content);
+            fields.insert(
+                "id".to_string(),
+                json!(format!("{:016x}", fnv1a(key.as_bytes()))),
+            );
+        }
+    }
+    doc["version"] = json!(2);
+    Ok(doc)
+}
+
+/// Returns the 64-bit FNV-1a hash of `data`, which is stable across runs and platforms.
+fn fnv1a(data: &[u8]) -> u64 {
+    data.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// Returns the layout version of a parsed data file.
+fn version_of(doc: &Value) -> Result<u64, String> {
+    match doc {
+        Value::Array(_) => Ok(0),
+        Value::Object(fields) => fields
+            .get("version")
+            .and_then(Value::as_u64)
+            .ok_or_else(|| "missing a numeric version field".to_string()),
+        _ => Err("expected an array or an object".to_string()),
+    }
+}
+
+/// Upgrades a parsed data file of any known version to `CURRENT_VERSION`.
+///
+/// # Returns
+///
+/// The upgraded document, or an error if it is of an unknown layout or was written by a
+/// newer version of the program.
+pub fn upgrade(mut doc: Value) -> Result<Value, String> {
+    let version = version_of(&doc)?;
+    if version > CURRENT_VERSION {
+        return Err(format!(
+            "the file has layout version {}, but this program only understands up to {}; \
+             update the program to open it",
+            version, CURRENT_VERSION
+        ));
+    }
+    for migration in &MIGRATIONS[version as usize..] {
+        doc = migration(doc)?;
+    }
+    Ok(doc)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn upgrade_gives_stable_distinct_ids_to_questions_without_one() {
+        let doc = json!({
+            "version": 1,
+            "exercises": [{
+                "type": "YesNo",
+                "data": [
+                    { "question": "A", "answer": true },
+                    { "question": "A", "answer": true },
+                    { "question": "B", "answer": false, "id": "kept" },
+                ],
+            }],
+        });
+        let first = upgrade(doc.clone()).unwrap();
+        let second = upgrade(doc).unwrap();
+        assert_eq!(first, second);
+        assert_eq!(first["version"], json!(CURRENT_VERSION));
+
+        let ids: Vec<&str> = first["exercises"][0]["data"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|q| q["id"].as_str().unwrap())
+            .collect();
+        assert_eq!(ids[0].len(), 16);
+        assert_ne!(ids[0], ids[1]);
+        assert_eq!(ids[2], "kept");
+    }
+
+    #[test]
+    fn upgrade_wraps_a_bare_array() {
+        let doc = upgrade(json!([{ "type": "YesNo", "data": [] }])).unwrap();
+        assert_eq!(doc["version"], json!(CURRENT_VERSION));
+        assert_eq!(doc["exercises"][0]["type"], json!("YesNo"));
+    }
+
+    #[test]
+    fn upgrade_rejects_newer_versions() {
+        assert!(upgrade(json!({ "version": CURRENT_VERSION + 1, "exercises": [] })).is_err());
+    }
+}
@@ -0,0 +1,491 @@
+use crate::node::Node;
+use serde::de::value::{BorrowedStrDeserializer, MapAccessDeserializer};
+use serde::de::{
+    self, Deserialize, DeserializeSeed, IntoDeserializer, MapAccess, SeqAccess, Visitor,
+};
+use std::fmt;
+
+/// How deeply arrays and maps may nest in a file being read, as in `serde_json`, so that a
+/// corrupt file can't exhaust the stack.
+const MAX_DEPTH: usize = 128;
+
+/// Checks whether `bytes` look like a MessagePack data file, i.e. start with a map (the
+/// versioned document) or an array (the bare array of older files).
+///
+/// JSON, YAML and TOML files start with text, which never begins with these bytes.
+pub fn is_msgpack(bytes: &[u8]) -> bool {
+    matches!(bytes.first(), Some(0x80..=0x9f | 0xdc..=0xdf))
+}
+
+/// Encodes a value as MessagePack, using the smallest encoding of every item.
+pub fn encode(value: &Node) -> Vec<u8> {
+    let mut out = Vec::new();
+    write(&mut out, value);
+    out
+}
+
+fn write(out: &mut Vec<u8>, value: &Node) {
+    match value {
+        Node::Null => out.push(0xc0),
+        Node::Bool(false) => out.push(0xc2),
+        Node::Bool(true) => out.push(0xc3),
+        Node::Number(n) => {
+            if let Some(n) = n.as_u64() {
+                write_uint(out, n);
+            } else if let Some(n) = n.as_i64() {
+                write_int(out, n);
+            } else {
+                out.push(0xcb);
+                out.extend(n.as_f64().unwrap_or_default().to_be_bytes());
+            }
+        }
+        Node::String(s) => write_str(out, s),
+        Node::Array(items) => {
+            write_header(out, items.len(), 0x90, 0xdc, 0xdd);
+            for item in items {
+                write(out, item);
+            }
+        }
+        Node::Object(fields) => {
+            write_header(out, fields.len(), 0x80, 0xde, 0xdf);
+            for (key, value) in fields {
+                write_str(out, key);
+                write(out, value);
+            }
+        }
+    }
+}
+
+fn write_uint(out: &mut Vec<u8>, n: u64) {
+    if n < 0x80 {
+        out.push(n as u8);
+    } else if n <= u8::MAX as u64 {
+        out.extend([0xcc, n as u8]);
+    } else if n <= u16::MAX as u64 {
+        out.push(0xcd);
+        out.extend((n as u16).to_be_bytes());
+    } else if n <= u32::MAX as u64 {
+        out.push(0xce);
+        out.extend((n as u32).to_be_bytes());
+    } else {
+        out.push(0xcf);
+        out.extend(n.to_be_bytes());
+    }
+}
+
+/// Writes a negative integer.
+fn write_int(out: &mut Vec<u8>, n: i64) {
+    if n >= -32 {
+        out.push(n as u8);
+    } else if n >= i8::MIN as i64 {
+        out.extend([0xd0, n as u8]);
+    } else if n >= i16::MIN as i64 {
+        out.push(0xd1);
+        out.extend((n as i16).to_be_bytes());
+    } else if n >= i32::MIN as i64 {
+        out.push(0xd2);
+        out.extend((n as i32).to_be_bytes());
+    } else {
+        out.push(0xd3);
+        out.extend(n.to_be_bytes());
+    }
+}
+
+fn write_str(out: &mut Vec<u8>, s: &str) {
+    let len = s.len();
+    if len < 32 {
+        out.push(0xa0 | len as u8);
+    } else if len <= u8::MAX as usize {
+        out.extend([0xd9, len as u8]);
+    } else if len <= u16::MAX as usize {
+        out.push(0xda);
+        out.extend((len as u16).to_be_bytes());
+    } else {
+        out.push(0xdb);
+        out.extend((len as u32).to_be_bytes());
+    }
+    out.extend(s.as_bytes());
+}
+
+/// Writes the header of an array or map of `len` items: the `fix` marker holding the
+/// length for fewer than 16 items, else the marker of the 16 or 32 bit length that follows.
+fn write_header(out: &mut Vec<u8>, len: usize, fix: u8, marker16: u8, marker32: u8) {
+    if len < 16 {
+        out.push(fix | len as u8);
+    } else if len <= u16::MAX as usize {
+        out.push(marker16);
+        out.extend((len as u16).to_be_bytes());
+    } else {
+        out.push(marker32);
+        out.extend((len as u32).to_be_bytes());
+    }
+}
+
+/// Deserializes a value from a MessagePack file, e.g. a whole `Document` without building
+/// a `serde_json::Value` of it first, or a `Value` to upgrade with `migrate::upgrade`.
+///
+/// # Returns
+///
+/// The value, or an error naming the byte offset where decoding failed, e.g. because the
+/// file is cut short or holds binary or extension data, which data files never contain.
+pub fn from_slice<'de, T: Deserialize<'de>>(bytes: &'de [u8]) -> Result<T, String> {
+    let mut reader = Reader {
+        bytes,
+        pos: 0,
+        depth: 0,
+    };
+    T::deserialize(&mut reader)
+        .and_then(|value| match reader.pos == bytes.len() {
+            true => Ok(value),
+            false => Err(Error("unexpected data".to_string())),
+        })
+        .map_err(|e| format!("{} at byte {}", e, reader.pos))
+}
+
+/// An error reading a MessagePack file; `from_slice` adds where it happened.
+#[derive(Debug)]
+struct Error(String);
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl de::Error for Error {
+    fn custom<T: fmt::Display>(message: T) -> Self {
+        Error(message.to_string())
+    }
+}
+
+/// Reads MessagePack items one after the other.
+struct Reader<'de> {
+    bytes: &'de [u8],
+    pos: usize,
+    /// How many arrays and maps the item being read is nested in.
+    depth: usize,
+}
+
+impl<'de> Reader<'de> {
+    fn take(&mut self, n: usize) -> Result<&'de [u8], Error> {
+        let end = self
+            .pos
+            .checked_add(n)
+            .filter(|&end| end <= self.bytes.len())
+            .ok_or_else(|| Error("the file ends too early".to_string()))?;
+        let bytes = &self.bytes[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    /// Reads a big-endian unsigned integer of `N` bytes.
+    fn uint<const N: usize>(&mut self) -> Result<u64, Error> {
+        let bytes = self.take(N)?;
+        Ok(bytes.iter().fold(0, |n, &b| n << 8 | u64::from(b)))
+    }
+
+    fn str(&mut self, len: usize) -> Result<&'de str, Error> {
+        let start = self.pos;
+        let bytes = self.take(len)?;
+        std::str::from_utf8(bytes).map_err(|_| {
+            self.pos = start;
+            Error("invalid UTF-8 in a string".to_string())
+        })
+    }
+
+    /// Reads the item at the reader if it is a string, as the keys of maps must be.
+    fn key(&mut self) -> Result<&'de str, Error> {
+        let marker = self.take(1)?[0];
+        let len = match marker {
+            0xa0..=0xbf => usize::from(marker & 0x1f),
+            0xd9 => self.uint::<1>()? as usize,
+            0xda => self.uint::<2>()? as usize,
+            0xdb => self.uint::<4>()? as usize,
+            _ => {
+                self.pos -= 1;
+                return Err(Error("map keys must be strings".to_string()));
+            }
+        };
+        self.str(len)
+    }
+
+    fn float<V: Visitor<'de>>(&self, f: f64, visitor: V) -> Result<V::Value, Error> {
+        match f.is_finite() {
+            true => visitor.visit_f64(f),
+            false => Err(Error("numbers must be finite".to_string())),
+        }
+    }
+
+    /// Hands the `len` items of an array, or the `len` entries of a map, to `visit`.
+    fn nested<T>(
+        &mut self,
+        len: usize,
+        visit: impl FnOnce(&mut Items<'_, 'de>) -> Result<T, Error>,
+    ) -> Result<T, Error> {
+        if self.depth == MAX_DEPTH {
+            return Err(Error("nested too deeply".to_string()));
+        }
+        self.depth += 1;
+        let mut items = Items { reader: self, len };
+        let value = visit(&mut items)?;
+        if items.len > 0 {
+            return Err(Error(format!("{} items too many", items.len)));
+        }
+        self.depth -= 1;
+        Ok(value)
+    }
+}
+
+impl<'de> de::Deserializer<'de> for &mut Reader<'de> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        let marker = self.take(1)?[0];
+        match marker {
+            0x00..=0x7f => visitor.visit_u64(marker.into()),
+            0x80..=0x8f => self.nested(usize::from(marker & 0x0f), |m| visitor.visit_map(m)),
+            0x90..=0x9f => self.nested(usize::from(marker & 0x0f), |s| visitor.visit_seq(s)),
+            0xa0..=0xbf => visitor.visit_borrowed_str(self.str(usize::from(marker & 0x1f))?),
+            0xc0 => visitor.visit_unit(),
+            0xc2 => visitor.visit_bool(false),
+            0xc3 => visitor.visit_bool(true),
+            0xca => {
+                let bits = self.uint::<4>()? as u32;
+                self.float(f64::from(f32::from_bits(bits)), visitor)
+            }
+            0xcb => {
+                let bits = self.uint::<8>()?;
+                self.float(f64::from_bits(bits), visitor)
+            }
+            0xcc => visitor.visit_u64(self.uint::<1>()?),
+            0xcd => visitor.visit_u64(self.uint::<2>()?),
+            0xce => visitor.visit_u64(self.uint::<4>()?),
+            0xcf => visitor.visit_u64(self.uint::<8>()?),
+            0xd0 => visitor.visit_i64(i64::from(self.uint::<1>()? as i8)),
+            0xd1 => visitor.visit_i64(i64::from(self.uint::<2>()? as i16)),
+            0xd2 => visitor.visit_i64(i64::from(self.uint::<4>()? as i32)),
+            0xd3 => visitor.visit_i64(self.uint::<8>()? as i64),
+            0xd9..=0xdb => {
+                self.pos -= 1;
+                visitor.visit_borrowed_str(self.key()?)
+            }
+            0xdc => {
+                let len = self.uint::<2>()? as usize;
+                self.nested(len, |s| visitor.visit_seq(s))
+            }
+            0xdd => {
+                let len = self.uint::<4>()? as usize;
+                self.nested(len, |s| visitor.visit_seq(s))
+            }
+            0xde => {
+                let len = self.uint::<2>()? as usize;
+                self.nested(len, |m| visitor.visit_map(m))
+            }
+            0xdf => {
+                let len = self.uint::<4>()? as usize;
+                self.nested(len, |m| visitor.visit_map(m))
+            }
+            0xe0..=0xff => visitor.visit_i64(i64::from(marker as i8)),
+            _ => {
+                self.pos -= 1;
+                Err(Error(format!("unsupported item type 0x{:02x}", marker)))
+            }
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        if self.bytes.get(self.pos) == Some(&0xc0) {
+            self.pos += 1;
+            visitor.visit_none()
+        } else {
+            visitor.visit_some(self)
+        }
+    }
+
+    /// Reads an enum as JSON writes it: a unit variant as its name, any other variant as a
+    /// map of its name to its contents.
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        match self.bytes.get(self.pos) {
+            Some(0x81) => {
+                self.pos += 1;
+                self.nested(1, |m| visitor.visit_enum(MapAccessDeserializer::new(m)))
+            }
+            Some(0x80..=0x8f | 0xde | 0xdf) => {
+                Err(Error("an enum must be a map of one entry".to_string()))
+            }
+            _ => visitor.visit_enum(self.key()?.into_deserializer()),
+        }
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf
+        unit unit_struct newtype_struct seq tuple tuple_struct map struct identifier
+        ignored_any
+    }
+}
+
+/// The items of an array or the entries of a map still to be read.
+struct Items<'a, 'de> {
+    reader: &'a mut Reader<'de>,
+    len: usize,
+}
+
+impl<'de> SeqAccess<'de> for Items<'_, 'de> {
+    type Error = Error;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, Error> {
+        if self.len == 0 {
+            return Ok(None);
+        }
+        self.len -= 1;
+        seed.deserialize(&mut *self.reader).map(Some)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.len)
+    }
+}
+
+impl<'de> MapAccess<'de> for Items<'_, 'de> {
+    type Error = Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, Error> {
+        if self.len == 0 {
+            return Ok(None);
+        }
+        self.len -= 1;
+        let key = self.reader.key()?;
+        seed.deserialize(BorrowedStrDeserializer::new(key))
+            .map(Some)
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, Error> {
+        seed.deserialize(&mut *self.reader)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exercise::Exercise;
+    use serde_json::{json, Value};
+
+    fn round_trip(value: &Value) -> Value {
+        from_slice(&encode(&Node::from_serialize(value).unwrap())).unwrap()
+    }
+
+    #[test]
+    fn round_trips_every_size_of_number_string_and_collection() {
+        let numbers = [
+            json!(0),
+            json!(127),
+            json!(128),
+            json!(255),
+            json!(256),
+            json!(65_535),
+            json!(65_536),
+            json!(u32::MAX),
+            json!(u64::from(u32::MAX) + 1),
+            json!(u64::MAX),
+            json!(-1),
+            json!(-32),
+            json!(-33),
+            json!(-128),
+            json!(-129),
+            json!(-32_769),
+            json!(i64::from(i32::MIN) - 1),
+            json!(i64::MIN),
+            json!(2.5),
+            json!(-0.1),
+        ];
+        for number in numbers {
+            assert_eq!(round_trip(&number), number);
+        }
+        for len in [0, 31, 32, 255, 256, 65_535, 65_536] {
+            let text = json!("é".repeat(len / 2) + &"x".repeat(len % 2));
+            assert_eq!(round_trip(&text), text);
+        }
+        for len in [0, 15, 16, 65_535, 65_536] {
+            let array = Value::Array(vec![json!(null); len]);
+            assert_eq!(round_trip(&array), array);
+            let map = Value::Object((0..len).map(|i| (i.to_string(), json!(true))).collect());
+            assert_eq!(round_trip(&map), map);
+        }
+    }
+
+    #[test]
+    fn rejects_truncated_input() {
+        let bytes = encode(&Node::from_serialize(&Exercise::examples()).unwrap());
+        for len in 0..bytes.len() {
+            assert!(
+                from_slice::<Value>(&bytes[..len]).is_err(),
+                "accepted {} bytes",
+                len
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_input() {
+        let malformed: [&[u8]; 9] = [
+            // Data after the value.
+            &[0x90, 0x90],
+            // A map key that isn't a string.
+            &[0x81, 0x01, 0x02],
+            // Invalid UTF-8.
+            &[0xa2, 0xff, 0xfe],
+            // Binary and extension data, and the unused marker.
+            &[0xc4, 0x01, 0x00],
+            &[0xd4, 0x01, 0x00],
+            &[0xc1],
+            // Lengths far beyond the end of the input.
+            &[0xdd, 0xff, 0xff, 0xff, 0xff],
+            &[0xdb, 0xff, 0xff, 0xff, 0xff],
+            // Not a finite number.
+            &[0xcb, 0x7f, 0xf8, 0, 0, 0, 0, 0, 0],
+        ];
+        for bytes in malformed {
+            assert!(
+                from_slice::<Value>(bytes).is_err(),
+                "accepted {:02x?}",
+                bytes
+            );
+        }
+
+        let deep = [vec![0x91; MAX_DEPTH + 1], vec![0xc0]].concat();
+        assert!(from_slice::<Value>(&deep).is_err());
+        let shallow = [vec![0x91; MAX_DEPTH - 1], vec![0xc0]].concat();
+        assert!(from_slice::<Value>(&shallow).is_ok());
+    }
+
+    #[test]
+    fn detects_maps_and_arrays_by_their_first_byte() {
+        for first in 0..=u8::MAX {
+            let expected = matches!(first, 0x80..=0x9f | 0xdc..=0xdf);
+            assert_eq!(is_msgpack(&[first]), expected, "0x{:02x}", first);
+        }
+        assert!(!is_msgpack(&[]));
+        assert!(!is_msgpack(b"{\"version\": 2}"));
+        assert!(!is_msgpack(b"version: 2"));
+        assert!(!is_msgpack(b"\xEF\xBB\xBF{}"));
+        assert!(is_msgpack(&encode(&Node::Object(Vec::new()))));
+        assert!(is_msgpack(&encode(&Node::Array(Vec::new()))));
+    }
+}
@@ -0,0 +1,457 @@
+/// The prefix that marks an accepted answer as a regular expression, e.g. `re:colou?r`.
+pub const REGEX_PREFIX: &str = "re:";
+
+/// The prefix that marks an accepted answer as a glob pattern, e.g. `glob:organi?e*`.
+pub const GLOB_PREFIX: &str = "glob:";
+
+/// The longest pattern accepted.
+const MAX_PATTERN_LEN: usize = 200;
+
+/// The most instructions a compiled pattern may have, which bounds the work done for
+/// every character of a reply.
+const MAX_PROGRAM_LEN: usize = 1000;
+
+/// An accepted answer written as a pattern instead of literal text.
+///
+/// Regular expressions support literal characters, `.`, character classes such as `[a-z]`
+/// or `[^aeiou]`, the repetitions `?`, `*` and `+`, groups `( )`, alternatives `|` and `\`
+/// to escape any of these. Globs support `*` for any run of characters and `?` for any one
+/// character. Either way the whole reply has to match, not just part of it.
+///
+/// Matching simulates all possible paths through the compiled pattern at once, so its cost
+/// grows with the length of the reply times the number of instructions, which is capped at
+/// `MAX_PROGRAM_LEN`. Every character of the pattern compiles to at most a few
+/// instructions, so no pattern can make matching take exponential time.
+pub struct Pattern {
+    program: Vec<Inst>,
+}
+
+/// One step of a compiled pattern.
+enum Inst {
+    Char(char),
+    Any,
+    Class {
+        ranges: Vec<(char, char)>,
+        negated: bool,
+    },
+    /// Continues at both targets.
+    Split(usize, usize),
+    Jump(usize),
+    Match,
+}
+
+/// A parsed regular expression.
+enum Node {
+    Char(char),
+    Any,
+    Class {
+        ranges: Vec<(char, char)>,
+        negated: bool,
+    },
+    Concat(Vec<Node>),
+    Alt(Vec<Node>),
+    /// `?` when `optional` but not `many`, `*` when both and `+` when only `many`.
+    Repeat {
+        node: Box<Node>,
+        optional: bool,
+        many: bool,
+    },
+}
+
+impl Pattern {
+    /// Reads an accepted answer.
+    ///
+    /// # Returns
+    ///
+    /// `None` for a plain text answer, the compiled pattern for one with the `re:` or
+    /// `glob:` prefix, or an error describing what is wrong with the pattern.
+    pub fn parse(answer: &str) -> Result<Option<Pattern>, String> {
+        let regex = if let Some(regex) = answer.strip_prefix(REGEX_PREFIX) {
+            regex.to_string()
+        } else if let Some(glob) = answer.strip_prefix(GLOB_PREFIX) {
+            glob_to_regex(glob)
+        } else {
+            return Ok(None);
+        };
+
+        if regex.chars().count() > MAX_PATTERN_LEN {
+            return Err(format!(
+                "Pattern '{}' is longer than {} characters",
+                answer, MAX_PATTERN_LEN
+            ));
+        }
+        let mut parser = Parser {
+            chars: regex.chars().collect(),
+            pos: 0,
+        };
+        let node = parser
+            .alternatives()
+            .and_then(|node| match parser.peek() {
+                None => Ok(node),
+                Some(c) => Err(format!("unexpected '{}'", c)),
+            })
+            .map_err(|e| format!("Invalid pattern '{}': {}", answer, e))?;
+
+        let mut program = Vec::new();
+        compile(&node, &mut program);
+        program.push(Inst::Match);
+        if program.len() > MAX_PROGRAM_LEN {
+            return Err(format!(
+                "Pattern '{}' is too complex: it compiles to more than {} steps",
+                answer, MAX_PROGRAM_LEN
+            ));
+        }
+        Ok(Some(Pattern { program }))
+    }
+
+    /// Checks whether the whole of `text` matches, folding case if `ignore_case` is set.
+    pub fn is_match(&self, text: &str, ignore_case: bool) -> bool {
+        let fold = |c: char| {
+            if ignore_case {
+                c.to_lowercase().next().unwrap_or(c)
+            } else {
+                c
+            }
+        };
+
+        let mut seen = vec![false; self.program.len()];
+        let mut current = Vec::new();
+        self.add_thread(&mut current, &mut seen, 0);
+        for c in text.chars().map(fold) {
+            seen.iter_mut().for_each(|s| *s = false);
+            let mut next = Vec::new();
+            for &pc in &current {
+                let matched = match &self.program[pc] {
+                    Inst::Char(expected) => fold(*expected) == c,
+                    Inst::Any => true,
+                    Inst::Class { ranges, negated } => {
+                        let inside = ranges.iter().any(|&(lo, hi)| {
+                            (lo..=hi).contains(&c) || (ignore_case && (lo..=hi).contains(&upper(c)))
+                        });
+                        inside != *negated
+                    }
+                    _ => false,
+                };
+                if matched {
+                    self.add_thread(&mut next, &mut seen, pc + 1);
+                }
+            }
+            if next.is_empty() {
+                return false;
+            }
+            current = next;
+        }
+        current
+            .iter()
+            .any(|&pc| matches!(self.program[pc], Inst::Match))
+    }
+
+    /// Adds the instruction at `pc` to a thread list, following jumps and splits.
+    ///
+    /// `seen` marks the instructions already in the list, so each is added only once.
+    fn add_thread(&self, threads: &mut Vec<usize>, seen: &mut [bool], pc: usize) {
+        if seen[pc] {
+            return;
+        }
+        seen[pc] = true;
+        threads.push(pc);
+        match self.program[pc] {
+            Inst::Jump(to) => self.add_thread(threads, seen, to),
+            Inst::Split(a, b) => {
+                self.add_thread(threads, seen, a);
+                self.add_thread(threads, seen, b);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Returns the upper case form of a character, for classes like `[A-Z]` when folding case.
+fn upper(c: char) -> char {
+    c.to_uppercase().next().unwrap_or(c)
+}
+
+/// Rewrites a glob as the equivalent regular expression.
+fn glob_to_regex(glob: &str) -> String {
+    glob.chars()
+        .map(|c| match c {
+            '*' => ".*".to_string(),
+            '?' => ".".to_string(),
+            c if "\\.[]()|+".contains(c) => format!("\\{}", c),
+            c => c.to_string(),
+        })
+        .collect()
+}
+
+/// A recursive descent parser for the supported regular expression syntax.
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek();
+        self.pos += 1;
+        c
+    }
+
+    /// Parses `a|b|...`.
+    fn alternatives(&mut self) -> Result<Node, String> {
+        let mut options = vec![self.sequence()?];
+        while self.peek() == Some('|') {
+            self.pos += 1;
+            options.push(self.sequence()?);
+        }
+        Ok(if options.len() == 1 {
+            options.remove(0)
+        } else {
+            Node::Alt(options)
+        })
+    }
+
+    /// Parses a run of repeated atoms, up to a `|` or `)`.
+    fn sequence(&mut self) -> Result<Node, String> {
+        let mut items = Vec::new();
+        while let Some(c) = self.peek() {
+            if c == '|' || c == ')' {
+                break;
+            }
+            let atom = self.atom()?;
+            let (optional, many) = match self.peek() {
+                Some('?') => (true, false),
+                Some('*') => (true, true),
+                Some('+') => (false, true),
+                _ => {
+                    items.push(atom);
+                    continue;
+                }
+            };
+            self.pos += 1;
+            items.push(Node::Repeat {
+                node: Box::new(atom),
+                optional,
+                many,
+            });
+        }
+        Ok(Node::Concat(items))
+    }
+
+    /// Parses a single character, class, `.` or group.
+    fn atom(&mut self) -> Result<Node, String> {
+        match self.next() {
+            Some('(') => {
+                let inner = self.alternatives()?;
+                match self.next() {
+                    Some(')') => Ok(inner),
+                    _ => Err("missing ')'".to_string()),
+                }
+            }
+            Some('[') => self.class(),
+            Some('.') => Ok(Node::Any),
+            Some('\\') => self
+                .next()
+                .map(Node::Char)
+                .ok_or_else(|| "nothing to escape after '\\'".to_string()),
+            Some(c @ ('?' | '*' | '+')) => Err(format!("nothing to repeat before '{}'", c)),
+            Some(c) => Ok(Node::Char(c)),
+            None => Err("unexpected end".to_string()),
+        }
+    }
+
+    /// Parses a character class after its opening `[`.
+    fn class(&mut self) -> Result<Node, String> {
+        let negated = self.peek() == Some('^');
+        if negated {
+            self.pos += 1;
+        }
+        let mut ranges = Vec::new();
+        loop {
+            let lo = match self.next() {
+                Some(']') if !ranges.is_empty() => break,
+                Some('\\') => self.next().ok_or("missing ']'")?,
+                Some(c) => c,
+                None => return Err("missing ']'".to_string()),
+            };
+            if self.peek() == Some('-') && self.chars.get(self.pos + 1).is_some_and(|&c| c != ']') {
+                self.pos += 1;
+                let hi = self.next().ok_or("missing ']'")?;
+                if hi < lo {
+                    return Err(format!("range {}-{} is backwards", lo, hi));
+                }
+                ranges.push((lo, hi));
+            } else {
+                ranges.push((lo, lo));
+            }
+        }
+        Ok(Node::Class { ranges, negated })
+    }
+}
+
+/// Appends the instructions for a node to a program.
+fn compile(node: &Node, program: &mut Vec<Inst>) {
+    match node {
+        Node::Char(c) => program.push(Inst::Char(*c)),
+        Node::Any => program.push(Inst::Any),
+        Node::Class { ranges, negated } => program.push(Inst::Class {
+            ranges: ranges.clone(),
+            negated: *negated,
+        }),
+        Node::Concat(items) => items.iter().for_each(|item| compile(item, program)),
+        Node::Alt(options) => {
+            // Each option but the last is preceded by a split to the next option and
+            // followed by a jump past the rest; the jumps are patched once the end is known.
+            let mut jumps = Vec::new();
+            for (i, option) in options.iter().enumerate() {
+                if i + 1 < options.len() {
+                    let split = program.len();
+                    program.push(Inst::Split(split + 1, 0));
+                    compile(option, program);
+                    jumps.push(program.len());
+                    program.push(Inst::Jump(0));
+                    let next = program.len();
+                    program[split] = Inst::Split(split + 1, next);
+                } else {
+                    compile(option, program);
+                }
+            }
+            let end = program.len();
+            for jump in jumps {
+                program[jump] = Inst::Jump(end);
+            }
+        }
+        Node::Repeat {
+            node,
+            optional: false,
+            ..
+        } => {
+            // `+`: the operand once, then a split back to it for every further match.
+            let start = program.len();
+            compile(node, program);
+            let end = program.len() + 1;
+            program.push(Inst::Split(start, end));
+        }
+        Node::Repeat { node, many, .. } => {
+            let split = program.len();
+            program.push(Inst::Split(split + 1, 0));
+            compile(node, program);
+            if *many {
+                program.push(Inst::Jump(split));
+            }
+            let end = program.len();
+            program[split] = Inst::Split(split + 1, end);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pattern(answer: &str) -> Pattern {
+        Pattern::parse(answer).unwrap().unwrap()
+    }
+
+    fn matches(answer: &str, text: &str) -> bool {
+        pattern(answer).is_match(text, false)
+    }
+
+    #[test]
+    fn plain_answers_are_not_patterns() {
+        assert!(Pattern::parse("colour").unwrap().is_none());
+    }
+
+    #[test]
+    fn classes() {
+        assert!(matches("re:gr[ae]y", "grey"));
+        assert!(matches("re:gr[ae]y", "gray"));
+        assert!(!matches("re:gr[ae]y", "groy"));
+        assert!(matches("re:[a-c]+", "abcba"));
+        assert!(!matches("re:[a-c]+", "abd"));
+        assert!(matches("re:[^aeiou]x", "bx"));
+        assert!(!matches("re:[^aeiou]x", "ax"));
+        assert!(matches("re:[-a]", "-"));
+        assert!(matches("re:[a\\]]", "]"));
+        assert!(pattern("re:[A-Z]").is_match("q", true));
+        assert!(!pattern("re:[A-Z]").is_match("q", false));
+        assert!(Pattern::parse("re:[z-a]").is_err());
+        assert!(Pattern::parse("re:[ab").is_err());
+    }
+
+    #[test]
+    fn groups_and_repetition() {
+        assert!(matches("re:colou?r", "color"));
+        assert!(matches("re:colou?r", "colour"));
+        assert!(matches("re:(ab)*c", "c"));
+        assert!(matches("re:(ab)*c", "ababc"));
+        assert!(!matches("re:(ab)+c", "c"));
+        assert!(matches("re:(ab)+c", "abc"));
+        assert!(!matches("re:(ab)+c", "abac"));
+        assert!(matches("re:a.c", "a-c"));
+        assert!(matches("re:a\\.c", "a.c"));
+        assert!(!matches("re:a\\.c", "abc"));
+        assert!(Pattern::parse("re:(ab").is_err());
+        assert!(Pattern::parse("re:ab)").is_err());
+        assert!(Pattern::parse("re:*a").is_err());
+        assert!(Pattern::parse("re:a\\").is_err());
+    }
+
+    #[test]
+    fn alternation() {
+        assert!(matches("re:cat|dog", "cat"));
+        assert!(matches("re:cat|dog", "dog"));
+        assert!(!matches("re:cat|dog", "cow"));
+        assert!(matches("re:(un|in)ept", "inept"));
+        assert!(matches("re:a|b|c|", ""));
+        assert!(matches("re:(x|y)+", "xyyx"));
+    }
+
+    #[test]
+    fn the_whole_reply_has_to_match() {
+        assert!(!matches("re:cat", "cats"));
+        assert!(!matches("re:cat", "scat"));
+        assert!(!matches("re:cat|dog", "catdog"));
+        assert!(matches("re:.*cat.*", "a cat here"));
+        assert!(!matches("re:a+", ""));
+    }
+
+    #[test]
+    fn case_folding() {
+        assert!(pattern("re:Colou?r").is_match("COLOR", true));
+        assert!(!pattern("re:Colou?r").is_match("COLOR", false));
+    }
+
+    #[test]
+    fn globs() {
+        assert!(matches("glob:organi?e*", "organise"));
+        assert!(matches("glob:organi?e*", "organizes"));
+        assert!(!matches("glob:organi?e*", "organe"));
+        assert!(matches("glob:*", ""));
+        assert!(matches("glob:a.b", "a.b"));
+        assert!(!matches("glob:a.b", "axb"));
+        assert!(matches("glob:(a|b)+", "(a|b)+"));
+        assert!(!matches("glob:(a|b)+", "a"));
+    }
+
+    #[test]
+    fn nested_repeats_stay_small_and_fast() {
+        let nested = format!("re:{}a{}", "(".repeat(30), ")+".repeat(30));
+        let compiled = pattern(&nested);
+        assert!(compiled.program.len() < 100);
+        assert!(compiled.is_match(&"a".repeat(1000), false));
+        assert!(!compiled.is_match(&format!("{}b", "a".repeat(1000)), false));
+
+        let empty_loops = pattern("re:((a*)*)*b");
+        assert!(empty_loops.is_match(&format!("{}b", "a".repeat(1000)), false));
+        assert!(!empty_loops.is_match(&"a".repeat(1000), false));
+    }
+
+    #[test]
+    fn long_patterns_are_rejected() {
+        assert!(Pattern::parse(&format!("re:{}", "a".repeat(MAX_PATTERN_LEN + 1))).is_err());
+    }
+}
@@ -0,0 +1,1261 @@
+use crate::entry::{
+    MULTI_OPTION_FORMATTER, OPTION_FORMATTER, SAME_OPPOSITE_FORMATTER, SAME_OPPOSITE_PARSER,
+};
+use crate::exercise::{
+    find_question, option_letter, Analogy, Cloze, Custom, Difficulty, Etymology, Exercise,
+    FillInTheBlank, Matching, MatchingSet, Mcq, McqMulti, Meta, OddOneOut, Ordering, Question,
+    Recall, RecognizeRoot, SameOrOpposite, Spelling, UseInSentence, YesNo,
+};
+use crate::history::Confidence;
+use crate::markdown::render as md;
+use crate::pattern::Pattern;
+use crate::rng::Rng;
+use crate::scheduler::Grade;
+use crate::score::{SessionResult, Weights};
+use crate::speech;
+use crate::template::{self, Template};
+use inquire::error::InquireResult;
+use inquire::{Confirm, InquireError, MultiSelect, Select, Text};
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// The result of asking a single question.
+pub struct Outcome {
+    /// Whether the learner's reply was correct.
+    pub correct: bool,
+    /// The share of the question's point earned, from 0 to 1; below 1 for a partly right
+    /// reply, which doesn't count as correct.
+    pub credit: f64,
+    /// The learner's reply, as shown in the exam report.
+    pub given: String,
+    /// The correct answer, as shown after a wrong reply.
+    pub expected: String,
+    /// An explanation attached to the reply that was given, if any.
+    pub feedback: Option<String>,
+    /// The author's note on why the answer is right, shown once the reply is graded.
+    pub explanation: Option<String>,
+    /// How the answer is pronounced, shown next to it once the reply is graded.
+    pub pronunciation: Option<String>,
+    /// A memory hook for the answer, shown after a wrong reply.
+    pub mnemonic: Option<String>,
+    /// How long the reply took.
+    pub elapsed: Duration,
+    /// Whether the time allowed by `QuizConfig::time_limit` ran out before the reply.
+    pub timed_out: bool,
+    /// How many hints were shown before the reply.
+    pub hints: usize,
+    /// How well the learner said they knew the answer, when asked to grade themselves.
+    pub grade: Option<Grade>,
+    /// How sure the learner said they were, when asked.
+    pub confidence: Option<Confidence>,
+    /// How hard the question is, as rated by its author.
+    pub difficulty: Option<Difficulty>,
+}
+
+impl Outcome {
+    fn new(correct: bool, given: String, expected: String) -> Outcome {
+        Outcome {
+            correct,
+            credit: if correct { 1.0 } else { 0.0 },
+            given,
+            expected,
+            feedback: None,
+            explanation: None,
+            pronunciation: None,
+            mnemonic: None,
+            elapsed: Duration::ZERO,
+            timed_out: false,
+            hints: 0,
+            grade: None,
+            confidence: None,
+            difficulty: None,
+        }
+    }
+}
+
+/// How typed answers are compared with the stored ones.
+///
+/// Surrounding whitespace is always ignored and runs of inner whitespace count as one space.
+#[derive(Clone, Copy)]
+pub struct Matcher {
+    pub ignore_case: bool,
+    /// Treats punctuation as whitespace, so "alter-ego" matches "alter ego".
+    pub ignore_punctuation: bool,
+}
+
+impl Matcher {
+    fn normalize(&self, text: &str) -> String {
+        let text = if self.ignore_case {
+            text.to_lowercase()
+        } else {
+            text.to_string()
+        };
+        let text: String = if self.ignore_punctuation {
+            text.chars()
+                .map(|c| if c.is_ascii_punctuation() { ' ' } else { c })
+                .collect()
+        } else {
+            text
+        };
+        text.split_whitespace().collect::<Vec<_>>().join(" ")
+    }
+
+    /// Checks whether a typed answer matches the stored one.
+    pub fn matches(&self, typed: &str, answer: &str) -> bool {
+        self.normalize(typed) == self.normalize(answer)
+    }
+}
+
+/// Settings that control how a quiz is played.
+pub struct QuizConfig {
+    /// Shuffles question and option order when set; `None` keeps the stored order.
+    pub shuffle: Option<Rng>,
+    /// The time allowed for each question; questions not answered in time count as wrong.
+    pub time_limit: Option<Duration>,
+    /// Grades typed answers.
+    pub matcher: Matcher,
+    /// Holds back all grading until every question has been answered.
+    pub exam: bool,
+    /// Asks the learner after each reply how well they knew the answer; ignored in exam mode.
+    pub self_grade: bool,
+    /// Alternates between exercise types instead of asking each type in one run.
+    pub interleave: bool,
+    /// Asks the learner after each reply how sure they were, before grading it.
+    pub ask_confidence: bool,
+    /// Where the files attached to questions are kept.
+    pub media_dir: PathBuf,
+    /// How much replies count for in the weighted score, by difficulty.
+    pub weights: Weights,
+    /// The command that reads every question aloud, see `--tts`.
+    pub tts: Option<String>,
+}
+
+impl QuizConfig {
+    /// Returns the options in the order they should be shown.
+    fn arrange(&mut self, options: &[String]) -> Vec<String> {
+        let mut options = options.to_vec();
+        if let Some(rng) = &mut self.shuffle {
+            rng.shuffle(&mut options);
+        }
+        options
+    }
+}
+
+/// A common trait for question types that can be played back in quiz mode.
+pub trait Quiz: Question + Sized {
+    /// Prompts the learner with the question and checks the reply against the stored answer.
+    ///
+    /// # Arguments
+    ///
+    /// * `group` - The exercise group the question belongs to, for question types whose
+    ///   options are the answers of the other questions.
+    /// * `config` - Settings that control how the question is shown.
+    ///
+    /// # Returns
+    ///
+    /// Whether the reply was correct, or the prompt error (e.g. `OperationInterrupted` on
+    /// Ctrl-C) that stopped the quiz.
+    fn ask(&self, group: &[Self], config: &mut QuizConfig) -> InquireResult<Outcome>;
+
+    /// Shows the first prompt `ask` puts to the learner, as it looks before any reply, so
+    /// that `--preview` can't drift from what a quiz shows.
+    fn preview(&self, group: &[Self], config: &mut QuizConfig) -> String;
+}
+
+/// Draws a typed-answer prompt the way it looks before anything is typed.
+fn show_text(message: &str, help: Option<&str>) -> String {
+    match help {
+        Some(help) => format!("? {}\n[{}]", message, help),
+        None => format!("? {}", message),
+    }
+}
+
+/// Draws a yes or no prompt the way it looks before anything is typed.
+fn show_confirm(message: &str) -> String {
+    format!("? {} (y/n)", message)
+}
+
+/// Draws a prompt that picks one option, with the cursor on the first.
+fn show_select(message: &str, options: &[String]) -> String {
+    let mut shown = format!("? {}", message);
+    for (i, option) in options.iter().enumerate() {
+        let cursor = if i == 0 { '>' } else { ' ' };
+        shown.push_str(&format!("\n{} {}", cursor, option));
+    }
+    shown
+}
+
+/// Draws a prompt that ticks any number of options, with none ticked yet.
+fn show_multi_select(message: &str, options: &[String]) -> String {
+    let mut shown = format!("? {}", message);
+    for (i, option) in options.iter().enumerate() {
+        let cursor = if i == 0 { '>' } else { ' ' };
+        shown.push_str(&format!("\n{} [ ] {}", cursor, option));
+    }
+    shown
+}
+
+/// What the learner types instead of an answer to get the next hint.
+const HINT_REQUEST: &str = "?";
+
+/// The help shown under a typed answer that has hints, before any is asked for.
+fn hint_help() -> String {
+    format!("type {} for a hint", HINT_REQUEST)
+}
+
+/// Reads a typed answer, showing the question's next hint whenever `?` is entered.
+///
+/// # Returns
+///
+/// The reply and the number of hints that were shown.
+fn read_with_hints(message: &str, question: &impl Question) -> InquireResult<(String, usize)> {
+    let mut hints = 0;
+    let mut help = hint_help();
+
+    loop {
+        let reply = Text::new(message).with_help_message(&help).prompt()?;
+        if reply.trim() != HINT_REQUEST {
+            return Ok((reply, hints));
+        }
+        match question.hint(hints + 1) {
+            Some(hint) => {
+                hints += 1;
+                help = hint;
+            }
+            None => help = format!("{} (no more hints)", help),
+        }
+    }
+}
+
+/// Renders options the way they are shown; picks are mapped back to them by position.
+fn shown(options: &[String]) -> Vec<String> {
+    options.iter().map(|o| md(o)).collect()
+}
+
+/// Shows an answer the way the options were lettered, e.g. "(b) egoist".
+fn lettered(options: &[String], answer: &str) -> String {
+    match options.iter().position(|o| o == answer) {
+        Some(i) => format!("({}) {}", option_letter(i), answer),
+        None => answer.to_string(),
+    }
+}
+
+/// The options of a matching question are the answers of its group, each listed once.
+impl Quiz for Matching {
+    fn ask(&self, group: &[Self], config: &mut QuizConfig) -> InquireResult<Outcome> {
+        let options = config.arrange(&matching_options(group));
+        let pick = Select::new(&md(&self.text()), shown(&options))
+            .with_formatter(OPTION_FORMATTER)
+            .raw_prompt()?;
+        let reply = options[pick.index].clone();
+        Ok(Outcome::new(
+            reply == self.answer(),
+            reply,
+            self.answer().to_string(),
+        ))
+    }
+
+    fn preview(&self, group: &[Self], config: &mut QuizConfig) -> String {
+        show_select(
+            &md(&self.text()),
+            &shown(&config.arrange(&matching_options(group))),
+        )
+    }
+}
+
+/// Returns the answers of a matching group, each listed once.
+fn matching_options(group: &[Matching]) -> Vec<String> {
+    let mut options: Vec<String> = Vec::new();
+    for item in group {
+        if !options.iter().any(|o| o == item.answer()) {
+            options.push(item.answer().to_string());
+        }
+    }
+    options
+}
+
+impl Quiz for YesNo {
+    fn ask(&self, _group: &[Self], _config: &mut QuizConfig) -> InquireResult<Outcome> {
+        let reply = Confirm::new(&md(&self.text())).prompt()?;
+        let yes_no = |answer: bool| if answer { "yes" } else { "no" }.to_string();
+        Ok(Outcome::new(
+            reply == self.answer(),
+            yes_no(reply),
+            yes_no(self.answer()),
+        ))
+    }
+
+    fn preview(&self, _group: &[Self], _config: &mut QuizConfig) -> String {
+        show_confirm(&md(&self.text()))
+    }
+}
+
+/// Any of the accepted answers counts, patterns matching the whole trimmed reply.
+/// Case-sensitive cards require matching capitalization whatever the matcher allows.
+impl Quiz for Recall {
+    fn ask(&self, _group: &[Self], config: &mut QuizConfig) -> InquireResult<Outcome> {
+        let matcher = Matcher {
+            ignore_case: config.matcher.ignore_case && !self.is_case_sensitive(),
+            ..config.matcher
+        };
+
+        let (reply, hints) = read_with_hints(&md(&self.text()), self)?;
+        let accepted = |answer: &String| match Pattern::parse(answer) {
+            Ok(Some(pattern)) => pattern.is_match(reply.trim(), matcher.ignore_case),
+            _ => matcher.matches(&reply, answer),
+        };
+        let mut outcome = Outcome::new(
+            self.answers().iter().any(accepted),
+            reply,
+            shown_answers(self.answers()).join(" / "),
+        );
+        outcome.hints = hints;
+        Ok(outcome)
+    }
+
+    fn preview(&self, _group: &[Self], _config: &mut QuizConfig) -> String {
+        show_text(&md(&self.text()), Some(&hint_help()))
+    }
+}
+
+/// Returns the accepted answers worth showing after a wrong reply: the plain ones, or the
+/// patterns if there are no others.
+fn shown_answers(answers: &[String]) -> Vec<&str> {
+    let plain: Vec<&str> = answers
+        .iter()
+        .filter(|a| matches!(Pattern::parse(a), Ok(None)))
+        .map(String::as_str)
+        .collect();
+    if plain.is_empty() {
+        answers.iter().map(String::as_str).collect()
+    } else {
+        plain
+    }
+}
+
+/// A wrong pick shows the feedback stored for that option.
+impl Quiz for Mcq {
+    fn ask(&self, _group: &[Self], config: &mut QuizConfig) -> InquireResult<Outcome> {
+        let options = config.arrange(self.options());
+        let pick = Select::new(&md(&self.text()), shown(&options))
+            .with_formatter(OPTION_FORMATTER)
+            .raw_prompt()?;
+        let reply = options[pick.index].clone();
+
+        let mut outcome = Outcome::new(
+            reply == self.answer(),
+            lettered(&options, &reply),
+            lettered(&options, self.answer()),
+        );
+        // Feedback is stored in the original option order.
+        outcome.feedback = self
+            .options()
+            .iter()
+            .position(|o| *o == reply)
+            .and_then(|i| self.feedback().get(i).cloned().flatten());
+        Ok(outcome)
+    }
+
+    fn preview(&self, _group: &[Self], config: &mut QuizConfig) -> String {
+        show_select(&md(&self.text()), &shown(&config.arrange(self.options())))
+    }
+}
+
+/// Only the exact set of correct options counts as right.
+/// Each correct pick earns a share of the point and each wrong pick takes one share away,
+/// so ticking every option earns nothing.
+impl Quiz for McqMulti {
+    fn ask(&self, _group: &[Self], config: &mut QuizConfig) -> InquireResult<Outcome> {
+        let options = config.arrange(self.options());
+        let reply: Vec<String> = MultiSelect::new(&md(&self.text()), shown(&options))
+            .with_formatter(MULTI_OPTION_FORMATTER)
+            .raw_prompt()?
+            .into_iter()
+            .map(|pick| options[pick.index].clone())
+            .collect();
+
+        let right = reply.iter().filter(|r| self.answers().contains(r)).count();
+        let wrong = reply.len() - right;
+        let correct = right == self.answers().len() && wrong == 0;
+        let expected: Vec<String> = self
+            .answers()
+            .iter()
+            .map(|a| lettered(&options, a))
+            .collect();
+        let given: Vec<String> = reply.iter().map(|r| lettered(&options, r)).collect();
+        let mut outcome = Outcome::new(correct, given.join(", "), expected.join(", "));
+        outcome.credit = right.saturating_sub(wrong) as f64 / self.answers().len().max(1) as f64;
+        Ok(outcome)
+    }
+
+    fn preview(&self, _group: &[Self], config: &mut QuizConfig) -> String {
+        show_multi_select(&md(&self.text()), &shown(&config.arrange(self.options())))
+    }
+}
+
+impl Quiz for RecognizeRoot {
+    fn ask(&self, _group: &[Self], config: &mut QuizConfig) -> InquireResult<Outcome> {
+        let reply = Text::new(&self.message()).prompt()?;
+        Ok(Outcome::new(
+            config.matcher.matches(&reply, self.answer()),
+            reply,
+            self.answer().to_string(),
+        ))
+    }
+
+    fn preview(&self, _group: &[Self], _config: &mut QuizConfig) -> String {
+        show_text(&self.message(), None)
+    }
+}
+
+impl RecognizeRoot {
+    fn message(&self) -> String {
+        format!("{}, Example: {}", md(&self.text()), md(self.example()))
+    }
+}
+
+impl Quiz for FillInTheBlank {
+    fn ask(&self, _group: &[Self], config: &mut QuizConfig) -> InquireResult<Outcome> {
+        let (reply, hints) = read_with_hints(&self.message(), self)?;
+        let mut outcome = Outcome::new(
+            config.matcher.matches(&reply, self.answer()),
+            reply,
+            self.answer().to_string(),
+        );
+        outcome.hints = hints;
+        Ok(outcome)
+    }
+
+    fn preview(&self, _group: &[Self], _config: &mut QuizConfig) -> String {
+        show_text(&self.message(), Some(&hint_help()))
+    }
+}
+
+impl FillInTheBlank {
+    fn message(&self) -> String {
+        format!("{}\n {}", md(&self.text()), self.blank())
+    }
+}
+
+impl Quiz for SameOrOpposite {
+    fn ask(&self, _group: &[Self], _config: &mut QuizConfig) -> InquireResult<Outcome> {
+        let reply = Confirm::new(&self.message())
+            .with_formatter(SAME_OPPOSITE_FORMATTER)
+            .with_parser(SAME_OPPOSITE_PARSER)
+            .prompt()?;
+        Ok(Outcome::new(
+            reply == self.answer(),
+            SAME_OPPOSITE_FORMATTER(reply),
+            SAME_OPPOSITE_FORMATTER(self.answer()),
+        ))
+    }
+
+    fn preview(&self, _group: &[Self], _config: &mut QuizConfig) -> String {
+        show_confirm(&self.message())
+    }
+}
+
+impl SameOrOpposite {
+    fn message(&self) -> String {
+        format!("{}-{}", md(self.first_word()), md(self.second_word()))
+    }
+}
+
+/// The items are picked one place at a time from those not yet placed. Each item in its
+/// right place earns a share of the point.
+impl Quiz for Ordering {
+    fn ask(&self, _group: &[Self], config: &mut QuizConfig) -> InquireResult<Outcome> {
+        let mut remaining = self.mixed_up(config);
+        let mut reply = Vec::new();
+        while remaining.len() > 1 {
+            let message = self.message(reply.len() + 1);
+            let pick = Select::new(&message, shown(&remaining)).raw_prompt()?;
+            reply.push(remaining.remove(pick.index));
+        }
+        reply.append(&mut remaining);
+
+        let placed = reply
+            .iter()
+            .zip(self.items())
+            .filter(|(r, a)| r == a)
+            .count();
+        let mut outcome = Outcome::new(
+            placed == self.items().len(),
+            reply.join(", "),
+            self.items().join(", "),
+        );
+        outcome.credit = placed as f64 / self.items().len().max(1) as f64;
+        Ok(outcome)
+    }
+
+    fn preview(&self, _group: &[Self], config: &mut QuizConfig) -> String {
+        show_select(&self.message(1), &shown(&self.mixed_up(config)))
+    }
+}
+
+impl Ordering {
+    /// Returns the items out of order. The stored order is the answer, so the items are
+    /// always mixed up.
+    fn mixed_up(&self, config: &mut QuizConfig) -> Vec<String> {
+        let mut items = self.items().to_vec();
+        match &mut config.shuffle {
+            Some(rng) => rng.shuffle(&mut items),
+            None => Rng::from_time().shuffle(&mut items),
+        }
+        if items == self.items() {
+            items.rotate_left(1);
+        }
+        items
+    }
+
+    /// Returns the prompt for picking the item in place `place`, counted from 1.
+    fn message(&self, place: usize) -> String {
+        format!("{} ({} of {})", md(&self.text()), place, self.items().len())
+    }
+}
+
+/// The blanks are asked one after another, with the replies so far filled in. Each blank
+/// filled in correctly earns a share of the point.
+impl Quiz for Cloze {
+    fn ask(&self, _group: &[Self], config: &mut QuizConfig) -> InquireResult<Outcome> {
+        let mut replies: Vec<String> = Vec::new();
+        for _ in self.answers() {
+            replies.push(Text::new(&self.message(&replies)).prompt()?);
+        }
+
+        let right = replies
+            .iter()
+            .zip(self.answers())
+            .filter(|(r, a)| config.matcher.matches(r, a))
+            .count();
+        let mut outcome = Outcome::new(
+            right == self.answers().len(),
+            replies.join(", "),
+            self.answers().join(", "),
+        );
+        outcome.credit = right as f64 / self.answers().len().max(1) as f64;
+        Ok(outcome)
+    }
+
+    fn preview(&self, _group: &[Self], _config: &mut QuizConfig) -> String {
+        show_text(&self.message(&[]), None)
+    }
+}
+
+impl Cloze {
+    /// Returns the prompt for the blank after those already filled in with `replies`.
+    fn message(&self, replies: &[String]) -> String {
+        let n = replies.len() + 1;
+        md(&self.fill(|b| match b.cmp(&n) {
+            std::cmp::Ordering::Less => format!("[{}]", replies[b - 1]),
+            std::cmp::Ordering::Equal => "___".to_string(),
+            std::cmp::Ordering::Greater => "_".to_string(),
+        }))
+    }
+}
+
+/// The word is shown with its roots and the learner supplies what the roots mean.
+impl Quiz for Etymology {
+    fn ask(&self, _group: &[Self], config: &mut QuizConfig) -> InquireResult<Outcome> {
+        let (reply, hints) = read_with_hints(&self.message(), self)?;
+        let mut outcome = Outcome::new(
+            config.matcher.matches(&reply, self.meaning()),
+            reply,
+            self.meaning().to_string(),
+        );
+        outcome.hints = hints;
+        Ok(outcome)
+    }
+
+    fn preview(&self, _group: &[Self], _config: &mut QuizConfig) -> String {
+        show_text(&self.message(), Some(&hint_help()))
+    }
+}
+
+impl Etymology {
+    fn message(&self) -> String {
+        format!(
+            "{}: literal meaning of {} ({})?",
+            md(&self.text()),
+            md(&self.roots().join(" + ")),
+            self.language()
+        )
+    }
+}
+
+/// There is no single right sentence, so the learner compares theirs with the model one
+/// and says whether they used the word correctly.
+impl Quiz for UseInSentence {
+    fn ask(&self, _group: &[Self], _config: &mut QuizConfig) -> InquireResult<Outcome> {
+        let reply = Text::new(&self.message()).prompt()?;
+        println!("  e.g. {}", md(self.model()));
+        let correct = Confirm::new("Did you use it correctly?").prompt()?;
+        Ok(Outcome::new(correct, reply, self.model().to_string()))
+    }
+
+    fn preview(&self, _group: &[Self], _config: &mut QuizConfig) -> String {
+        show_text(&self.message(), None)
+    }
+}
+
+impl UseInSentence {
+    fn message(&self) -> String {
+        format!("Use {} in a sentence", md(&self.text()))
+    }
+}
+
+/// Shows how a misspelling differs from the word, one character at a time.
+///
+/// Characters that match are shown as they are, a wrong character as `[typed→right]`, an
+/// extra one as `[-typed]` and a missing one as `[+right]`, following the alignment with
+/// the fewest edits.
+fn spelling_diff(typed: &str, word: &str) -> String {
+    let typed: Vec<char> = typed.chars().collect();
+    let word: Vec<char> = word.chars().collect();
+
+    // edits[i][j] is the number of edits turning typed[i..] into word[j..].
+    let mut edits = vec![vec![0; word.len() + 1]; typed.len() + 1];
+    for i in (0..=typed.len()).rev() {
+        for j in (0..=word.len()).rev() {
+            edits[i][j] = if i == typed.len() {
+                word.len() - j
+            } else if j == word.len() {
+                typed.len() - i
+            } else {
+                let substitute = edits[i + 1][j + 1] + usize::from(typed[i] != word[j]);
+                substitute.min(edits[i + 1][j] + 1).min(edits[i][j + 1] + 1)
+            };
+        }
+    }
+
+    let (mut i, mut j) = (0, 0);
+    let mut diff = String::new();
+    while i < typed.len() || j < word.len() {
+        if i < typed.len()
+            && j < word.len()
+            && typed[i] == word[j]
+            && edits[i][j] == edits[i + 1][j + 1]
+        {
+            diff.push(typed[i]);
+            i += 1;
+            j += 1;
+        } else if i < typed.len() && j < word.len() && edits[i][j] == edits[i + 1][j + 1] + 1 {
+            diff.push_str(&format!("[{}→{}]", typed[i], word[j]));
+            i += 1;
+            j += 1;
+        } else if i < typed.len() && edits[i][j] == edits[i + 1][j] + 1 {
+            diff.push_str(&format!("[-{}]", typed[i]));
+            i += 1;
+        } else {
+            diff.push_str(&format!("[+{}]", word[j]));
+            j += 1;
+        }
+    }
+    diff
+}
+
+/// Only the exact spelling counts, whatever the matcher allows; surrounding whitespace is
+/// ignored. The question's pronunciation, if any, is shown with the definition.
+impl Quiz for Spelling {
+    fn ask(&self, _group: &[Self], _config: &mut QuizConfig) -> InquireResult<Outcome> {
+        let reply = Text::new(&self.message()).prompt()?;
+
+        let typed = reply.trim();
+        let mut outcome =
+            Outcome::new(typed == self.word(), reply.clone(), self.word().to_string());
+        if !outcome.correct {
+            outcome.feedback = Some(spelling_diff(typed, self.word()));
+        }
+        Ok(outcome)
+    }
+
+    fn preview(&self, _group: &[Self], _config: &mut QuizConfig) -> String {
+        show_text(&self.message(), None)
+    }
+}
+
+impl Spelling {
+    fn message(&self) -> String {
+        match &self.meta().pronunciation {
+            Some(pronunciation) => format!("{} ({})", md(&self.text()), pronunciation),
+            None => md(&self.text()),
+        }
+    }
+}
+
+/// The left items are matched one after another, and each right item can only be used
+/// once. Each correct pair earns a share of the point.
+impl Quiz for MatchingSet {
+    fn ask(&self, _group: &[Self], config: &mut QuizConfig) -> InquireResult<Outcome> {
+        println!("{}", md(&self.text()));
+        let mut remaining = config.arrange(self.right());
+        let mut given = Vec::new();
+        let mut expected = Vec::new();
+        let mut right = 0;
+
+        for (i, item) in self.left().iter().enumerate() {
+            let pick = Select::new(&md(item), shown(&remaining)).raw_prompt()?;
+            let pick = remaining.remove(pick.index);
+            if pick == self.match_for(i) {
+                right += 1;
+            }
+            given.push(format!("{} -> {}", item, pick));
+            expected.push(format!("{} -> {}", item, self.match_for(i)));
+        }
+
+        let mut outcome = Outcome::new(
+            right == self.left().len(),
+            given.join(", "),
+            expected.join(", "),
+        );
+        outcome.credit = right as f64 / self.left().len().max(1) as f64;
+        Ok(outcome)
+    }
+
+    fn preview(&self, _group: &[Self], config: &mut QuizConfig) -> String {
+        let first = self.left().first().map_or("", String::as_str);
+        format!(
+            "{}\n{}",
+            md(&self.text()),
+            show_select(&md(first), &shown(&config.arrange(self.right())))
+        )
+    }
+}
+
+/// Why the odd word doesn't belong is the question's explanation, shown once graded.
+impl Quiz for OddOneOut {
+    fn ask(&self, _group: &[Self], config: &mut QuizConfig) -> InquireResult<Outcome> {
+        let words = config.arrange(self.words());
+        let pick = Select::new(ODD_ONE_OUT_MESSAGE, shown(&words))
+            .with_formatter(OPTION_FORMATTER)
+            .raw_prompt()?;
+        let reply = words[pick.index].clone();
+        Ok(Outcome::new(
+            reply == self.answer(),
+            lettered(&words, &reply),
+            lettered(&words, self.answer()),
+        ))
+    }
+
+    fn preview(&self, _group: &[Self], config: &mut QuizConfig) -> String {
+        show_select(ODD_ONE_OUT_MESSAGE, &shown(&config.arrange(self.words())))
+    }
+}
+
+/// What every odd one out question asks.
+const ODD_ONE_OUT_MESSAGE: &str = "Which word doesn't belong?";
+
+/// Analogies with distractors are multiple choice; the others are typed, with hints.
+impl Quiz for Analogy {
+    fn ask(&self, _group: &[Self], config: &mut QuizConfig) -> InquireResult<Outcome> {
+        if self.distractors().is_empty() {
+            let (reply, hints) = read_with_hints(&md(&self.text()), self)?;
+            let mut outcome = Outcome::new(
+                config.matcher.matches(&reply, self.answer()),
+                reply,
+                self.answer().to_string(),
+            );
+            outcome.hints = hints;
+            return Ok(outcome);
+        }
+
+        let options = self.options(config);
+        let pick = Select::new(&md(&self.text()), shown(&options))
+            .with_formatter(OPTION_FORMATTER)
+            .raw_prompt()?;
+        let reply = options[pick.index].clone();
+        Ok(Outcome::new(
+            reply == self.answer(),
+            lettered(&options, &reply),
+            lettered(&options, self.answer()),
+        ))
+    }
+
+    fn preview(&self, _group: &[Self], config: &mut QuizConfig) -> String {
+        if self.distractors().is_empty() {
+            show_text(&md(&self.text()), Some(&hint_help()))
+        } else {
+            show_select(&md(&self.text()), &shown(&self.options(config)))
+        }
+    }
+}
+
+impl Analogy {
+    /// Returns the distractors and the answer to pick from. The answer is stored last, so
+    /// the options are always mixed up.
+    fn options(&self, config: &mut QuizConfig) -> Vec<String> {
+        let mut options = self.distractors().to_vec();
+        options.push(self.answer().to_string());
+        match &mut config.shuffle {
+            Some(rng) => rng.shuffle(&mut options),
+            None => Rng::from_time().shuffle(&mut options),
+        }
+        options
+    }
+}
+
+/// The prompt and answer are filled in from the question's template.
+impl Quiz for Custom {
+    fn ask(&self, _group: &[Self], config: &mut QuizConfig) -> InquireResult<Outcome> {
+        let template = Template::find(&template::template_dir(), self.template())
+            .map_err(|e| InquireError::Custom(e.into()))?;
+        let prompt = Template::fill(&template.prompt, self.fields());
+        let answer = Template::fill(&template.answer, self.fields());
+
+        let reply = Text::new(&md(&prompt)).prompt()?;
+        Ok(Outcome::new(
+            config.matcher.matches(&reply, &answer),
+            reply,
+            answer,
+        ))
+    }
+
+    /// Without its template the question can't be asked, which is shown instead.
+    fn preview(&self, _group: &[Self], _config: &mut QuizConfig) -> String {
+        match Template::find(&template::template_dir(), self.template()) {
+            Ok(template) => show_text(&md(&Template::fill(&template.prompt, self.fields())), None),
+            Err(e) => format!("({})", e),
+        }
+    }
+}
+
+/// Asks question `q` of an exercise group.
+fn ask(exercise: &Exercise, q: usize, config: &mut QuizConfig) -> InquireResult<Outcome> {
+    match exercise {
+        Exercise::Matching(items) => items[q].ask(items, config),
+        Exercise::YesNo(items) => items[q].ask(items, config),
+        Exercise::Recall(items) => items[q].ask(items, config),
+        Exercise::Mcq(items) => items[q].ask(items, config),
+        Exercise::McqMulti(items) => items[q].ask(items, config),
+        Exercise::RecognizeRoot(items) => items[q].ask(items, config),
+        Exercise::FillInTheBlank(items) => items[q].ask(items, config),
+        Exercise::SameOrOpposite(items) => items[q].ask(items, config),
+        Exercise::Ordering(items) => items[q].ask(items, config),
+        Exercise::Cloze(items) => items[q].ask(items, config),
+        Exercise::Etymology(items) => items[q].ask(items, config),
+        Exercise::UseInSentence(items) => items[q].ask(items, config),
+        Exercise::Spelling(items) => items[q].ask(items, config),
+        Exercise::MatchingSet(items) => items[q].ask(items, config),
+        Exercise::OddOneOut(items) => items[q].ask(items, config),
+        Exercise::Analogy(items) => items[q].ask(items, config),
+        Exercise::Custom(items) => items[q].ask(items, config),
+    }
+}
+
+/// Asks question `q` of an exercise group, grading it wrong if `limit` runs out first.
+///
+/// The prompt runs on a worker thread while this thread waits for its reply. A prompt
+/// can't be aborted while it waits for input, so once the time is up the learner is told
+/// and the prompt is left open until it is finished; whatever is replied then doesn't
+/// count.
+fn ask_timed(
+    exercise: &Exercise,
+    q: usize,
+    config: &mut QuizConfig,
+    limit: Duration,
+) -> InquireResult<Outcome> {
+    thread::scope(|scope| {
+        let (sender, receiver) = mpsc::channel();
+        scope.spawn(move || {
+            // The receiver lives until the scope ends, so sending can't fail.
+            let _ = sender.send(ask(exercise, q, config));
+        });
+        match receiver.recv_timeout(limit) {
+            Ok(outcome) => outcome,
+            Err(_) => {
+                // The prompt holds the terminal in raw mode, so lines need a carriage return.
+                print!("\r\nTime's up! Finish the prompt to see the answer; the reply won't count.\r\n");
+                let _ = io::stdout().flush();
+                let mut outcome = receiver.recv().map_err(|_| {
+                    InquireError::Custom("the timed prompt stopped unexpectedly".into())
+                })??;
+                outcome.correct = false;
+                outcome.credit = 0.0;
+                outcome.given = "no reply in time".to_string();
+                outcome.timed_out = true;
+                Ok(outcome)
+            }
+        }
+    })
+}
+
+/// Shows how question `q` of an exercise group is first put to the learner, see
+/// `Quiz::preview`.
+pub fn preview(exercise: &Exercise, q: usize, config: &mut QuizConfig) -> String {
+    match exercise {
+        Exercise::Matching(items) => items[q].preview(items, config),
+        Exercise::YesNo(items) => items[q].preview(items, config),
+        Exercise::Recall(items) => items[q].preview(items, config),
+        Exercise::Mcq(items) => items[q].preview(items, config),
+        Exercise::McqMulti(items) => items[q].preview(items, config),
+        Exercise::RecognizeRoot(items) => items[q].preview(items, config),
+        Exercise::FillInTheBlank(items) => items[q].preview(items, config),
+        Exercise::SameOrOpposite(items) => items[q].preview(items, config),
+        Exercise::Ordering(items) => items[q].preview(items, config),
+        Exercise::Cloze(items) => items[q].preview(items, config),
+        Exercise::Etymology(items) => items[q].preview(items, config),
+        Exercise::UseInSentence(items) => items[q].preview(items, config),
+        Exercise::Spelling(items) => items[q].preview(items, config),
+        Exercise::MatchingSet(items) => items[q].preview(items, config),
+        Exercise::OddOneOut(items) => items[q].preview(items, config),
+        Exercise::Analogy(items) => items[q].preview(items, config),
+        Exercise::Custom(items) => items[q].preview(items, config),
+    }
+}
+
+/// Tells the learner whether their reply was right, and what was expected if not.
+fn report(outcome: &Outcome) {
+    if outcome.timed_out {
+        println!("Time's up, the answer is {}", md(&outcome.expected));
+    } else if outcome.correct {
+        println!("Correct!");
+    } else if outcome.credit > 0.0 {
+        println!(
+            "Partly right ({:.0}%), the answer is {}",
+            outcome.credit * 100.0,
+            md(&outcome.expected)
+        );
+    } else {
+        println!("Wrong, the answer is {}", md(&outcome.expected));
+    }
+    if let Some(pronunciation) = &outcome.pronunciation {
+        println!("  pronounced {}", pronunciation);
+    }
+    if let Some(feedback) = &outcome.feedback {
+        println!("  {}", md(feedback));
+    }
+    if let Some(explanation) = &outcome.explanation {
+        println!("  {}", md(explanation));
+    }
+    if let Some(mnemonic) = outcome.mnemonic.as_ref().filter(|_| !outcome.correct) {
+        println!("  remember: {}", md(mnemonic));
+    }
+}
+
+/// Shows every exam question with the reply given and, for wrong replies, the answer.
+fn print_exam_report(answers: &[(String, Outcome)]) {
+    println!("Results");
+    for (i, (question, outcome)) in answers.iter().enumerate() {
+        println!("{:>3}. {}", i + 1, md(question));
+        if outcome.correct {
+            println!("     correct: {}", md(&outcome.given));
+        } else if outcome.credit > 0.0 {
+            println!(
+                "     partly right ({:.0}%): {}, the answer is {}",
+                outcome.credit * 100.0,
+                md(&outcome.given),
+                md(&outcome.expected)
+            );
+        } else {
+            println!(
+                "     wrong: {}, the answer is {}",
+                md(&outcome.given),
+                md(&outcome.expected)
+            );
+        }
+        if let Some(pronunciation) = &outcome.pronunciation {
+            println!("     pronounced {}", pronunciation);
+        }
+        if let Some(feedback) = &outcome.feedback {
+            println!("     {}", md(feedback));
+        }
+        if let Some(explanation) = &outcome.explanation {
+            println!("     {}", md(explanation));
+        }
+        if let Some(mnemonic) = outcome.mnemonic.as_ref().filter(|_| !outcome.correct) {
+            println!("     remember: {}", md(mnemonic));
+        }
+    }
+}
+
+/// Asks the questions at `positions` in turn, grading each reply as it is given.
+///
+/// # Arguments
+///
+/// * `exercises` - The exercise groups the positions point into.
+/// * `positions` - The group and question index of every question to ask, in order.
+/// * `config` - Settings that control how the questions are shown.
+/// * `record` - Called with the position, exercise type, question text and outcome of
+///   every reply.
+///   In exam mode this is the only place the outcome goes; nothing is shown yet.
+///
+/// # Returns
+///
+/// The positions of the questions answered wrongly, or the prompt error that ended the
+/// round early.
+fn ask_round(
+    exercises: &[Exercise],
+    positions: Vec<(usize, usize)>,
+    config: &mut QuizConfig,
+    mut record: impl FnMut((usize, usize), &'static str, String, Outcome),
+) -> InquireResult<Vec<(usize, usize)>> {
+    let mut missed = Vec::new();
+    let mut last_variant = None;
+
+    for (g, q) in positions {
+        let exercise = &exercises[g];
+        let variant: &'static str = exercise.into();
+        if last_variant != Some(variant) {
+            println!("{}", variant);
+            last_variant = Some(variant);
+        }
+        if let Some(media) = &exercise.questions()[q].meta().media {
+            println!("  see {}", config.media_dir.join(media).display());
+        }
+
+        if let Some(command) = &config.tts {
+            if let Err(e) = speech::speak(command, &exercise.questions()[q].text()) {
+                println!(
+                    "Warning: can't run the text-to-speech command '{}': {}",
+                    command, e
+                );
+                config.tts = None;
+            }
+        }
+
+        let started = Instant::now();
+        let mut outcome = match config.time_limit {
+            Some(limit) => ask_timed(exercise, q, config, limit)?,
+            None => ask(exercise, q, config)?,
+        };
+        outcome.elapsed = started.elapsed();
+        let meta = exercise.questions()[q].meta();
+        outcome.explanation = meta.explanation.clone();
+        outcome.pronunciation = meta.pronunciation.clone();
+        outcome.mnemonic = meta.mnemonic.clone();
+        outcome.difficulty = meta.difficulty;
+        if config.ask_confidence {
+            outcome.confidence = Some(
+                Select::new("How confident were you?", Confidence::all())
+                    .with_starting_cursor(2)
+                    .prompt()?,
+            );
+        }
+        if !config.exam {
+            report(&outcome);
+            offer_links(exercises, exercise.questions()[q].meta())?;
+            if config.self_grade {
+                outcome.grade = Some(ask_grade(&outcome)?);
+            }
+        }
+        if !outcome.correct {
+            missed.push((g, q));
+        }
+        record((g, q), variant, exercise.questions()[q].text(), outcome);
+    }
+
+    Ok(missed)
+}
+
+/// Lets the learner star some of the questions just asked, to revisit them with `--starred`.
+///
+/// # Returns
+///
+/// The positions of the questions to star, none if the prompt was skipped.
+pub fn choose_stars(
+    exercises: &[Exercise],
+    positions: &[(usize, usize)],
+) -> InquireResult<Vec<(usize, usize)>> {
+    let texts: Vec<String> = positions
+        .iter()
+        .map(|&(g, q)| md(&exercises[g].questions()[q].text()))
+        .collect();
+    let starred = MultiSelect::new("Star questions to revisit?", texts)
+        .with_help_message("space to star, enter to confirm, esc to skip")
+        .raw_prompt_skippable()?
+        .unwrap_or_default();
+    Ok(starred.into_iter().map(|s| positions[s.index]).collect())
+}
+
+/// Offers to show the questions a question links to, with their answers.
+///
+/// Links to questions that are no longer in the deck are left out.
+fn offer_links(exercises: &[Exercise], meta: &Meta) -> InquireResult<()> {
+    let linked: Vec<(usize, usize)> = meta
+        .links
+        .iter()
+        .filter_map(|id| find_question(exercises, id).ok())
+        .collect();
+    if linked.is_empty()
+        || !Confirm::new(&format!("Show {} related questions?", linked.len()))
+            .with_default(false)
+            .prompt()?
+    {
+        return Ok(());
+    }
+
+    for (g, q) in linked {
+        let question = exercises[g].questions()[q].to_string();
+        println!("  {}", question.replace('\n', "\n  "));
+    }
+    Ok(())
+}
+
+/// Asks how well the learner knew the answer, starting from the grade the reply earned.
+fn ask_grade(outcome: &Outcome) -> InquireResult<Grade> {
+    let start = if outcome.correct { 2 } else { 0 };
+    Select::new("How well did you know it?", Grade::all())
+        .with_starting_cursor(start)
+        .prompt()
+}
+
+/// Returns the position of every question in the deck, as its group and its index within
+/// that group.
+pub fn positions(exercises: &[Exercise]) -> Vec<(usize, usize)> {
+    exercises
+        .iter()
+        .enumerate()
+        .flat_map(|(g, e)| (0..e.question_count()).map(move |q| (g, q)))
+        .collect()
+}
+
+/// Reorders questions so that consecutive ones are of different types where possible.
+///
+/// The types take turns in the order they first appear, and each type keeps the order its
+/// own questions were given in. Once a type runs out the others carry on without it.
+fn interleave(exercises: &[Exercise], positions: Vec<(usize, usize)>) -> Vec<(usize, usize)> {
+    let mut queues: Vec<(&'static str, Vec<(usize, usize)>)> = Vec::new();
+    for position in positions {
+        let variant: &'static str = (&exercises[position.0]).into();
+        match queues.iter_mut().find(|(v, _)| *v == variant) {
+            Some((_, queue)) => queue.push(position),
+            None => queues.push((variant, vec![position])),
+        }
+    }
+
+    let longest = queues.iter().map(|(_, q)| q.len()).max().unwrap_or(0);
+    let mut mixed = Vec::new();
+    for i in 0..longest {
+        mixed.extend(queues.iter().filter_map(|(_, queue)| queue.get(i)));
+    }
+    mixed
+}
+
+/// Picks `count` of the questions at `positions`, favouring the ones most often missed.
+///
+/// Each question is weighted by its share of wrong replies in its history, counting one
+/// imaginary right and one imaginary wrong reply so that new questions sit in the middle
+/// and a single reply doesn't decide everything. Questions are drawn without replacement
+/// using the Efraimidis-Spirakis method. The picked positions are returned in deck order.
+pub fn weighted_sample(
+    exercises: &[Exercise],
+    positions: Vec<(usize, usize)>,
+    count: usize,
+    rng: &mut Rng,
+) -> Vec<(usize, usize)> {
+    let mut keyed: Vec<(f64, (usize, usize))> = positions
+        .into_iter()
+        .map(|(g, q)| {
+            let history = &exercises[g].questions()[q].meta().history;
+            let missed = history.iter().filter(|a| !a.correct).count();
+            let weight = (missed + 1) as f64 / (history.len() + 2) as f64;
+            (rng.unit().powf(1.0 / weight), (g, q))
+        })
+        .collect();
+    keyed.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+    let mut picked: Vec<(usize, usize)> = keyed
+        .into_iter()
+        .take(count)
+        .map(|(_, position)| position)
+        .collect();
+    picked.sort_unstable();
+    picked
+}
+
+/// Plays back questions of the deck, grading each reply as it is given.
+///
+/// Questions are asked in the given order, or in random order across all groups when
+/// `config.shuffle` is set. The exercise type is shown whenever it changes. Afterwards the
+/// missed questions can be drilled again, round after round, until each has been answered
+/// correctly once; only the first round counts towards the session's score.
+///
+/// With `config.interleave` the exercise types take turns, so no two questions in a row
+/// share a type while more than one type is left.
+///
+/// In exam mode nothing is graded aloud until the end, when a report of every reply is
+/// shown, and there is no retry. Ending the exam early shows the report of the replies
+/// given so far.
+///
+/// With `config.time_limit`, a question that isn't answered in time is graded wrong as
+/// soon as the time runs out, see `ask_timed`.
+///
+/// # Arguments
+///
+/// * `exercises` - The exercise groups to ask from.
+/// * `positions` - The questions to ask, see `positions`.
+/// * `config` - Settings that control how the quiz is played.
+/// * `session` - Collects the outcome of every answered question, so that the results so
+///   far are kept when the quiz is ended early.
+/// * `on_answer` - Called with the position and outcome of every first-round reply.
+///
+/// # Returns
+///
+/// The prompt error that ended the quiz early, if any.
+pub fn run(
+    exercises: &[Exercise],
+    mut positions: Vec<(usize, usize)>,
+    config: &mut QuizConfig,
+    session: &mut SessionResult,
+    mut on_answer: impl FnMut((usize, usize), &Outcome),
+) -> InquireResult<()> {
+    if let Some(rng) = &mut config.shuffle {
+        rng.shuffle(&mut positions);
+    }
+    if config.interleave {
+        positions = interleave(exercises, positions);
+    }
+
+    if let Some(limit) = config.time_limit {
+        println!("Time limit: {}s per question", limit.as_secs());
+    }
+
+    let weights = config.weights;
+    if config.exam {
+        let mut answers = Vec::new();
+        let round = ask_round(
+            exercises,
+            positions,
+            config,
+            |position, variant, text, outcome| {
+                session.record(variant, &text, &outcome, &weights);
+                on_answer(position, &outcome);
+                answers.push((text, outcome));
+            },
+        );
+        // An exam that is ended early still reports the replies given so far.
+        if !answers.is_empty() {
+            print_exam_report(&answers);
+        }
+        return round.map(|_| ());
+    }
+
+    let mut missed = ask_round(
+        exercises,
+        positions,
+        config,
+        |position, variant, text, outcome| {
+            session.record(variant, &text, &outcome, &weights);
+            on_answer(position, &outcome);
+        },
+    )?;
+
+    while !missed.is_empty()
+        && Confirm::new(&format!("Retry the {} missed questions?", missed.len()))
+            .with_default(true)
+            .prompt()?
+    {
+        if let Some(rng) = &mut config.shuffle {
+            rng.shuffle(&mut missed);
+        }
+        // Retries aren't recorded, so there is nothing to grade or rate.
+        config.self_grade = false;
+        config.ask_confidence = false;
+        missed = ask_round(exercises, missed, config, |_, _, _, _| {})?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,56 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// A small xorshift64* pseudo-random number generator.
+///
+/// This is not suitable for anything security related, but it is more than enough for
+/// picking and shuffling exercises, and a fixed seed makes the choices reproducible.
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    /// Creates a generator from an explicit seed.
+    pub fn new(seed: u64) -> Rng {
+        const MIX: u64 = 0x9E37_79B9_7F4A_7C15;
+        // The state must never be zero, otherwise the generator only ever yields zero.
+        let state = seed ^ MIX;
+        Rng {
+            state: if state == 0 { MIX } else { state },
+        }
+    }
+
+    /// Creates a generator seeded from the current time.
+    pub fn from_time() -> Rng {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos() as u64)
+            .unwrap_or(0);
+        Rng::new(nanos)
+    }
+
+    /// Returns the next pseudo-random 64-bit value.
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    /// Returns a value in `0..n`. `n` must be greater than zero.
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+
+    /// Returns a value in `0.0..1.0`.
+    pub fn unit(&mut self) -> f64 {
+        // The top 53 bits fill an f64 mantissa exactly.
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Shuffles a slice in place using the Fisher-Yates algorithm.
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            items.swap(i, self.below(i + 1));
+        }
+    }
+}
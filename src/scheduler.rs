@@ -0,0 +1,338 @@
+use crate::date::Date;
+use crate::exercise::Exercise;
+use serde::{Deserialize, Serialize};
+
+/// The ease factor new questions start with.
+const INITIAL_EASE: f64 = 2.5;
+
+/// Ease factors never drop below this, or intervals would stop growing.
+const MIN_EASE: f64 = 1.3;
+
+/// The review intervals in days of the Leitner boxes, from the first box to the last.
+const LEITNER_INTERVALS: [u32; 5] = [1, 2, 4, 8, 16];
+
+/// The spaced repetition state of a single question.
+///
+/// Questions without a schedule have never been reviewed and are due immediately. The
+/// fields used depend on the `Strategy` that reviews the question; the others keep their
+/// defaults.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Schedule {
+    /// How quickly the interval grows after each successful review.
+    pub ease: f64,
+    /// The number of days until the next review.
+    pub interval: u32,
+    /// The number of successful reviews in a row.
+    pub repetitions: u32,
+    /// The day the question should next be reviewed.
+    pub due: Date,
+    /// The Leitner box the question is in, starting at 1; 0 until first reviewed by `Leitner`.
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub leitner_box: u32,
+}
+
+fn is_zero(n: &u32) -> bool {
+    *n == 0
+}
+
+impl Default for Schedule {
+    fn default() -> Schedule {
+        Schedule {
+            ease: INITIAL_EASE,
+            interval: 0,
+            repetitions: 0,
+            due: Date::today(),
+            leitner_box: 0,
+        }
+    }
+}
+
+impl Schedule {
+    /// Checks whether the question should be reviewed on `today`.
+    pub fn is_due(&self, today: Date) -> bool {
+        self.due <= today
+    }
+}
+
+/// A way of rescheduling questions after they have been reviewed.
+pub trait Strategy {
+    /// Updates a question's schedule after a review.
+    ///
+    /// # Arguments
+    ///
+    /// * `schedule` - The question's schedule, updated in place.
+    /// * `quality` - How well the question was recalled, from 0 (blackout) to 5 (perfect).
+    ///   Anything below 3 counts as a lapse.
+    /// * `today` - The day of the review.
+    fn review(&self, schedule: &mut Schedule, quality: u8, today: Date);
+}
+
+/// The SM-2 algorithm, which grows each question's interval by its own ease factor.
+pub struct Sm2;
+
+impl Strategy for Sm2 {
+    fn review(&self, schedule: &mut Schedule, quality: u8, today: Date) {
+        let quality = quality.min(5);
+
+        if quality < 3 {
+            schedule.repetitions = 0;
+            schedule.interval = 1;
+        } else {
+            schedule.repetitions += 1;
+            schedule.interval = match schedule.repetitions {
+                1 => 1,
+                2 => 6,
+                _ => (f64::from(schedule.interval) * schedule.ease).round() as u32,
+            };
+        }
+
+        let miss = f64::from(5 - quality);
+        schedule.ease = (schedule.ease + 0.1 - miss * (0.08 + miss * 0.02)).max(MIN_EASE);
+        schedule.due = today.add_days(i64::from(schedule.interval));
+    }
+}
+
+/// The Leitner system: a recalled question moves up one box, a lapse sends it back to the
+/// first box, and each box has a fixed interval.
+pub struct Leitner;
+
+impl Strategy for Leitner {
+    fn review(&self, schedule: &mut Schedule, quality: u8, today: Date) {
+        let last_box = LEITNER_INTERVALS.len() as u32;
+        schedule.leitner_box = if quality < 3 {
+            1
+        } else {
+            (schedule.leitner_box + 1).min(last_box)
+        };
+
+        schedule.interval = LEITNER_INTERVALS[schedule.leitner_box as usize - 1];
+        schedule.due = today.add_days(i64::from(schedule.interval));
+    }
+}
+
+/// The names accepted by `strategy`.
+const STRATEGIES: [&str; 2] = ["sm2", "leitner"];
+
+/// Looks up a scheduling strategy by name, case-insensitively.
+pub fn strategy(name: &str) -> Result<Box<dyn Strategy>, String> {
+    match name.to_lowercase().as_str() {
+        "sm2" => Ok(Box::new(Sm2)),
+        "leitner" => Ok(Box::new(Leitner)),
+        _ => Err(format!(
+            "Unknown scheduler '{}' (valid schedulers: {})",
+            name,
+            STRATEGIES.join(", ")
+        )),
+    }
+}
+
+/// How well the learner says they knew a question, as offered after each reply.
+#[derive(Serialize, Deserialize, strum_macros::Display, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Grade {
+    Again,
+    Hard,
+    Good,
+    Easy,
+}
+
+impl Grade {
+    /// Returns a list of all grades, from worst to best.
+    pub fn all() -> Vec<Grade> {
+        vec![Self::Again, Self::Hard, Self::Good, Self::Easy]
+    }
+
+    /// The grade a right or wrong reply gets when the learner doesn't grade it.
+    pub fn from_correct(correct: bool) -> Grade {
+        if correct {
+            Self::Good
+        } else {
+            Self::Again
+        }
+    }
+
+    /// Maps the grade to an SM-2 quality; only `Again` counts as a lapse.
+    pub fn quality(self) -> u8 {
+        match self {
+            Self::Again => 1,
+            Self::Hard => 3,
+            Self::Good => 4,
+            Self::Easy => 5,
+        }
+    }
+}
+
+/// How many questions a day's reviews may include.
+pub struct DailyLimits {
+    /// Questions that have never been reviewed before.
+    pub new: usize,
+    /// Questions that are due again.
+    pub reviews: usize,
+}
+
+/// The questions to review today, see `review_queue`.
+pub struct Queue {
+    /// The position of every question to review, as its group and its index in the group.
+    pub positions: Vec<(usize, usize)>,
+    /// How many due questions were left out because of the daily limits.
+    pub carried_over: usize,
+}
+
+/// Picks the questions to review on `today` within the daily limits.
+///
+/// Suspended questions are left out, and questions already reviewed earlier today count
+/// towards the limits; only scheduled reviews count, not quiz or cram replies. Due questions come first, the most overdue first, followed by new
+/// questions in deck order. Whatever doesn't fit stays due and is picked up on a later day.
+pub fn review_queue(exercises: &[Exercise], today: Date, limits: &DailyLimits) -> Queue {
+    let mut new = Vec::new();
+    let mut due = Vec::new();
+    let mut new_done = 0;
+    let mut reviews_done = 0;
+
+    for (g, exercise) in exercises.iter().enumerate() {
+        for (q, question) in exercise.questions().into_iter().enumerate() {
+            let meta = question.meta();
+
+            let days: Vec<Date> = meta
+                .history
+                .iter()
+                .filter(|a| a.scheduled)
+                .map(|a| Date::from_unix(a.time))
+                .collect();
+            if days.contains(&today) {
+                if days[0] == today {
+                    new_done += 1;
+                } else {
+                    reviews_done += 1;
+                }
+            }
+
+            match &meta.schedule {
+                _ if meta.suspended => {}
+                None => new.push((g, q)),
+                Some(schedule) if schedule.is_due(today) => due.push((schedule.due, (g, q))),
+                Some(_) => {}
+            }
+        }
+    }
+    due.sort_by_key(|&(date, _)| date);
+
+    let due_count = due.len() + new.len();
+    let mut positions: Vec<(usize, usize)> = due
+        .into_iter()
+        .map(|(_, position)| position)
+        .take(limits.reviews.saturating_sub(reviews_done))
+        .collect();
+    positions.extend(new.into_iter().take(limits.new.saturating_sub(new_done)));
+
+    Queue {
+        carried_over: due_count - positions.len(),
+        positions,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history::{unix_now, Attempt};
+    use serde_json::json;
+
+    /// Reviews a fresh schedule with each quality in turn, returning the intervals.
+    fn intervals(strategy: &dyn Strategy, qualities: &[u8], today: Date) -> Vec<u32> {
+        let mut schedule = Schedule::default();
+        qualities
+            .iter()
+            .map(|&quality| {
+                strategy.review(&mut schedule, quality, today);
+                assert!(schedule.due == today.add_days(i64::from(schedule.interval)));
+                schedule.interval
+            })
+            .collect()
+    }
+
+    /// Returns a deck of `n` yes or no questions that have never been reviewed.
+    fn deck(n: usize) -> Vec<Exercise> {
+        let data: Vec<_> = (0..n)
+            .map(|i| json!({ "question": format!("Q{}", i), "answer": true }))
+            .collect();
+        serde_json::from_value(json!([{ "type": "YesNo", "data": data }])).unwrap()
+    }
+
+    /// Records a reply given just now to question `q` of the deck.
+    fn reply(exercises: &mut [Exercise], q: usize, scheduled: bool) {
+        let mut attempt = Attempt::new(true, std::time::Duration::ZERO, None);
+        attempt.scheduled = scheduled;
+        exercises[0].questions_mut()[q]
+            .meta_mut()
+            .history
+            .push(attempt);
+    }
+
+    #[test]
+    fn sm2_grows_the_interval_by_the_ease_and_resets_it_on_a_lapse() {
+        let today = Date::today();
+        assert_eq!(intervals(&Sm2, &[4, 4, 4, 1, 4], today), [1, 6, 15, 1, 1]);
+
+        let mut schedule = Schedule::default();
+        Sm2.review(&mut schedule, 5, today);
+        assert!((schedule.ease - 2.6).abs() < 1e-9);
+        Sm2.review(&mut schedule, 3, today);
+        assert!((schedule.ease - 2.46).abs() < 1e-9);
+        for _ in 0..10 {
+            Sm2.review(&mut schedule, 0, today);
+        }
+        assert_eq!(schedule.repetitions, 0);
+        assert!(schedule.ease == MIN_EASE);
+    }
+
+    #[test]
+    fn leitner_moves_up_one_box_and_back_to_the_first_on_a_lapse() {
+        let today = Date::today();
+        assert_eq!(
+            intervals(&Leitner, &[4, 4, 4, 4, 4, 4, 1, 3], today),
+            [1, 2, 4, 8, 16, 16, 1, 2]
+        );
+        let mut schedule = Schedule::default();
+        Leitner.review(&mut schedule, 0, today);
+        assert_eq!(schedule.leitner_box, 1);
+    }
+
+    #[test]
+    fn review_queue_keeps_to_the_daily_limits_with_the_most_overdue_first() {
+        let today = Date::from_unix(unix_now());
+        let mut exercises = deck(6);
+        for (q, days_ago) in [(3, 1), (4, 5), (5, 0)] {
+            exercises[0].questions_mut()[q].meta_mut().schedule = Some(Schedule {
+                due: today.add_days(-days_ago),
+                ..Schedule::default()
+            });
+        }
+        let limits = DailyLimits { new: 2, reviews: 2 };
+        let queue = review_queue(&exercises, today, &limits);
+        assert_eq!(queue.positions, [(0, 4), (0, 3), (0, 0), (0, 1)]);
+        assert_eq!(queue.carried_over, 2);
+
+        exercises[0].questions_mut()[0].meta_mut().suspended = true;
+        let queue = review_queue(&exercises, today, &limits);
+        assert_eq!(queue.positions, [(0, 4), (0, 3), (0, 1), (0, 2)]);
+    }
+
+    #[test]
+    fn review_queue_counts_only_scheduled_reviews_towards_the_limits() {
+        let today = Date::from_unix(unix_now());
+        let limits = DailyLimits {
+            new: 2,
+            reviews: 10,
+        };
+        let mut exercises = deck(5);
+
+        reply(&mut exercises, 3, false);
+        reply(&mut exercises, 4, false);
+        let queue = review_queue(&exercises, today, &limits);
+        assert_eq!(queue.positions, [(0, 0), (0, 1)]);
+
+        reply(&mut exercises, 4, true);
+        let queue = review_queue(&exercises, today, &limits);
+        assert_eq!(queue.positions, [(0, 0)]);
+    }
+}
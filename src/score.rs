@@ -0,0 +1,218 @@
+use crate::exercise::Difficulty;
+use crate::quiz::Outcome;
+use serde::Serialize;
+use std::fmt;
+use std::str::FromStr;
+
+/// The tally of one exercise type within a session.
+#[derive(Serialize)]
+pub struct TypeScore {
+    pub kind: String,
+    pub correct: usize,
+    pub total: usize,
+}
+
+/// A question that was answered wrongly.
+#[derive(Serialize)]
+pub struct Missed {
+    pub kind: String,
+    pub question: String,
+    pub expected: String,
+}
+
+/// How many points each hint takes off a correct reply.
+const HINT_PENALTY: f64 = 0.25;
+
+/// How much a reply counts for by the difficulty of its question, see `--weights`.
+///
+/// Questions without a difficulty count as medium ones.
+#[derive(Clone, Copy)]
+pub struct Weights {
+    pub easy: f64,
+    pub medium: f64,
+    pub hard: f64,
+}
+
+impl Default for Weights {
+    fn default() -> Weights {
+        Weights {
+            easy: 1.0,
+            medium: 2.0,
+            hard: 3.0,
+        }
+    }
+}
+
+impl Weights {
+    /// Returns the weight of a question of the given difficulty.
+    pub fn of(&self, difficulty: Option<Difficulty>) -> f64 {
+        match difficulty {
+            Some(Difficulty::Easy) => self.easy,
+            Some(Difficulty::Medium) | None => self.medium,
+            Some(Difficulty::Hard) => self.hard,
+        }
+    }
+}
+
+/// Reads weights written as `easy,medium,hard`, e.g. `1,2,3`.
+impl FromStr for Weights {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            format!(
+                "Invalid weights '{}': expected three numbers for easy, medium and hard, e.g. 1,2,3",
+                text
+            )
+        };
+        let weights: Vec<f64> = text
+            .split(',')
+            .map(|w| w.trim().parse::<f64>())
+            .collect::<Result<_, _>>()
+            .map_err(|_| invalid())?;
+        match weights[..] {
+            [easy, medium, hard] if weights.iter().all(|w| w.is_finite() && *w >= 0.0) => {
+                Ok(Weights { easy, medium, hard })
+            }
+            _ => Err(invalid()),
+        }
+    }
+}
+
+/// The results of a quiz session, built up as questions are answered.
+#[derive(Serialize, Default)]
+pub struct SessionResult {
+    pub correct: usize,
+    pub wrong: usize,
+    /// One point per correct reply and part of one for a partly right reply, less
+    /// `HINT_PENALTY` for every hint it needed.
+    pub points: f64,
+    pub hints: usize,
+    /// The points of every reply times the weight of its question's difficulty.
+    pub weighted_points: f64,
+    /// The weighted points every reply would have earned if it had been right.
+    pub weighted_total: f64,
+    /// How many replies were to questions with a difficulty; the weighted score is only
+    /// shown when there were some.
+    pub rated: usize,
+    /// Scores per exercise type, in the order the types were first asked.
+    pub by_type: Vec<TypeScore>,
+    pub missed: Vec<Missed>,
+}
+
+/// Rounds points to two decimals, so that whole numbers print without any.
+fn rounded(points: f64) -> f64 {
+    (points * 100.0).round() / 100.0
+}
+
+/// Returns `part` as a percentage of `total`, or 0 when nothing was asked.
+pub fn percentage(part: usize, total: usize) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        part as f64 * 100.0 / total as f64
+    }
+}
+
+impl SessionResult {
+    /// Adds the outcome of one question to the tally.
+    ///
+    /// # Arguments
+    ///
+    /// * `kind` - The exercise type the question belongs to, e.g. `Mcq`.
+    /// * `question` - The question text, listed if the reply was wrong.
+    /// * `outcome` - The graded reply.
+    /// * `weights` - How much the reply counts for by its question's difficulty.
+    pub fn record(&mut self, kind: &str, question: &str, outcome: &Outcome, weights: &Weights) {
+        let index = match self.by_type.iter().position(|t| t.kind == kind) {
+            Some(i) => i,
+            None => {
+                self.by_type.push(TypeScore {
+                    kind: kind.to_string(),
+                    correct: 0,
+                    total: 0,
+                });
+                self.by_type.len() - 1
+            }
+        };
+        let score = &mut self.by_type[index];
+        score.total += 1;
+
+        self.hints += outcome.hints;
+        let points = (outcome.credit - HINT_PENALTY * outcome.hints as f64).max(0.0);
+        let weight = weights.of(outcome.difficulty);
+        self.points += points;
+        self.weighted_points += points * weight;
+        self.weighted_total += weight;
+        if outcome.difficulty.is_some() {
+            self.rated += 1;
+        }
+        if outcome.correct {
+            self.correct += 1;
+            score.correct += 1;
+        } else {
+            self.wrong += 1;
+            self.missed.push(Missed {
+                kind: kind.to_string(),
+                question: question.to_string(),
+                expected: outcome.expected.clone(),
+            });
+        }
+    }
+
+    /// Returns the number of questions answered.
+    pub fn total(&self) -> usize {
+        self.correct + self.wrong
+    }
+}
+
+impl fmt::Display for SessionResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "{} correct, {} wrong ({:.0}%)",
+            self.correct,
+            self.wrong,
+            percentage(self.correct, self.total())
+        )?;
+        if self.hints > 0 || self.points != self.correct as f64 {
+            write!(f, "{:.2} of {} points", self.points, self.total())?;
+            if self.hints > 0 {
+                write!(f, " after {} hints", self.hints)?;
+            }
+            writeln!(f)?;
+        }
+        if self.rated > 0 {
+            writeln!(
+                f,
+                "{}/{} correct ({}/{} points weighted by difficulty)",
+                self.correct,
+                self.total(),
+                rounded(self.weighted_points),
+                rounded(self.weighted_total)
+            )?;
+        }
+        for score in &self.by_type {
+            writeln!(
+                f,
+                "  {}: {}/{} ({:.0}%)",
+                score.kind,
+                score.correct,
+                score.total,
+                percentage(score.correct, score.total)
+            )?;
+        }
+
+        if !self.missed.is_empty() {
+            writeln!(f, "Missed:")?;
+            for missed in &self.missed {
+                writeln!(
+                    f,
+                    "  [{}] {} -> {}",
+                    missed.kind, missed.question, missed.expected
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
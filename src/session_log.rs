@@ -0,0 +1,43 @@
+use crate::score::SessionResult;
+use serde::Serialize;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// The file, next to the data file, that every finished quiz and review session is logged
+/// to, one JSON object per line.
+pub const SESSION_LOG: &str = "sessions.jsonl";
+
+/// Returns the session log belonging to `data_file`.
+pub fn log_file(data_file: &str) -> PathBuf {
+    Path::new(data_file)
+        .parent()
+        .unwrap_or(Path::new(""))
+        .join(SESSION_LOG)
+}
+
+/// One line of the session log.
+#[derive(Serialize)]
+pub struct SessionRecord<'a> {
+    /// The command that ran the session, e.g. `quiz` or `review`.
+    pub mode: &'static str,
+    /// When the session started and ended, in seconds since the Unix epoch.
+    pub started: u64,
+    pub ended: u64,
+    #[serde(flatten)]
+    pub result: &'a SessionResult,
+}
+
+/// Appends a session to the log at `file_path`, creating the file if needed.
+///
+/// The log is kept apart from the data file so that it only ever grows by whole lines.
+pub fn append(file_path: &Path, record: &SessionRecord) -> Result<(), Box<dyn std::error::Error>> {
+    let mut line = serde_json::to_string(record)?;
+    line.push('\n');
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(file_path)?
+        .write_all(line.as_bytes())?;
+    Ok(())
+}
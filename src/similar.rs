@@ -0,0 +1,120 @@
+use crate::exercise::Exercise;
+use serde::Serialize;
+
+/// A question's text and its position in the deck, numbered from 1 like `--list` shows it.
+#[derive(Clone, Serialize)]
+pub struct Location {
+    pub group: usize,
+    pub question: usize,
+    pub text: String,
+}
+
+/// Two questions whose texts are at least as similar as the requested threshold.
+#[derive(Serialize)]
+pub struct SimilarPair {
+    pub similarity: f64,
+    pub first: Location,
+    pub second: Location,
+}
+
+/// Normalizes a question for comparison: lowercase, punctuation removed, whitespace collapsed.
+pub fn normalize(text: &str) -> String {
+    text.to_lowercase()
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { ' ' })
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Returns the sorted character bigrams of a normalized text.
+fn bigrams(text: &str) -> Vec<(char, char)> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut pairs: Vec<(char, char)> = chars.windows(2).map(|w| (w[0], w[1])).collect();
+    pairs.sort_unstable();
+    pairs
+}
+
+/// Computes the Sørensen-Dice coefficient of two sorted bigram lists, from 0.0 to 1.0.
+fn dice(a: &[(char, char)], b: &[(char, char)]) -> f64 {
+    if a.is_empty() && b.is_empty() {
+        return 1.0;
+    }
+
+    // Count the shared bigrams by walking both sorted lists at once.
+    let (mut i, mut j, mut shared) = (0, 0, 0);
+    while i < a.len() && j < b.len() {
+        match a[i].cmp(&b[j]) {
+            std::cmp::Ordering::Less => i += 1,
+            std::cmp::Ordering::Greater => j += 1,
+            std::cmp::Ordering::Equal => {
+                shared += 1;
+                i += 1;
+                j += 1;
+            }
+        }
+    }
+    2.0 * shared as f64 / (a.len() + b.len()) as f64
+}
+
+/// Finds pairs of questions whose texts are nearly the same.
+///
+/// Texts are compared after normalization using the Dice coefficient of their character
+/// bigrams, which tolerates changed punctuation, a changed word or a typo. Pairs whose
+/// lengths alone rule out reaching the threshold are skipped without comparing them.
+///
+/// # Arguments
+///
+/// * `exercises` - The deck to search.
+/// * `threshold` - The minimum similarity, from 0.0 to 1.0, for a pair to be reported.
+///
+/// # Returns
+///
+/// The similar pairs, most similar first.
+pub fn find_similar(exercises: &[Exercise], threshold: f64) -> Vec<SimilarPair> {
+    let questions: Vec<(Location, Vec<(char, char)>)> = exercises
+        .iter()
+        .enumerate()
+        .flat_map(|(g, e)| {
+            e.questions()
+                .into_iter()
+                .enumerate()
+                .map(move |(q, question)| {
+                    let text = question.text();
+                    let grams = bigrams(&normalize(&text));
+                    let location = Location {
+                        group: g + 1,
+                        question: q + 1,
+                        text,
+                    };
+                    (location, grams)
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect();
+
+    let mut pairs = Vec::new();
+    for (i, (first, a)) in questions.iter().enumerate() {
+        for (second, b) in &questions[i + 1..] {
+            // At best every bigram of the shorter text is shared, which bounds the score.
+            let (short, total) = (a.len().min(b.len()), a.len() + b.len());
+            let best = 2.0 * short as f64 / total.max(1) as f64;
+            if best < threshold {
+                continue;
+            }
+
+            let similarity = dice(a, b);
+            if similarity >= threshold {
+                pairs.push(SimilarPair {
+                    similarity,
+                    first: first.clone(),
+                    second: second.clone(),
+                });
+            }
+        }
+    }
+
+    pairs.sort_by(|a, b| b.similarity.total_cmp(&a.similarity));
+    pairs
+}
@@ -0,0 +1,35 @@
+use std::io::{self, Write};
+use std::process::{Command, Stdio};
+use std::thread;
+
+/// The environment variable holding the text-to-speech command for `--tts`, e.g. `say`
+/// on macOS or `espeak` on Linux, optionally followed by its arguments.
+pub const TTS_VAR: &str = "WORD_POWER_TTS";
+
+/// Reads `text` aloud by piping it to the standard input of `command`.
+///
+/// The command is split on whitespace into the program and its arguments, and is not
+/// waited for, so that the prompt is shown while the text is spoken.
+pub fn speak(command: &str, text: &str) -> io::Result<()> {
+    let mut words = command.split_whitespace();
+    let program = words
+        .next()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "empty command"))?;
+    let mut child = Command::new(program)
+        .args(words)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    let mut stdin = child.stdin.take();
+    let text = text.to_string();
+    thread::spawn(move || {
+        if let Some(stdin) = &mut stdin {
+            // A command that exits early just stops reading; there is nothing to report.
+            let _ = stdin.write_all(text.as_bytes());
+        }
+        drop(stdin);
+        let _ = child.wait();
+    });
+    Ok(())
+}
@@ -0,0 +1,561 @@
+use crate::chart::bar_chart;
+use crate::date::Date;
+use crate::exercise::{Exercise, Question};
+use crate::goal::Goal;
+use crate::score::percentage;
+use crate::similar::Location;
+use serde::Serialize;
+use std::fmt;
+use std::io::{self, Write};
+
+/// Totals for one exercise type, or for the whole deck.
+#[derive(Serialize, Default)]
+pub struct Tally {
+    pub questions: usize,
+    pub attempts: usize,
+    pub correct: usize,
+    /// The summed response time of every attempt, in milliseconds.
+    pub millis: u64,
+}
+
+impl Tally {
+    /// Adds a question and its history to the totals.
+    fn add(&mut self, question: &dyn Question) {
+        let history = &question.meta().history;
+        self.questions += 1;
+        self.attempts += history.len();
+        self.correct += history.iter().filter(|a| a.correct).count();
+        self.millis += history.iter().map(|a| a.millis).sum::<u64>();
+    }
+
+    /// Returns the average response time in seconds, or `None` before the first attempt.
+    pub fn average_seconds(&self) -> Option<f64> {
+        if self.attempts == 0 {
+            None
+        } else {
+            Some(self.millis as f64 / 1000.0 / self.attempts as f64)
+        }
+    }
+}
+
+/// The totals of one exercise type.
+#[derive(Serialize)]
+pub struct TypeStats {
+    pub kind: &'static str,
+    #[serde(flatten)]
+    pub tally: Tally,
+}
+
+/// How many days back `stats` counts the attempts per day, including today.
+const CHART_DAYS: usize = 30;
+
+/// The width of the longest bar in the charts, in cells.
+const CHART_WIDTH: usize = 40;
+
+/// The number of attempts made on one day.
+#[derive(Serialize)]
+pub struct DayCount {
+    pub day: Date,
+    pub attempts: usize,
+    /// The summed response time of the attempts, in milliseconds.
+    pub millis: u64,
+}
+
+/// Totals aggregated from the history of every question in the deck.
+#[derive(Serialize, Default)]
+pub struct Stats {
+    #[serde(flatten)]
+    pub overall: Tally,
+    /// Totals per exercise type, in the order the types first appear in the deck.
+    pub by_type: Vec<TypeStats>,
+    /// Attempts on each of the last `CHART_DAYS` days, oldest first.
+    pub per_day: Vec<DayCount>,
+    /// Days in a row with a review session, up to today or yesterday.
+    pub current_streak: usize,
+    pub longest_streak: usize,
+    /// The daily goal, if one is set.
+    pub goal: Option<Goal>,
+}
+
+impl Stats {
+    /// Returns the number of days in `per_day` that met the goal, or 0 without a goal.
+    pub fn goal_days(&self) -> usize {
+        match self.goal {
+            Some(goal) => self
+                .per_day
+                .iter()
+                .filter(|c| goal.is_met(c.attempts, c.millis))
+                .count(),
+            None => 0,
+        }
+    }
+}
+
+/// Adds up the attempt history of every question in `exercises`, with `today` as the last
+/// day of the daily counts.
+pub fn stats(exercises: &[Exercise], today: Date) -> Stats {
+    let first_day = today.add_days(1 - CHART_DAYS as i64);
+    let mut stats = Stats {
+        per_day: (0..CHART_DAYS)
+            .map(|i| DayCount {
+                day: first_day.add_days(i as i64),
+                attempts: 0,
+                millis: 0,
+            })
+            .collect(),
+        ..Stats::default()
+    };
+    for exercise in exercises {
+        let kind: &'static str = exercise.into();
+        let index = match stats.by_type.iter().position(|t| t.kind == kind) {
+            Some(i) => i,
+            None => {
+                stats.by_type.push(TypeStats {
+                    kind,
+                    tally: Tally::default(),
+                });
+                stats.by_type.len() - 1
+            }
+        };
+
+        for question in exercise.questions() {
+            stats.overall.add(question);
+            stats.by_type[index].tally.add(question);
+            for attempt in &question.meta().history {
+                let day = Date::from_unix(attempt.time);
+                if let Some(count) = stats.per_day.iter_mut().find(|c| c.day == day) {
+                    count.attempts += 1;
+                    count.millis += attempt.millis;
+                }
+            }
+        }
+    }
+    stats
+}
+
+/// Formats the accuracy and response time of a tally, e.g. `75% correct, 2.4s average`.
+fn summary(tally: &Tally) -> String {
+    match tally.average_seconds() {
+        Some(seconds) => format!(
+            "{:.0}% correct, {:.1}s average",
+            percentage(tally.correct, tally.attempts),
+            seconds
+        ),
+        None => "not practiced yet".to_string(),
+    }
+}
+
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "{} questions, {} attempts, {}",
+            self.overall.questions,
+            self.overall.attempts,
+            summary(&self.overall)
+        )?;
+        for by_type in &self.by_type {
+            writeln!(
+                f,
+                "  {}: {} questions, {} attempts, {}",
+                by_type.kind,
+                by_type.tally.questions,
+                by_type.tally.attempts,
+                summary(&by_type.tally)
+            )?;
+        }
+        if self.overall.attempts > 0 {
+            let rows: Vec<(String, f64, String)> = self
+                .per_day
+                .iter()
+                .map(|c| (c.day.to_string(), c.attempts as f64, c.attempts.to_string()))
+                .collect();
+            let max = self.per_day.iter().map(|c| c.attempts).max().unwrap_or(0);
+            writeln!(f, "Attempts per day:")?;
+            write!(f, "{}", bar_chart(&rows, max as f64, CHART_WIDTH))?;
+
+            let rows: Vec<(String, f64, String)> = self
+                .by_type
+                .iter()
+                .filter(|t| t.tally.attempts > 0)
+                .map(|t| {
+                    let accuracy = percentage(t.tally.correct, t.tally.attempts);
+                    (t.kind.to_string(), accuracy, format!("{:.0}%", accuracy))
+                })
+                .collect();
+            writeln!(f, "Accuracy per type:")?;
+            write!(f, "{}", bar_chart(&rows, 100.0, CHART_WIDTH))?;
+        }
+        if let Some(goal) = self.goal {
+            writeln!(
+                f,
+                "Goal of {} met on {} of the last {} days",
+                goal,
+                self.goal_days(),
+                self.per_day.len()
+            )?;
+        }
+        if self.longest_streak > 0 {
+            writeln!(
+                f,
+                "Streak: {} days, longest {} days",
+                self.current_streak, self.longest_streak
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// How many questions share a label, such as an exercise type.
+#[derive(Serialize)]
+pub struct Count {
+    pub label: String,
+    pub questions: usize,
+    /// Questions without a single attempt.
+    pub unpracticed: usize,
+}
+
+/// An inventory of the questions in the deck, for spotting gaps while authoring.
+#[derive(Serialize)]
+pub struct Bank {
+    pub questions: usize,
+    pub unpracticed: usize,
+    /// Counts per exercise type, in the order the types first appear in the deck.
+    pub by_type: Vec<Count>,
+    /// Counts per tag, sorted by tag; a question with several tags counts under each.
+    pub by_tag: Vec<Count>,
+    /// Questions without any tag.
+    pub untagged: usize,
+    /// Counts per chapter, in the order the chapters first appear in the deck.
+    pub by_chapter: Vec<Count>,
+}
+
+/// Adds a question to the count labelled `label`, ignoring case, starting a new count if
+/// there is none.
+fn count(counts: &mut Vec<Count>, label: &str, practiced: bool) {
+    let index = match counts
+        .iter()
+        .position(|c| c.label.eq_ignore_ascii_case(label))
+    {
+        Some(i) => i,
+        None => {
+            counts.push(Count {
+                label: label.to_string(),
+                questions: 0,
+                unpracticed: 0,
+            });
+            counts.len() - 1
+        }
+    };
+    counts[index].questions += 1;
+    counts[index].unpracticed += usize::from(!practiced);
+}
+
+/// Counts the questions in `exercises`, overall, per exercise type, per tag and per chapter.
+pub fn bank(exercises: &[Exercise]) -> Bank {
+    let mut bank = Bank {
+        questions: 0,
+        unpracticed: 0,
+        by_type: Vec::new(),
+        by_tag: Vec::new(),
+        untagged: 0,
+        by_chapter: Vec::new(),
+    };
+    for exercise in exercises {
+        let kind: &'static str = exercise.into();
+        for question in exercise.questions() {
+            let practiced = !question.meta().history.is_empty();
+            bank.questions += 1;
+            bank.unpracticed += usize::from(!practiced);
+            count(&mut bank.by_type, kind, practiced);
+            for tag in &question.meta().tags {
+                count(&mut bank.by_tag, tag, practiced);
+            }
+            bank.untagged += usize::from(question.meta().tags.is_empty());
+            if let Some(chapter) = &question.meta().chapter {
+                count(&mut bank.by_chapter, chapter, practiced);
+            }
+        }
+    }
+    bank.by_tag.sort_by_key(|c| c.label.to_lowercase());
+    bank
+}
+
+impl fmt::Display for Bank {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "{} questions, {} never practiced",
+            self.questions, self.unpracticed
+        )?;
+        let groups = [
+            ("By type:", &self.by_type),
+            ("By chapter:", &self.by_chapter),
+            ("By tag:", &self.by_tag),
+        ];
+        for (heading, counts) in groups {
+            if counts.is_empty() {
+                continue;
+            }
+            writeln!(f, "{}", heading)?;
+            for count in counts {
+                writeln!(
+                    f,
+                    "  {}: {} questions, {} never practiced",
+                    count.label, count.questions, count.unpracticed
+                )?;
+            }
+        }
+        if !self.by_tag.is_empty() {
+            writeln!(f, "{} questions without tags", self.untagged)?;
+        }
+        Ok(())
+    }
+}
+
+/// The attempts made during one week.
+pub struct Week {
+    /// The first day of the week.
+    pub start: Date,
+    pub attempts: usize,
+    pub correct: usize,
+}
+
+impl Week {
+    /// Returns the percentage of correct attempts, or `None` for a week without any.
+    pub fn accuracy(&self) -> Option<f64> {
+        if self.attempts == 0 {
+            None
+        } else {
+            Some(percentage(self.correct, self.attempts))
+        }
+    }
+}
+
+/// Adds up the attempts of the last `weeks` seven-day weeks, the last one ending `today`.
+///
+/// # Returns
+///
+/// One entry per week, oldest first.
+pub fn weekly_accuracy(exercises: &[Exercise], today: Date, weeks: usize) -> Vec<Week> {
+    let first_day = today.add_days(1 - 7 * weeks as i64);
+    let mut totals: Vec<Week> = (0..weeks)
+        .map(|i| Week {
+            start: first_day.add_days(7 * i as i64),
+            attempts: 0,
+            correct: 0,
+        })
+        .collect();
+
+    for exercise in exercises {
+        for question in exercise.questions() {
+            for attempt in &question.meta().history {
+                let day = Date::from_unix(attempt.time);
+                if let Some(week) = totals
+                    .iter_mut()
+                    .rev()
+                    .find(|w| w.start <= day && day <= today)
+                {
+                    week.attempts += 1;
+                    week.correct += usize::from(attempt.correct);
+                }
+            }
+        }
+    }
+    totals
+}
+
+/// A question with enough attempts to rank among the hardest.
+#[derive(Serialize)]
+pub struct Hard {
+    #[serde(flatten)]
+    pub location: Location,
+    pub kind: &'static str,
+    pub tags: Vec<String>,
+    pub attempts: usize,
+    pub missed: usize,
+}
+
+impl Hard {
+    /// Returns the share of attempts that were wrong, as a percentage.
+    pub fn miss_rate(&self) -> f64 {
+        percentage(self.missed, self.attempts)
+    }
+}
+
+/// Ranks the questions with at least `min_attempts` attempts by how often they were missed.
+///
+/// Ties are broken by the number of misses, so a question missed 4 times out of 4 ranks
+/// above one missed once out of once.
+pub fn hardest(exercises: &[Exercise], min_attempts: usize) -> Vec<Hard> {
+    let mut hard = Vec::new();
+    for (g, exercise) in exercises.iter().enumerate() {
+        let kind: &'static str = exercise.into();
+        for (q, question) in exercise.questions().into_iter().enumerate() {
+            let history = &question.meta().history;
+            if history.is_empty() || history.len() < min_attempts {
+                continue;
+            }
+            hard.push(Hard {
+                location: Location {
+                    group: g + 1,
+                    question: q + 1,
+                    text: question.text(),
+                },
+                kind,
+                tags: question.meta().tags.clone(),
+                attempts: history.len(),
+                missed: history.iter().filter(|a| !a.correct).count(),
+            });
+        }
+    }
+    hard.sort_by(|a, b| {
+        b.miss_rate()
+            .total_cmp(&a.miss_rate())
+            .then(b.missed.cmp(&a.missed))
+    });
+    hard
+}
+
+/// How long the correct replies to a question took.
+#[derive(Serialize)]
+pub struct Latency {
+    #[serde(flatten)]
+    pub location: Location,
+    pub kind: &'static str,
+    /// The number of correct replies the times are taken from.
+    pub correct: usize,
+    pub average_millis: u64,
+    pub median_millis: u64,
+    pub p90_millis: u64,
+}
+
+/// Returns the `p`th percentile of sorted values by the nearest-rank method.
+fn percentile(sorted: &[u64], p: usize) -> u64 {
+    let rank = (p * sorted.len()).div_ceil(100).max(1);
+    sorted[rank - 1]
+}
+
+/// Ranks the questions with at least `min_correct` correct replies by how slowly they were
+/// answered, slowest median first.
+///
+/// Only correct replies count: a question that is answered right but slowly hasn't really
+/// been learned yet, while wrong replies are already covered by `hardest`.
+pub fn slowest(exercises: &[Exercise], min_correct: usize) -> Vec<Latency> {
+    let mut latencies = Vec::new();
+    for (g, exercise) in exercises.iter().enumerate() {
+        let kind: &'static str = exercise.into();
+        for (q, question) in exercise.questions().into_iter().enumerate() {
+            let mut millis: Vec<u64> = question
+                .meta()
+                .history
+                .iter()
+                .filter(|a| a.correct)
+                .map(|a| a.millis)
+                .collect();
+            if millis.is_empty() || millis.len() < min_correct {
+                continue;
+            }
+            millis.sort_unstable();
+
+            latencies.push(Latency {
+                location: Location {
+                    group: g + 1,
+                    question: q + 1,
+                    text: question.text(),
+                },
+                kind,
+                correct: millis.len(),
+                average_millis: millis.iter().sum::<u64>() / millis.len() as u64,
+                median_millis: percentile(&millis, 50),
+                p90_millis: percentile(&millis, 90),
+            });
+        }
+    }
+    latencies.sort_by_key(|l| std::cmp::Reverse(l.median_millis));
+    latencies
+}
+
+/// Quotes a CSV field if it contains a separator, quote or line break.
+fn csv_field(text: &str) -> String {
+    if text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+/// Writes every attempt in the deck's history as CSV, one row per attempt.
+///
+/// Questions are identified by their ID, and by their group and question number, both
+/// counted from 1 like `--list`. Times are seconds since the Unix epoch. The confidence
+/// column is empty for attempts that weren't rated, and the grade column for attempts
+/// that weren't part of a scheduled review.
+pub fn write_csv(exercises: &[Exercise], mut out: impl Write) -> io::Result<()> {
+    writeln!(
+        out,
+        "id,group,question,type,text,time,correct,millis,confidence,grade"
+    )?;
+    for (g, exercise) in exercises.iter().enumerate() {
+        let kind: &'static str = exercise.into();
+        for (q, question) in exercise.questions().into_iter().enumerate() {
+            let text = csv_field(&question.text());
+            for attempt in &question.meta().history {
+                let confidence = attempt
+                    .confidence
+                    .map(|c| c.to_string())
+                    .unwrap_or_default();
+                let grade = attempt
+                    .grade
+                    .map(|g| g.to_string().to_lowercase())
+                    .unwrap_or_default();
+                writeln!(
+                    out,
+                    "{},{},{},{},{},{},{},{},{},{}",
+                    question.meta().id,
+                    g + 1,
+                    q + 1,
+                    kind,
+                    text,
+                    attempt.time,
+                    attempt.correct,
+                    attempt.millis,
+                    confidence,
+                    grade
+                )?;
+            }
+        }
+    }
+    out.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn csv_has_a_row_per_attempt_with_its_confidence_and_grade() {
+        let exercises: Vec<Exercise> = serde_json::from_value(json!([{
+            "type": "YesNo",
+            "data": [{
+                "question": "Is \"egoist\" a noun?",
+                "answer": true,
+                "id": "q1",
+                "history": [
+                    { "time": 10, "correct": false, "millis": 900, "confidence": "guess" },
+                    { "time": 20, "correct": true, "millis": 400, "scheduled": true, "grade": "hard" },
+                ],
+            }],
+        }]))
+        .unwrap();
+        let mut out = Vec::new();
+        write_csv(&exercises, &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "id,group,question,type,text,time,correct,millis,confidence,grade\n\
+             q1,1,1,YesNo,\"Is \"\"egoist\"\" a noun?\",10,false,900,guess,\n\
+             q1,1,1,YesNo,\"Is \"\"egoist\"\" a noun?\",20,true,400,,hard\n"
+        );
+    }
+}
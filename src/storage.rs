@@ -0,0 +1,967 @@
+use crate::backup;
+use crate::crypt;
+use crate::exercise::Exercise;
+use crate::filter::Filter;
+use crate::gzip;
+use crate::migrate::{self, CURRENT_VERSION};
+use crate::msgpack;
+use crate::node::Node;
+use crate::{toml, yaml};
+use serde::de::{self, DeserializeSeed, Deserializer as _, MapAccess, SeqAccess, Visitor};
+use serde::ser::{SerializeSeq, Serializer as _};
+use serde::{Deserialize, Serialize};
+use serde_json::ser::PrettyFormatter;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+/// Where a deck is kept, independent of the file format.
+///
+/// Every command loads and saves its deck through this trait, so another backend only has
+/// to implement these operations to be usable everywhere.
+pub trait Storage {
+    /// Loads the whole deck, or an empty one if nothing is stored yet.
+    fn load(&self) -> Result<Vec<Exercise>, Box<dyn std::error::Error>>;
+
+    /// Replaces the stored deck, e.g. after questions were reviewed or edited.
+    fn save(&self, exercises: &[Exercise]) -> Result<(), Box<dyn std::error::Error>>;
+
+    /// Adds exercises to the end of the stored deck.
+    fn append(&self, new_exercises: Vec<Exercise>) -> Result<(), Box<dyn std::error::Error>>;
+
+    /// Loads only the questions matching `filter`, e.g. by tag or due date.
+    ///
+    /// The default loads the whole deck and filters it; backends that can search without
+    /// loading everything should override it.
+    fn query(&self, filter: &Filter) -> Result<Vec<Exercise>, Box<dyn std::error::Error>> {
+        Ok(filter.apply(self.load()?))
+    }
+}
+
+/// A deck stored in a single file, as pretty-printed JSON or in the YAML, TOML or
+/// MessagePack format its name calls for, see `format_of`.
+///
+/// Before every write the previous file is copied into the backup directory, keeping the
+/// `keep_backups` most recent copies.
+///
+/// With `log` set, new exercises are appended to the log at `log_path` instead, as one
+/// line per batch, and only folded into the file by the next save. A log is read and
+/// folded whenever it exists, whatever `log` says.
+///
+/// With `binary` set, the deck is saved as MessagePack whatever the file is called, see
+/// `saved_format`.
+pub struct FileStorage {
+    path: String,
+    keep_backups: usize,
+    log: bool,
+    binary: bool,
+}
+
+impl Storage for FileStorage {
+    fn load(&self) -> Result<Vec<Exercise>, Box<dyn std::error::Error>> {
+        let mut exercises = load_exercises(&self.path)?;
+        exercises.extend(load_log(&log_path(&self.path))?);
+        Ok(exercises)
+    }
+
+    /// Saves the deck, folding the log into it.
+    ///
+    /// The log is deleted after the new deck is in place. If the program dies in between,
+    /// the logged batches are loaded twice next time; `dedupe` removes the copies.
+    fn save(&self, exercises: &[Exercise]) -> Result<(), Box<dyn std::error::Error>> {
+        let log = log_path(&self.path);
+        backup::create(&self.path, self.keep_backups)?;
+        backup::create(&log, self.keep_backups)?;
+        save_exercises(&self.path, exercises, self.binary)?;
+        match fs::remove_file(&log) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+
+    fn append(&self, new_exercises: Vec<Exercise>) -> Result<(), Box<dyn std::error::Error>> {
+        // A log would keep the new exercises in plain text next to an encrypted deck, so
+        // they are saved into the deck instead, with anything logged before it was encrypted.
+        if crypt::encrypts(&self.path)? {
+            let mut exercises = self.load()?;
+            exercises.extend(new_exercises);
+            return self.save(&exercises);
+        }
+        let log = log_path(&self.path);
+        // Once there is a log, new exercises have to follow the batches already in it.
+        if self.log || Path::new(&log).exists() {
+            return append_log(&log, &new_exercises);
+        }
+        if !new_exercises.is_empty() {
+            backup::create(&self.path, self.keep_backups)?;
+        }
+        append_exercises(&self.path, new_exercises, self.binary)
+    }
+}
+
+/// The storage backends `open` knows by name.
+const BACKENDS: [&str; 3] = ["json", "jsonl", "msgpack"];
+
+/// Opens the storage for `file_path`.
+///
+/// # Arguments
+///
+/// * `file_path` - The file the deck is kept in.
+/// * `backend` - The backend to use by name, or `None` to pick one from the file's
+///   extension: `sqlite` for `.db`, `.sqlite` and `.sqlite3`, and otherwise `json`, which
+///   saves in the format the extension calls for. `jsonl` is the JSON file with new
+///   exercises appended to a log, and `msgpack` the file saved as MessagePack, see
+///   `FileStorage`. `sqlite` is known but unsupported, as this build has no SQLite driver.
+/// * `keep_backups` - How many earlier versions of the file to keep, see `backup::create`.
+///
+/// # Returns
+///
+/// The storage, or an error for an unknown or unsupported backend.
+pub fn open(
+    file_path: &str,
+    backend: Option<&str>,
+    keep_backups: usize,
+) -> Result<Box<dyn Storage>, String> {
+    let backend = match backend {
+        Some(name) => name.to_lowercase(),
+        None => match Path::new(file_path).extension().and_then(|e| e.to_str()) {
+            Some("db" | "sqlite" | "sqlite3") => "sqlite".to_string(),
+            _ => "json".to_string(),
+        },
+    };
+    match backend.as_str() {
+        "json" | "jsonl" | "msgpack" => Ok(Box::new(FileStorage {
+            path: file_path.to_string(),
+            keep_backups,
+            log: backend == "jsonl",
+            binary: backend == "msgpack",
+        })),
+        "sqlite" => Err(format!(
+            "Cannot open {}: unsupported backend: sqlite (this build has no SQLite driver; \
+             keep the deck in a .json, .yaml, .toml or .msgpack file instead)",
+            file_path
+        )),
+        other => Err(format!(
+            "Unknown storage '{}' (valid storages: {})",
+            other,
+            BACKENDS.join(", ")
+        )),
+    }
+}
+
+/// The UTF-8 byte order mark that some editors (e.g. Notepad) put at the start of a file.
+const BOM: &[u8] = b"\xEF\xBB\xBF";
+
+/// The layout of a data file: the deck and the layout version it was written in, see
+/// `migrate`.
+#[derive(Serialize, Deserialize)]
+struct Document<E> {
+    version: u64,
+    exercises: E,
+}
+
+/// Returns how `save_exercises` starts a data file, up to the bracket opening the deck.
+fn header() -> String {
+    format!(
+        "{{\n  \"version\": {},\n  \"exercises\": [",
+        CURRENT_VERSION
+    )
+}
+
+/// Returns how `save_exercises` starts a MessagePack data file, up to the deck.
+fn msgpack_header() -> Vec<u8> {
+    let empty = Node::Object(vec![
+        ("version".to_string(), Node::Number(CURRENT_VERSION.into())),
+        ("exercises".to_string(), Node::Array(Vec::new())),
+    ]);
+    let mut header = msgpack::encode(&empty);
+    // Drop the header of the empty deck, which a deck of questions doesn't share.
+    header.pop();
+    header
+}
+
+/// Checks whether the file at `file_path` starts with `header`, which means it is in the
+/// current layout as written by `save_exercises`.
+fn has_current_header(file_path: &str) -> io::Result<bool> {
+    let header = header();
+    let mut start = Vec::new();
+    File::open(file_path)?
+        .take((BOM.len() + header.len()) as u64)
+        .read_to_end(&mut start)?;
+    Ok(start
+        .strip_prefix(BOM)
+        .unwrap_or(&start)
+        .starts_with(header.as_bytes()))
+}
+
+/// The formats a data file can be written in.
+#[derive(Clone, Copy, PartialEq)]
+enum Format {
+    Json,
+    Yaml,
+    Toml,
+    MessagePack,
+}
+
+/// Returns the format of the data file at `file_path`, from its extension: `.yaml` or
+/// `.yml` for YAML, `.toml` for TOML, `.msgpack` or `.mpk` for MessagePack and JSON for
+/// anything else. A `.gz` extension and the `.tmp` of a file being saved are looked past,
+/// e.g. `data.yaml.gz` is YAML.
+fn format_of(file_path: &str) -> Format {
+    let name = file_path.strip_suffix(".tmp").unwrap_or(file_path);
+    let name = name.strip_suffix(".gz").unwrap_or(name);
+    match Path::new(name).extension().and_then(|e| e.to_str()) {
+        Some("yaml" | "yml") => Format::Yaml,
+        Some("toml") => Format::Toml,
+        Some("msgpack" | "mpk") => Format::MessagePack,
+        _ => Format::Json,
+    }
+}
+
+/// Returns the format a deck is saved to `file_path` in: MessagePack if `binary` is set or
+/// the file already holds MessagePack, so a binary deck stays binary whatever it is called,
+/// and otherwise the format its name calls for.
+fn saved_format(file_path: &str, binary: bool) -> Format {
+    let mut first = [0; 1];
+    let holds_msgpack = if crypt::is_encrypted_file(file_path).unwrap_or(false) {
+        // The deck was unlocked to be loaded, so this doesn't ask for the passphrase again.
+        fs::read(file_path)
+            .ok()
+            .and_then(|bytes| crypt::unseal(file_path, &bytes).ok())
+            .is_some_and(|bytes| msgpack::is_msgpack(&bytes))
+    } else {
+        File::open(file_path)
+            .and_then(|mut file| file.read_exact(&mut first))
+            .is_ok_and(|()| msgpack::is_msgpack(&first))
+    };
+    if binary || holds_msgpack {
+        Format::MessagePack
+    } else {
+        format_of(file_path)
+    }
+}
+
+/// Checks whether decks saved to `file_path` are compressed, which is the case for names
+/// ending in `.gz` such as `data.json.gz`.
+fn is_compressed(file_path: &str) -> bool {
+    file_path.ends_with(".gz")
+}
+
+/// Loads all exercises stored in `file_path`, or an empty list if the file does not exist.
+///
+/// Encrypted files are decrypted first, asking for the passphrase. Gzip compressed and
+/// MessagePack files are recognised by their first bytes, whatever their name. YAML and TOML
+/// files are recognised by their extension, see `format_of`. Files in an older layout are
+/// upgraded by `migrate::upgrade` as they are read; they are saved in the current layout the
+/// next time the deck is written.
+fn load_exercises(file_path: &str) -> Result<Vec<Exercise>, Box<dyn std::error::Error>> {
+    if !Path::new(file_path).exists() {
+        return Ok(Vec::new());
+    }
+    // Read the raw file contents
+    let mut bytes = fs::read(file_path)?;
+    if crypt::is_encrypted(&bytes) {
+        bytes = crypt::unseal(file_path, &bytes)?;
+    }
+    if gzip::is_gzip(&bytes) {
+        bytes =
+            gzip::decompress(&bytes).map_err(|e| format!("Cannot read {}: {}", file_path, e))?;
+    }
+    let parsed = if msgpack::is_msgpack(&bytes) {
+        // Files in the current layout are deserialized directly, skipping the generic upgrade.
+        if bytes.starts_with(&msgpack_header()) {
+            let document: Document<Vec<Exercise>> = msgpack::from_slice(&bytes)
+                .map_err(|e| format!("Cannot read {}: {}", file_path, e))?;
+            return Ok(document.exercises);
+        }
+        msgpack::from_slice(&bytes)
+    } else {
+        let file_content = decode_text(file_path, &bytes)?;
+        match format_of(file_path) {
+            Format::Yaml => yaml::from_str(file_content),
+            Format::Toml => toml::from_str(file_content),
+            Format::Json | Format::MessagePack => {
+                if file_content.starts_with(&header()) {
+                    let document: Document<Vec<Exercise>> = serde_json::from_str(file_content)?;
+                    return Ok(document.exercises);
+                }
+                return parse_document(file_path, file_content);
+            }
+        }
+    };
+    let document = parsed
+        .and_then(migrate::upgrade)
+        .map_err(|e| format!("Cannot read {}: {}", file_path, e))?;
+    let document: Document<Vec<Exercise>> = serde_json::from_value(document)?;
+    Ok(document.exercises)
+}
+
+/// Parses a data file, or a line of a log, of any layout version.
+fn parse_document(
+    file_path: &str,
+    text: &str,
+) -> Result<Vec<Exercise>, Box<dyn std::error::Error>> {
+    let document = migrate::upgrade(serde_json::from_str(text)?)
+        .map_err(|e| format!("Cannot read {}: {}", file_path, e))?;
+    let document: Document<Vec<Exercise>> = serde_json::from_value(document)?;
+    Ok(document.exercises)
+}
+
+/// Returns the log that new exercises are appended to with the `jsonl` backend, e.g.
+/// `data.log.jsonl` for `data.json`.
+pub fn log_path(file_path: &str) -> String {
+    Path::new(file_path)
+        .with_extension("log.jsonl")
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// Returns how many batches the log at `log_path` holds, i.e. how many complete lines.
+pub fn logged_batches(log_path: &str) -> io::Result<usize> {
+    match fs::read(log_path) {
+        Ok(bytes) => Ok(bytes.iter().filter(|&&b| b == b'\n').count()),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(0),
+        Err(e) => Err(e),
+    }
+}
+
+/// Loads the batches of exercises in a log, in the order they were appended.
+///
+/// Each line is a document like a data file's, holding one batch. A last line without a
+/// newline was cut off while being written, since `append_log` ends every line it writes
+/// with one, and is ignored.
+fn load_log(log_path: &str) -> Result<Vec<Exercise>, Box<dyn std::error::Error>> {
+    let content = match fs::read(log_path) {
+        Ok(bytes) => bytes,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+    let content = decode_text(log_path, &content)?;
+    let mut exercises = Vec::new();
+    for (i, line) in content.split_inclusive('\n').enumerate() {
+        let Some(line) = line.strip_suffix('\n') else {
+            break;
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+        let batch = parse_document(log_path, line)
+            .map_err(|e| format!("{}, line {}: {}", log_path, i + 1, e))?;
+        exercises.extend(batch);
+    }
+    Ok(exercises)
+}
+
+/// Appends a batch of exercises to the log as one line, without reading what is already
+/// logged.
+///
+/// A line left unfinished by an earlier interrupted append is cut off first, so the new
+/// line starts on a line of its own.
+fn append_log(
+    log_path: &str,
+    new_exercises: &[Exercise],
+) -> Result<(), Box<dyn std::error::Error>> {
+    if new_exercises.is_empty() {
+        return Ok(());
+    }
+    let mut line = serde_json::to_string(&Document {
+        version: CURRENT_VERSION,
+        exercises: new_exercises,
+    })?;
+    line.push('\n');
+
+    let mut file = fs::OpenOptions::new()
+        .read(true)
+        .append(true)
+        .create(true)
+        .open(log_path)?;
+    truncate_unfinished_line(&mut file)?;
+    file.write_all(line.as_bytes())?;
+    file.sync_all()?;
+    Ok(())
+}
+
+/// Cuts the file back to just after its last newline, if it doesn't end in one.
+fn truncate_unfinished_line(file: &mut File) -> io::Result<()> {
+    let len = file.seek(SeekFrom::End(0))?;
+    let mut end = len;
+    let mut chunk = vec![0; TAIL_LEN as usize];
+    while end > 0 {
+        let start = end.saturating_sub(TAIL_LEN);
+        let chunk = &mut chunk[..(end - start) as usize];
+        file.seek(SeekFrom::Start(start))?;
+        file.read_exact(chunk)?;
+        if let Some(i) = chunk.iter().rposition(|&b| b == b'\n') {
+            let complete = start + i as u64 + 1;
+            if complete < len {
+                file.set_len(complete)?;
+            }
+            return Ok(());
+        }
+        end = start;
+    }
+    file.set_len(0)
+}
+
+/// Decodes a data file as UTF-8, dropping a leading byte order mark.
+///
+/// # Returns
+///
+/// The decoded text, or an error naming the byte offset of the first invalid UTF-8 sequence.
+fn decode_text<'a>(file_path: &str, bytes: &'a [u8]) -> Result<&'a str, String> {
+    let bytes = bytes.strip_prefix(BOM).unwrap_or(bytes);
+
+    std::str::from_utf8(bytes).map_err(|e| {
+        format!(
+            "{} is not valid UTF-8: invalid byte at offset {}. Re-save the file with UTF-8 encoding.",
+            file_path,
+            e.valid_up_to()
+        )
+    })
+}
+
+/// Returns the temporary file a save of `file_path` is written to before replacing it.
+fn tmp_path(file_path: &str) -> String {
+    format!("{}.tmp", file_path)
+}
+
+/// Moves a fully written and synced temporary file over `file_path`.
+///
+/// The rename is atomic, so `file_path` holds either the old deck or the new one even if
+/// the program is killed halfway. The directory is synced too, where the platform allows
+/// it, so the rename itself survives a power loss.
+fn replace_with(tmp_path: &str, file_path: &str) -> Result<(), Box<dyn std::error::Error>> {
+    fs::rename(tmp_path, file_path)?;
+    let dir = Path::new(file_path).parent().unwrap_or(Path::new(""));
+    let dir = if dir.as_os_str().is_empty() {
+        Path::new(".")
+    } else {
+        dir
+    };
+    if let Ok(dir) = File::open(dir) {
+        let _ = dir.sync_all();
+    }
+    Ok(())
+}
+
+/// Writes exercises to `file_path` as pretty-printed JSON, or in the format
+/// `saved_format` picks, replacing its contents.
+///
+/// The deck is written to a temporary file and synced to disk first, then moved over
+/// `file_path` by `replace_with`, so a crash never leaves a half-written deck behind. It is
+/// gzip compressed if `is_compressed` says so, and then encrypted if `crypt::encrypts` does.
+fn save_exercises(
+    file_path: &str,
+    exercises: &[Exercise],
+    binary: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let document = Document {
+        version: CURRENT_VERSION,
+        exercises,
+    };
+    let bytes = match saved_format(file_path, binary) {
+        Format::Json => serde_json::to_vec_pretty(&document)?,
+        Format::Yaml => yaml::to_string(&Node::from_serialize(&document)?).into_bytes(),
+        Format::Toml => toml::to_string(&Node::from_serialize(&document)?)
+            .map_err(|e| format!("Could not save {}: {}", file_path, e))?
+            .into_bytes(),
+        Format::MessagePack => msgpack::encode(&Node::from_serialize(&document)?),
+    };
+
+    let mut bytes = if is_compressed(file_path) {
+        gzip::compress(&bytes)
+    } else {
+        bytes
+    };
+    if crypt::encrypts(file_path)? {
+        bytes = crypt::seal(file_path, &bytes)?;
+    }
+    write_file(file_path, &bytes)
+}
+
+/// Replaces the contents of `file_path` with `bytes` through a synced temporary file.
+fn write_file(file_path: &str, bytes: &[u8]) -> Result<(), Box<dyn std::error::Error>> {
+    let tmp_path = tmp_path(file_path);
+    let result = (|| -> Result<(), Box<dyn std::error::Error>> {
+        let mut file = File::create(&tmp_path)?;
+        file.write_all(bytes)?;
+        file.sync_all()?;
+        Ok(())
+    })();
+    if let Err(e) = result {
+        let _ = fs::remove_file(&tmp_path);
+        return Err(format!("Could not save {}: {}", file_path, e).into());
+    }
+    replace_with(&tmp_path, file_path)
+}
+
+/// Finishes or cleans up a save of `file_path` that was interrupted, e.g. by a crash.
+///
+/// A leftover temporary file next to an existing deck is an unfinished save and is
+/// deleted, keeping the last complete deck. Without a deck, the temporary file replaces it
+/// if it holds a complete deck, which happens when the first save was interrupted just
+/// before the rename, and is deleted otherwise.
+///
+/// # Returns
+///
+/// A description of what was done, or `None` if there was nothing to recover.
+pub fn recover(file_path: &str) -> Result<Option<String>, Box<dyn std::error::Error>> {
+    let tmp_path = tmp_path(file_path);
+    if !Path::new(&tmp_path).exists() {
+        return Ok(None);
+    }
+    if Path::new(file_path).exists() {
+        fs::remove_file(&tmp_path)?;
+        return Ok(Some(format!(
+            "Discarded an unfinished save of {}; the last complete save is kept",
+            file_path
+        )));
+    }
+    if load_exercises(&tmp_path).is_ok() {
+        replace_with(&tmp_path, file_path)?;
+        return Ok(Some(format!(
+            "Restored {} from a save that was interrupted",
+            file_path
+        )));
+    }
+    fs::remove_file(&tmp_path)?;
+    Ok(Some(format!(
+        "Discarded an unfinished save of {}; there was no complete save to keep",
+        file_path
+    )))
+}
+
+/// Encrypts `file_path` and its backups, including those of its log, with a new passphrase,
+/// which is asked for whenever the deck is loaded from then on. The file's contents are
+/// sealed as they are, compressed or not. A file with a log has to be compacted first.
+pub fn encrypt(file_path: &str) -> Result<(), Box<dyn std::error::Error>> {
+    if !Path::new(file_path).exists() {
+        return Err(format!("{} doesn't exist yet; add some exercises first", file_path).into());
+    }
+    let bytes = fs::read(file_path)?;
+    if crypt::is_encrypted(&bytes) {
+        return Err(format!("{} is encrypted already", file_path).into());
+    }
+    if Path::new(&log_path(file_path)).exists() {
+        return Err(format!(
+            "{} has exercises logged in {}; run compact first to fold them in",
+            file_path,
+            log_path(file_path)
+        )
+        .into());
+    }
+    crypt::protect(file_path)?;
+    // Backups are sealed first, so no plain copy of the deck is left once the file is.
+    let log_backups = backup::list(&log_path(file_path))?;
+    for path in backup::list(file_path)?.into_iter().chain(log_backups) {
+        let backup = fs::read(&path)?;
+        if !crypt::is_encrypted(&backup) {
+            write_file(&path.to_string_lossy(), &crypt::seal(file_path, &backup)?)?;
+        }
+    }
+    write_file(file_path, &crypt::seal(file_path, &bytes)?)
+}
+
+/// Decrypts `file_path` and its backups, including those of its log, so that the deck is stored as plain text again.
+pub fn decrypt(file_path: &str) -> Result<(), Box<dyn std::error::Error>> {
+    if !crypt::is_encrypted_file(file_path)? {
+        return Err(format!("{} is not encrypted", file_path).into());
+    }
+    let bytes = crypt::unseal(file_path, &fs::read(file_path)?)?;
+    let log_backups = backup::list(&log_path(file_path))?;
+    for path in backup::list(file_path)?.into_iter().chain(log_backups) {
+        let backup = fs::read(&path)?;
+        if crypt::is_encrypted(&backup) {
+            write_file(&path.to_string_lossy(), &crypt::unseal(file_path, &backup)?)?;
+        }
+    }
+    crypt::unprotect(file_path);
+    write_file(file_path, &bytes)
+}
+
+/// How many bytes at the end of a data file are searched for the closing bracket.
+const TAIL_LEN: u64 = 4096;
+
+/// Appends exercises to those already stored in `file_path`.
+///
+/// The new exercises are normally written over the closing `]` of the stored array, so
+/// the existing entries are neither parsed nor re-serialized: the file is copied to a
+/// temporary file, which is appended to and then moved over the original. If the end of
+/// the file doesn't look like a pretty-printed array of exercises, the whole deck is
+/// rewritten instead through `rewrite_with`.
+fn append_exercises(
+    file_path: &str,
+    new_exercises: Vec<Exercise>,
+    binary: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if !Path::new(file_path).exists() {
+        return save_exercises(file_path, &new_exercises, binary);
+    }
+    if new_exercises.is_empty() {
+        return Ok(());
+    }
+    // Only uncompressed, unencrypted JSON can be added to in place; other decks are
+    // rewritten whole.
+    if is_compressed(file_path)
+        || saved_format(file_path, binary) != Format::Json
+        || crypt::encrypts(file_path)?
+    {
+        let mut exercises = load_exercises(file_path)?;
+        exercises.extend(new_exercises);
+        return save_exercises(file_path, &exercises, binary);
+    }
+
+    let tmp_path = tmp_path(file_path);
+    fs::copy(file_path, &tmp_path)?;
+    match append_in_place(&tmp_path, &new_exercises) {
+        Ok(true) => return replace_with(&tmp_path, file_path),
+        Ok(false) => fs::remove_file(&tmp_path)?,
+        Err(e) => {
+            let _ = fs::remove_file(&tmp_path);
+            return Err(e);
+        }
+    }
+    rewrite_with(file_path, new_exercises)
+}
+
+/// Writes exercises over the closing bracket of the deck stored in `file_path`.
+///
+/// # Returns
+///
+/// `false`, leaving the file untouched, if the file isn't in the current layout or doesn't
+/// end in the array of exercises.
+fn append_in_place(
+    file_path: &str,
+    new_exercises: &[Exercise],
+) -> Result<bool, Box<dyn std::error::Error>> {
+    if !has_current_header(file_path)? {
+        return Ok(false);
+    }
+    let mut file = fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(file_path)?;
+
+    let len = file.metadata()?.len();
+    let start = len.saturating_sub(TAIL_LEN);
+    let mut tail = Vec::new();
+    file.seek(SeekFrom::Start(start))?;
+    file.read_to_end(&mut tail)?;
+
+    // The document ends with the deck, whose last element is always an object, so the file
+    // ends in `}`, `]` and `}`, or in `[`, `]` and `}` for an empty deck.
+    let mut significant = tail
+        .iter()
+        .enumerate()
+        .rev()
+        .filter(|(_, b)| !b.is_ascii_whitespace());
+    let (has_elements, last) = match (significant.next(), significant.next(), significant.next()) {
+        (Some((_, b'}')), Some((_, b']')), Some((i, b'}'))) => (true, i),
+        (Some((_, b'}')), Some((_, b']')), Some((i, b'['))) => (false, i),
+        _ => return Ok(false),
+    };
+    // A `[` other than the one ending the header could belong to a nested empty array.
+    if !has_elements {
+        let bom = if tail.starts_with(BOM) { BOM.len() } else { 0 };
+        if start > 0 || last + 1 != bom + header().len() {
+            return Ok(false);
+        }
+    }
+
+    let mut json = String::new();
+    for (i, exercise) in new_exercises.iter().enumerate() {
+        let separator = if i == 0 && !has_elements { "\n" } else { ",\n" };
+        json.push_str(separator);
+        let element = serde_json::to_string_pretty(exercise)?;
+        let indented: Vec<String> = element.lines().map(|l| format!("    {}", l)).collect();
+        json.push_str(&indented.join("\n"));
+    }
+    json.push_str("\n  ]\n}");
+
+    let offset = start + last as u64 + 1;
+    file.seek(SeekFrom::Start(offset))?;
+    file.write_all(json.as_bytes())?;
+    file.set_len(offset + json.len() as u64)?;
+    file.sync_all()?;
+    Ok(true)
+}
+
+/// Rewrites the deck stored in `file_path` with `new_exercises` added to its end.
+///
+/// The existing deck is never held in memory as a whole: its array elements are parsed
+/// one at a time and immediately re-serialized into a temporary file, followed by the new
+/// exercises. The temporary file then replaces the original, so peak memory stays bounded
+/// by the largest single exercise group rather than the size of the deck.
+///
+/// A file in an older layout is instead loaded, upgraded and saved as a whole, which only
+/// happens once per file.
+fn rewrite_with(
+    file_path: &str,
+    new_exercises: Vec<Exercise>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if !has_current_header(file_path)? {
+        let mut exercises = load_exercises(file_path)?;
+        exercises.extend(new_exercises);
+        return save_exercises(file_path, &exercises, false);
+    }
+
+    let mut reader = BufReader::new(File::open(file_path)?);
+    if reader.fill_buf()?.starts_with(BOM) {
+        reader.consume(BOM.len());
+    }
+
+    let tmp_path = tmp_path(file_path);
+    let mut writer = BufWriter::new(File::create(&tmp_path)?);
+
+    let result = (|| -> Result<(), Box<dyn std::error::Error>> {
+        // The header is written as it is; the deck is nested one level deep in it.
+        writer.write_all(header().trim_end_matches('[').as_bytes())?;
+        let mut serializer =
+            serde_json::Serializer::with_formatter(Indented(&mut writer), PrettyFormatter::new());
+        let mut deserializer = serde_json::Deserializer::from_reader(reader);
+
+        deserializer.deserialize_map(DocumentVisitor(AppendVisitor {
+            serializer: &mut serializer,
+            new_exercises,
+        }))?;
+        deserializer.end()?;
+        writer.write_all(b"\n}")?;
+        writer.flush()?;
+        writer.get_ref().sync_all()?;
+        Ok(())
+    })();
+
+    match result {
+        Ok(()) => {
+            drop(writer);
+            replace_with(&tmp_path, file_path)
+        }
+        Err(e) => {
+            drop(writer);
+            let _ = fs::remove_file(&tmp_path);
+            Err(format!("Could not append to {}: {}", file_path, e).into())
+        }
+    }
+}
+
+/// Passes everything written on to the inner writer, indenting every line after the first
+/// by two spaces. JSON strings never contain raw line breaks, so this nests pretty-printed
+/// JSON one level deeper.
+struct Indented<W: Write>(W);
+
+impl<W: Write> Write for Indented<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        for (i, line) in buf.split(|&b| b == b'\n').enumerate() {
+            if i > 0 {
+                self.0.write_all(b"\n  ")?;
+            }
+            self.0.write_all(line)?;
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.flush()
+    }
+}
+
+/// Reads the fields of a document in the current layout, streaming its deck through an
+/// `AppendVisitor`. The header has already been checked, so the version is only skipped.
+struct DocumentVisitor<'a, W: Write>(AppendVisitor<'a, W>);
+
+impl<'de, W: Write> Visitor<'de> for DocumentVisitor<'_, W> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a versioned document of exercises")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<(), A::Error> {
+        if map.next_key::<String>()?.as_deref() != Some("version") {
+            return Err(de::Error::missing_field("version"));
+        }
+        map.next_value::<u64>()?;
+        if map.next_key::<String>()?.as_deref() != Some("exercises") {
+            return Err(de::Error::missing_field("exercises"));
+        }
+        map.next_value_seed(self.0)?;
+        match map.next_key::<String>()? {
+            Some(key) => Err(de::Error::unknown_field(&key, &["version", "exercises"])),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Streams the elements of the stored array into a serializer, then appends new exercises.
+struct AppendVisitor<'a, W: Write> {
+    serializer: &'a mut serde_json::Serializer<W, PrettyFormatter<'static>>,
+    new_exercises: Vec<Exercise>,
+}
+
+impl<'de, W: Write> DeserializeSeed<'de> for AppendVisitor<'_, W> {
+    type Value = ();
+
+    fn deserialize<D: de::Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de, W: Write> Visitor<'de> for AppendVisitor<'_, W> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "an array of exercises")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<(), A::Error> {
+        let mut out = self
+            .serializer
+            .serialize_seq(None)
+            .map_err(de::Error::custom)?;
+
+        while let Some(exercise) = seq.next_element::<Exercise>()? {
+            out.serialize_element(&exercise)
+                .map_err(de::Error::custom)?;
+        }
+        for exercise in &self.new_exercises {
+            out.serialize_element(exercise).map_err(de::Error::custom)?;
+        }
+        out.end().map_err(de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns a path in a scratch directory for this test run, with nothing there yet.
+    fn scratch_file(name: &str) -> String {
+        let dir = std::env::temp_dir().join(format!("word_power-storage-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(name);
+        let _ = fs::remove_file(&path);
+        path.to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn saved_format_follows_the_name_of_a_new_file() {
+        let cases = [
+            ("deck.json", Format::Json),
+            ("deck", Format::Json),
+            ("deck.yaml", Format::Yaml),
+            ("deck.yml.gz", Format::Yaml),
+            ("deck.toml", Format::Toml),
+            ("deck.msgpack", Format::MessagePack),
+            ("deck.mpk", Format::MessagePack),
+        ];
+        for (name, format) in cases {
+            assert!(
+                saved_format(&scratch_file(name), false) == format,
+                "{}",
+                name
+            );
+            assert!(saved_format(&scratch_file(name), true) == Format::MessagePack);
+        }
+    }
+
+    #[test]
+    fn open_rejects_sqlite_by_name_or_extension() {
+        for (name, backend) in [
+            ("deck.json", Some("SQLite")),
+            ("deck.db", None),
+            ("deck.sqlite", None),
+            ("deck.sqlite3", None),
+        ] {
+            let error = open(name, backend, 0).err().unwrap();
+            assert!(error.contains("unsupported backend: sqlite"), "{}", error);
+        }
+        assert!(open("deck.db", Some("json"), 0).is_ok());
+        assert!(open("deck.json", Some("csv"), 0)
+            .err()
+            .unwrap()
+            .starts_with("Unknown storage"));
+    }
+
+    #[test]
+    fn saved_format_keeps_a_messagepack_file_binary() {
+        let file = scratch_file("binary.json");
+        fs::write(&file, "{}").unwrap();
+        assert!(saved_format(&file, false) == Format::Json);
+        fs::write(&file, msgpack::encode(&Node::Object(Vec::new()))).unwrap();
+        assert!(saved_format(&file, false) == Format::MessagePack);
+        fs::write(&file, msgpack::encode(&Node::Array(Vec::new()))).unwrap();
+        assert!(saved_format(&file, false) == Format::MessagePack);
+    }
+
+    #[test]
+    fn every_format_round_trips_every_exercise_type_with_its_metadata() {
+        let exercises = Exercise::examples_with_meta();
+        let expected = serde_json::to_value(&exercises).unwrap();
+        for name in [
+            "round-trip.json",
+            "round-trip.yaml",
+            "round-trip.toml",
+            "round-trip.msgpack",
+            "round-trip.toml.gz",
+        ] {
+            let file = scratch_file(name);
+            save_exercises(&file, &exercises, false).unwrap();
+            let read = load_exercises(&file).unwrap_or_else(|e| {
+                let saved = fs::read(&file).unwrap();
+                panic!("{}: {}\n{}", name, e, String::from_utf8_lossy(&saved))
+            });
+            assert_eq!(serde_json::to_value(&read).unwrap(), expected, "{}", name);
+        }
+    }
+
+    #[test]
+    fn messagepack_decks_load_as_they_were_saved() {
+        let exercises = Exercise::examples_with_meta();
+        let expected = serde_json::to_value(&exercises).unwrap();
+        for (name, binary) in [
+            ("deck.msgpack", false),
+            ("binary.yaml", true),
+            ("deck.mpk.gz", false),
+        ] {
+            let file = scratch_file(name);
+            save_exercises(&file, &exercises, binary).unwrap();
+            let bytes = fs::read(&file).unwrap();
+            assert!(
+                msgpack::is_msgpack(&bytes) != is_compressed(&file),
+                "{}",
+                name
+            );
+            let read = load_exercises(&file).unwrap();
+            assert_eq!(serde_json::to_value(&read).unwrap(), expected, "{}", name);
+        }
+    }
+
+    #[test]
+    fn recover_only_claims_to_keep_a_save_that_exists() {
+        let file = scratch_file("recover.json");
+        let tmp = tmp_path(&file);
+
+        fs::write(&tmp, "[{").unwrap();
+        let message = recover(&file).unwrap().unwrap();
+        assert!(message.contains("no complete save"), "{}", message);
+        assert!(!Path::new(&tmp).exists() && !Path::new(&file).exists());
+
+        save_exercises(&file, &Exercise::examples(), false).unwrap();
+        fs::write(&tmp, "[{").unwrap();
+        let message = recover(&file).unwrap().unwrap();
+        assert!(
+            message.contains("last complete save is kept"),
+            "{}",
+            message
+        );
+        assert!(!Path::new(&tmp).exists());
+        assert!(load_exercises(&file).is_ok());
+
+        fs::rename(&file, &tmp).unwrap();
+        let message = recover(&file).unwrap().unwrap();
+        assert!(message.starts_with("Restored"), "{}", message);
+        assert!(load_exercises(&file).is_ok());
+        assert_eq!(recover(&file).unwrap(), None);
+    }
+}
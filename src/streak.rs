@@ -0,0 +1,79 @@
+use crate::date::Date;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The file the practice days are kept in, next to the data file.
+pub const STREAK_FILE: &str = "streak.json";
+
+/// Returns the streak file belonging to `data_file`.
+pub fn streak_file(data_file: &str) -> PathBuf {
+    Path::new(data_file)
+        .parent()
+        .unwrap_or(Path::new(""))
+        .join(STREAK_FILE)
+}
+
+/// The days on which at least one review session was completed.
+///
+/// Days are UTC dates, like the review schedule, so a streak doesn't break or double up
+/// when the local timezone changes.
+#[derive(Serialize, Deserialize, Default)]
+pub struct Streak {
+    days: BTreeSet<Date>,
+}
+
+impl Streak {
+    /// Loads the practice days from `file_path`, or an empty record if it doesn't exist.
+    pub fn load(file_path: &Path) -> Result<Streak, Box<dyn std::error::Error>> {
+        if !file_path.exists() {
+            return Ok(Streak::default());
+        }
+        Ok(serde_json::from_str(&fs::read_to_string(file_path)?)?)
+    }
+
+    /// Writes the practice days to `file_path`.
+    pub fn save(&self, file_path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        fs::write(file_path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Marks `day` as practiced.
+    pub fn record(&mut self, day: Date) {
+        self.days.insert(day);
+    }
+
+    /// Returns the number of days in a row practiced up to `today`.
+    ///
+    /// A streak that reaches yesterday still counts, since today's session may come later.
+    pub fn current(&self, today: Date) -> usize {
+        let mut day = if self.days.contains(&today) {
+            today
+        } else {
+            today.add_days(-1)
+        };
+        let mut length = 0;
+        while self.days.contains(&day) {
+            length += 1;
+            day = day.add_days(-1);
+        }
+        length
+    }
+
+    /// Returns the longest run of days in a row ever practiced.
+    pub fn longest(&self) -> usize {
+        let mut longest = 0;
+        let mut length = 0;
+        let mut previous: Option<Date> = None;
+        for &day in &self.days {
+            length = match previous {
+                Some(p) if p.add_days(1) == day => length + 1,
+                _ => 1,
+            };
+            longest = longest.max(length);
+            previous = Some(day);
+        }
+        longest
+    }
+}
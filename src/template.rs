@@ -0,0 +1,96 @@
+use crate::datadir;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The directory, inside the data directory, that custom exercise templates are loaded
+/// from.
+pub const TEMPLATE_DIR: &str = "templates";
+
+/// Returns the template directory, or `TEMPLATE_DIR` in the working directory if there is
+/// no data directory.
+pub fn template_dir() -> PathBuf {
+    datadir::data_dir().map_or(PathBuf::from(TEMPLATE_DIR), |d| d.join(TEMPLATE_DIR))
+}
+
+/// A user-defined question format for `Custom` exercises.
+///
+/// Templates are JSON files in the `templates/` directory of the data directory, named
+/// after the template:
+///
+/// ```json
+/// {
+///   "fields": ["word", "synonym"],
+///   "prompt": "Give a synonym for {word}",
+///   "answer": "{synonym}"
+/// }
+/// ```
+///
+/// `{field}` placeholders in the prompt and answer are replaced by the values entered for
+/// each question.
+#[derive(Deserialize)]
+pub struct Template {
+    #[serde(skip)]
+    pub name: String,
+    pub fields: Vec<String>,
+    pub prompt: String,
+    pub answer: String,
+}
+
+impl Template {
+    /// Replaces every `{field}` placeholder in `text` with the field's value.
+    pub fn fill(text: &str, fields: &BTreeMap<String, String>) -> String {
+        fields.iter().fold(text.to_string(), |text, (name, value)| {
+            text.replace(&format!("{{{}}}", name), value)
+        })
+    }
+
+    /// Loads every template in `dir`, sorted by name.
+    ///
+    /// # Returns
+    ///
+    /// The templates, an empty list if the directory does not exist, or an error naming the
+    /// template file that could not be read.
+    pub fn load_all(dir: &Path) -> Result<Vec<Template>, String> {
+        if !dir.is_dir() {
+            return Ok(Vec::new());
+        }
+
+        let entries = fs::read_dir(dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+        let mut templates = entries
+            .filter_map(Result::ok)
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+            .map(|path| {
+                let content =
+                    fs::read_to_string(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+                let mut template: Template = serde_json::from_str(&content)
+                    .map_err(|e| format!("{}: {}", path.display(), e))?;
+                template.name = path
+                    .file_stem()
+                    .map(|stem| stem.to_string_lossy().into_owned())
+                    .unwrap_or_default();
+                Ok(template)
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+
+        templates.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(templates)
+    }
+
+    /// Loads the template called `name` from `dir`.
+    pub fn find(dir: &Path, name: &str) -> Result<Template, String> {
+        Template::load_all(dir)?
+            .into_iter()
+            .find(|t| t.name == name)
+            .ok_or_else(|| format!("No template named '{}' in {}", name, dir.display()))
+    }
+}
+
+impl fmt::Display for Template {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} ({})", self.name, self.prompt)
+    }
+}
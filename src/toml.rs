@@ -0,0 +1,681 @@
+use crate::node::Node;
+use serde_json::{Map, Number, Value};
+
+/// Writes a value as a TOML document.
+///
+/// Arrays of objects, such as the deck's exercise groups and their questions, become
+/// arrays of tables (`[[exercises]]`, `[[exercises.data]]`), and strings spanning several
+/// lines become multi-line strings. TOML has no null: null fields are left out, which
+/// reads back the same, but a null inside an array can't be written.
+///
+/// # Returns
+///
+/// The document, or an error naming where a value TOML can't hold was found.
+pub fn to_string(value: &Node) -> Result<String, String> {
+    let Node::Object(fields) = value else {
+        return Err("a TOML document must be a table".to_string());
+    };
+    let mut out = String::new();
+    write_table(&mut out, "", fields)?;
+    Ok(out)
+}
+
+/// Checks whether a value is written as an array of tables rather than inline.
+fn is_table_array(value: &Node) -> bool {
+    matches!(value, Node::Array(items)
+        if !items.is_empty() && items.iter().all(|i| matches!(i, Node::Object(_))))
+}
+
+/// Writes the fields of the table at `path`, after its header.
+///
+/// TOML assigns every `key = value` line to the table of the header above it, so the
+/// fields that are written inline come first, followed by the nested tables.
+fn write_table(out: &mut String, path: &str, fields: &[(String, Node)]) -> Result<(), String> {
+    for (key, value) in fields {
+        if matches!(value, Node::Null | Node::Object(_)) || is_table_array(value) {
+            continue;
+        }
+        out.push_str(&format!(
+            "{} = {}\n",
+            write_key(key),
+            inline(value, path, key)?
+        ));
+    }
+    for (key, value) in fields {
+        let nested = if path.is_empty() {
+            write_key(key)
+        } else {
+            format!("{}.{}", path, write_key(key))
+        };
+        match value {
+            Node::Object(fields) => {
+                out.push_str(&format!("\n[{}]\n", nested));
+                write_table(out, &nested, fields)?;
+            }
+            Node::Array(items) if is_table_array(value) => {
+                for item in items {
+                    if let Node::Object(fields) = item {
+                        out.push_str(&format!("\n[[{}]]\n", nested));
+                        write_table(out, &nested, fields)?;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Writes a key, bare if TOML allows it, otherwise quoted.
+fn write_key(key: &str) -> String {
+    if !key.is_empty()
+        && key
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b == b'_' || b == b'-')
+    {
+        key.to_string()
+    } else {
+        write_string(key)
+    }
+}
+
+/// Writes a value on the line of its key; `path` and `key` name it in errors.
+fn inline(value: &Node, path: &str, key: &str) -> Result<String, String> {
+    Ok(match value {
+        Node::Null => {
+            let name = if path.is_empty() {
+                key.to_string()
+            } else {
+                format!("{}.{}", path, key)
+            };
+            return Err(format!(
+                "{} holds a null, which TOML can't store; use JSON or YAML",
+                name
+            ));
+        }
+        Node::Bool(b) => b.to_string(),
+        Node::Number(n) => {
+            if n.is_u64() && n.as_i64().is_none() {
+                return Err(format!("{} is too large for a TOML integer", n));
+            }
+            n.to_string()
+        }
+        Node::String(s) if s.contains('\n') => write_multiline_string(s),
+        Node::String(s) => write_string(s),
+        Node::Array(items) => {
+            let items = items
+                .iter()
+                .map(|item| inline(item, path, key))
+                .collect::<Result<Vec<_>, _>>()?;
+            format!("[{}]", items.join(", "))
+        }
+        Node::Object(fields) => {
+            let fields = fields
+                .iter()
+                .filter(|(_, value)| !matches!(value, Node::Null))
+                .map(|(k, value)| Ok(format!("{} = {}", write_key(k), inline(value, path, key)?)))
+                .collect::<Result<Vec<_>, String>>()?;
+            if fields.is_empty() {
+                "{}".to_string()
+            } else {
+                format!("{{ {} }}", fields.join(", "))
+            }
+        }
+    })
+}
+
+/// Writes a string as a basic string, escaping what TOML requires.
+fn write_string(s: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in s.chars() {
+        push_escaped(&mut quoted, c);
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Writes a string with line breaks as a multi-line basic string, keeping them as they
+/// are.
+fn write_multiline_string(s: &str) -> String {
+    // A line break right after the opening quotes is not part of the string.
+    let mut quoted = String::from("\"\"\"\n");
+    for c in s.chars() {
+        if c == '\n' {
+            quoted.push('\n');
+        } else {
+            push_escaped(&mut quoted, c);
+        }
+    }
+    quoted.push_str("\"\"\"");
+    quoted
+}
+
+fn push_escaped(out: &mut String, c: char) {
+    match c {
+        '"' => out.push_str("\\\""),
+        '\\' => out.push_str("\\\\"),
+        '\n' => out.push_str("\\n"),
+        '\t' => out.push_str("\\t"),
+        '\r' => out.push_str("\\r"),
+        c if c.is_control() => out.push_str(&format!("\\u{:04X}", c as u32)),
+        c => out.push(c),
+    }
+}
+
+/// Parses a TOML document into a value.
+///
+/// Everything in TOML 1.0 except dates and times is understood; those are reported as
+/// errors, since no field of a deck holds one.
+///
+/// # Returns
+///
+/// The value, or an error naming the line where parsing failed.
+pub fn from_str(text: &str) -> Result<Value, String> {
+    let mut parser = Parser {
+        chars: text.chars().collect(),
+        pos: 0,
+    };
+    let mut root = Map::new();
+    // The keys leading from the root to the table of the last header.
+    let mut current: Vec<String> = Vec::new();
+    // Tables defined by a header or a dotted key, which may not be defined again.
+    let mut defined: Vec<Vec<String>> = Vec::new();
+
+    loop {
+        parser.skip_whitespace_and_comments();
+        let Some(c) = parser.peek() else {
+            break;
+        };
+        if c == '[' {
+            let array = parser.peek_at(1) == Some('[');
+            parser.pos += if array { 2 } else { 1 };
+            let keys = parser.parse_key()?;
+            parser.expect(']')?;
+            if array {
+                parser.expect(']')?;
+            }
+            parser.end_of_line()?;
+
+            let (last, parents) = keys.split_last().expect("a key has at least one part");
+            let parent = table_at(&mut root, parents).map_err(|e| parser.error(&e))?;
+            if array {
+                match parent
+                    .entry(last.clone())
+                    .or_insert_with(|| Value::Array(Vec::new()))
+                {
+                    Value::Array(tables) if !defined.contains(&keys) => {
+                        tables.push(Value::Object(Map::new()));
+                        // The tables of earlier elements may be defined again in this one.
+                        defined.retain(|d| !d.starts_with(&keys));
+                    }
+                    _ => {
+                        return Err(parser
+                            .error(&format!("'{}' is not an array of tables", keys.join("."))))
+                    }
+                }
+            } else {
+                if defined.contains(&keys) {
+                    return Err(parser.error(&format!("table '{}' defined twice", keys.join("."))));
+                }
+                match parent
+                    .entry(last.clone())
+                    .or_insert_with(|| Value::Object(Map::new()))
+                {
+                    Value::Object(_) => defined.push(keys.clone()),
+                    _ => return Err(parser.error(&format!("'{}' is not a table", keys.join(".")))),
+                }
+            }
+            current = keys;
+        } else {
+            let keys = parser.parse_key()?;
+            parser.skip_spaces();
+            parser.expect('=')?;
+            parser.skip_spaces();
+            let value = parser.parse_value()?;
+            parser.end_of_line()?;
+
+            let (last, parents) = keys.split_last().expect("a key has at least one part");
+            let mut path = current.clone();
+            for key in parents {
+                path.push(key.clone());
+                defined.push(path.clone());
+            }
+            let table = table_at(&mut root, &path).map_err(|e| parser.error(&e))?;
+            if table.insert(last.clone(), value).is_some() {
+                return Err(parser.error(&format!("duplicate key '{}'", keys.join("."))));
+            }
+        }
+    }
+    Ok(Value::Object(root))
+}
+
+/// Returns the table that `keys` lead to from `root`, creating missing tables. A key
+/// naming an array of tables leads to its last table.
+fn table_at<'a>(
+    root: &'a mut Map<String, Value>,
+    keys: &[String],
+) -> Result<&'a mut Map<String, Value>, String> {
+    let mut table = root;
+    for key in keys {
+        let value = table
+            .entry(key.clone())
+            .or_insert_with(|| Value::Object(Map::new()));
+        let value = match value {
+            Value::Array(items) => match items.last_mut() {
+                Some(last) => last,
+                None => return Err(format!("'{}' is not a table", key)),
+            },
+            value => value,
+        };
+        table = match value {
+            Value::Object(map) => map,
+            _ => return Err(format!("'{}' is not a table", key)),
+        };
+    }
+    Ok(table)
+}
+
+/// Reads the keys and values of a TOML document character by character.
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn error(&self, message: &str) -> String {
+        let line = self.chars[..self.pos.min(self.chars.len())]
+            .iter()
+            .filter(|&&c| c == '\n')
+            .count();
+        format!("line {}: {}", line + 1, message)
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<char> {
+        self.chars.get(self.pos + offset).copied()
+    }
+
+    fn starts_with(&self, s: &str) -> bool {
+        s.chars()
+            .enumerate()
+            .all(|(i, c)| self.peek_at(i) == Some(c))
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), String> {
+        if self.peek() != Some(c) {
+            return Err(self.error(&format!("expected '{}'", c)));
+        }
+        self.pos += 1;
+        Ok(())
+    }
+
+    fn skip_spaces(&mut self) {
+        while matches!(self.peek(), Some(' ' | '\t')) {
+            self.pos += 1;
+        }
+    }
+
+    fn skip_comment(&mut self) {
+        if self.peek() == Some('#') {
+            while self.peek().is_some_and(|c| c != '\n') {
+                self.pos += 1;
+            }
+        }
+    }
+
+    fn skip_whitespace_and_comments(&mut self) {
+        loop {
+            self.skip_spaces();
+            self.skip_comment();
+            match self.peek() {
+                Some('\n') => self.pos += 1,
+                Some('\r') if self.peek_at(1) == Some('\n') => self.pos += 2,
+                _ => return,
+            }
+        }
+    }
+
+    /// Checks that nothing but a comment follows on the line.
+    fn end_of_line(&mut self) -> Result<(), String> {
+        self.skip_spaces();
+        self.skip_comment();
+        match self.peek() {
+            None | Some('\n') => Ok(()),
+            Some('\r') if self.peek_at(1) == Some('\n') => Ok(()),
+            Some(_) => Err(self.error("expected the end of the line")),
+        }
+    }
+
+    /// Reads a key, which may be dotted, e.g. `exercises.data` or `"a b".c`.
+    fn parse_key(&mut self) -> Result<Vec<String>, String> {
+        let mut keys = Vec::new();
+        loop {
+            self.skip_spaces();
+            let key = match self.peek() {
+                Some('"') if !self.starts_with("\"\"\"") => self.parse_basic_string()?,
+                Some('\'') if !self.starts_with("'''") => self.parse_literal_string()?,
+                _ => {
+                    let start = self.pos;
+                    while self
+                        .peek()
+                        .is_some_and(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+                    {
+                        self.pos += 1;
+                    }
+                    if self.pos == start {
+                        return Err(self.error("expected a key"));
+                    }
+                    self.chars[start..self.pos].iter().collect()
+                }
+            };
+            keys.push(key);
+            self.skip_spaces();
+            if self.peek() != Some('.') {
+                return Ok(keys);
+            }
+            self.pos += 1;
+        }
+    }
+
+    fn parse_value(&mut self) -> Result<Value, String> {
+        match self.peek() {
+            Some('"') if self.starts_with("\"\"\"") => self.parse_multiline_string('"'),
+            Some('\'') if self.starts_with("'''") => self.parse_multiline_string('\''),
+            Some('"') => self.parse_basic_string().map(Value::String),
+            Some('\'') => self.parse_literal_string().map(Value::String),
+            Some('[') => self.parse_array(),
+            Some('{') => self.parse_inline_table(),
+            Some('t') if self.starts_with("true") => {
+                self.pos += 4;
+                Ok(Value::Bool(true))
+            }
+            Some('f') if self.starts_with("false") => {
+                self.pos += 5;
+                Ok(Value::Bool(false))
+            }
+            Some(_) => self.parse_number(),
+            None => Err(self.error("expected a value")),
+        }
+    }
+
+    /// Reads a basic string on one line, with escapes.
+    fn parse_basic_string(&mut self) -> Result<String, String> {
+        self.pos += 1;
+        let mut s = String::new();
+        loop {
+            match self.peek() {
+                None | Some('\n') => return Err(self.error("unterminated string")),
+                Some('"') => {
+                    self.pos += 1;
+                    return Ok(s);
+                }
+                Some('\\') => {
+                    self.pos += 1;
+                    s.push(self.parse_escape()?);
+                }
+                Some(c) => {
+                    self.pos += 1;
+                    s.push(c);
+                }
+            }
+        }
+    }
+
+    /// Reads a literal string on one line, without escapes.
+    fn parse_literal_string(&mut self) -> Result<String, String> {
+        self.pos += 1;
+        let start = self.pos;
+        loop {
+            match self.peek() {
+                None | Some('\n') => return Err(self.error("unterminated string")),
+                Some('\'') => {
+                    let s = self.chars[start..self.pos].iter().collect();
+                    self.pos += 1;
+                    return Ok(s);
+                }
+                Some(_) => self.pos += 1,
+            }
+        }
+    }
+
+    /// Reads a multi-line string delimited by three `quote`s, with escapes for `"`.
+    fn parse_multiline_string(&mut self, quote: char) -> Result<Value, String> {
+        self.pos += 3;
+        // A line break right after the opening quotes is not part of the string.
+        if self.peek() == Some('\n') {
+            self.pos += 1;
+        } else if self.starts_with("\r\n") {
+            self.pos += 2;
+        }
+        let delimiter: String = [quote; 3].iter().collect();
+        let mut s = String::new();
+        loop {
+            if self.starts_with(&delimiter) {
+                // Up to two quotes right before the closing ones belong to the string.
+                let mut extra = 0;
+                while extra < 2 && self.peek_at(3 + extra) == Some(quote) {
+                    extra += 1;
+                }
+                s.extend(std::iter::repeat_n(quote, extra));
+                self.pos += 3 + extra;
+                return Ok(Value::String(s));
+            }
+            match self.peek() {
+                None => return Err(self.error("unterminated multi-line string")),
+                Some('\\') if quote == '"' => {
+                    self.pos += 1;
+                    // A backslash at the end of a line trims the break and the whitespace
+                    // that follows.
+                    let rest = self.pos;
+                    self.skip_spaces();
+                    if matches!(self.peek(), Some('\n' | '\r')) {
+                        while matches!(self.peek(), Some(' ' | '\t' | '\n' | '\r')) {
+                            self.pos += 1;
+                        }
+                    } else {
+                        self.pos = rest;
+                        s.push(self.parse_escape()?);
+                    }
+                }
+                Some('\r') if self.peek_at(1) == Some('\n') => {
+                    self.pos += 2;
+                    s.push('\n');
+                }
+                Some(c) => {
+                    self.pos += 1;
+                    s.push(c);
+                }
+            }
+        }
+    }
+
+    /// Reads the character escaped after a backslash in a basic string.
+    fn parse_escape(&mut self) -> Result<char, String> {
+        let Some(c) = self.peek() else {
+            return Err(self.error("unterminated string"));
+        };
+        self.pos += 1;
+        let hex_digits = match c {
+            'b' => return Ok('\u{8}'),
+            't' => return Ok('\t'),
+            'n' => return Ok('\n'),
+            'f' => return Ok('\u{c}'),
+            'r' => return Ok('\r'),
+            'e' => return Ok('\u{1b}'),
+            '"' => return Ok('"'),
+            '\\' => return Ok('\\'),
+            'u' => 4,
+            'U' => 8,
+            _ => return Err(self.error(&format!("unknown escape '\\{}'", c))),
+        };
+        let digits: String = self
+            .chars
+            .get(self.pos..self.pos + hex_digits)
+            .unwrap_or(&[])
+            .iter()
+            .collect();
+        self.pos += hex_digits;
+        u32::from_str_radix(&digits, 16)
+            .ok()
+            .filter(|_| digits.len() == hex_digits)
+            .and_then(char::from_u32)
+            .ok_or_else(|| self.error(&format!("invalid escape '\\{}{}'", c, digits)))
+    }
+
+    /// Reads an array, which may span several lines and end in a comma.
+    fn parse_array(&mut self) -> Result<Value, String> {
+        self.pos += 1;
+        let mut items = Vec::new();
+        loop {
+            self.skip_whitespace_and_comments();
+            if self.peek() == Some(']') {
+                self.pos += 1;
+                return Ok(Value::Array(items));
+            }
+            items.push(self.parse_value()?);
+            self.skip_whitespace_and_comments();
+            match self.peek() {
+                Some(',') => self.pos += 1,
+                Some(']') => {}
+                _ => return Err(self.error("expected ',' or ']'")),
+            }
+        }
+    }
+
+    /// Reads an inline table such as `{ a = 1, b.c = "d" }`, which must fit on one line.
+    fn parse_inline_table(&mut self) -> Result<Value, String> {
+        self.pos += 1;
+        let mut table = Map::new();
+        self.skip_spaces();
+        if self.peek() == Some('}') {
+            self.pos += 1;
+            return Ok(Value::Object(table));
+        }
+        loop {
+            let keys = self.parse_key()?;
+            self.skip_spaces();
+            self.expect('=')?;
+            self.skip_spaces();
+            let value = self.parse_value()?;
+            let (last, parents) = keys.split_last().expect("a key has at least one part");
+            let target = table_at(&mut table, parents).map_err(|e| self.error(&e))?;
+            if target.insert(last.clone(), value).is_some() {
+                return Err(self.error(&format!("duplicate key '{}'", keys.join("."))));
+            }
+            self.skip_spaces();
+            match self.peek() {
+                Some(',') => {
+                    self.pos += 1;
+                    self.skip_spaces();
+                }
+                Some('}') => {
+                    self.pos += 1;
+                    return Ok(Value::Object(table));
+                }
+                _ => return Err(self.error("expected ',' or '}'")),
+            }
+        }
+    }
+
+    /// Reads an integer or a float, e.g. `42`, `-1_000`, `0xff`, `3.5` or `1e6`.
+    fn parse_number(&mut self) -> Result<Value, String> {
+        let start = self.pos;
+        while self
+            .peek()
+            .is_some_and(|c| c.is_ascii_alphanumeric() || "+-._:".contains(c))
+        {
+            self.pos += 1;
+        }
+        let token: String = self.chars[start..self.pos].iter().collect();
+        if token.contains(':') || (token.len() >= 10 && token.as_bytes()[4] == b'-') {
+            return Err(self.error("dates and times are not supported"));
+        }
+        let invalid = || self.error(&format!("invalid value '{}'", token));
+        if token.is_empty()
+            || token.starts_with('_')
+            || token.ends_with('_')
+            || token.contains("__")
+        {
+            return Err(invalid());
+        }
+        let digits = token.replace('_', "");
+
+        for (prefix, radix) in [("0x", 16), ("0o", 8), ("0b", 2)] {
+            if let Some(rest) = digits.strip_prefix(prefix) {
+                return u64::from_str_radix(rest, radix)
+                    .ok()
+                    .filter(|&n| n <= i64::MAX as u64)
+                    .map(|n| Value::Number(n.into()))
+                    .ok_or_else(invalid);
+            }
+        }
+        let unsigned = digits.strip_prefix(['+', '-']).unwrap_or(&digits);
+        if unsigned.len() > 1
+            && unsigned.starts_with('0')
+            && unsigned.as_bytes()[1].is_ascii_digit()
+        {
+            return Err(invalid());
+        }
+        if unsigned.bytes().all(|b| b.is_ascii_digit()) {
+            return digits
+                .parse::<i64>()
+                .map(|n| Value::Number(n.into()))
+                .map_err(|_| invalid());
+        }
+        if matches!(unsigned, "inf" | "nan") {
+            return Err(self.error(&format!(
+                "'{}' can't be stored; numbers must be finite",
+                token
+            )));
+        }
+        if unsigned
+            .bytes()
+            .all(|b| b.is_ascii_digit() || b"+-.eE".contains(&b))
+            && !unsigned.starts_with('.')
+            && !unsigned.ends_with('.')
+        {
+            if let Some(n) = digits.parse::<f64>().ok().and_then(Number::from_f64) {
+                return Ok(Value::Number(n));
+            }
+        }
+        Err(invalid())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn reads_literal_and_multiline_strings() {
+        let text = r#"
+basic = "tab\tquote\" \u00e9"
+literal = 'C:\no\escapes "here"'
+multiline = """
+first line
+second \
+    joined"""
+multiline_literal = '''
+keeps \n as typed
+'''
+"#;
+        assert_eq!(
+            from_str(text).unwrap(),
+            json!({
+                "basic": "tab\tquote\" \u{e9}",
+                "literal": r#"C:\no\escapes "here""#,
+                "multiline": "first line\nsecond joined",
+                "multiline_literal": "keeps \\n as typed\n",
+            })
+        );
+    }
+
+    #[test]
+    fn rejects_dates_and_times() {
+        assert!(from_str("when = 2026-10-16\n").is_err());
+    }
+}
@@ -0,0 +1,212 @@
+use crate::exercise::{option_index, option_letter, Exercise};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+/// An inconsistency found by `verify`, numbered from 1 like `--list` shows it.
+#[derive(Serialize)]
+pub struct Problem {
+    pub group: usize,
+    pub question: usize,
+    pub message: String,
+}
+
+impl Problem {
+    /// Returns where the question is in the data file, e.g. `$.exercises[0].data[2]`.
+    pub fn json_path(&self) -> String {
+        format!(
+            "$.exercises[{}].data[{}]",
+            self.group - 1,
+            self.question - 1
+        )
+    }
+}
+
+/// Checks the option lettering invariants of every multiple choice and matching question.
+///
+/// Options are shown and picked by letter (`a` for the first option), so every stored
+/// answer must be exactly one of the options, every option position must have a plain
+/// `a`-`z` letter, and that letter must lead back to the same position.
+///
+/// # Arguments
+///
+/// * `exercises` - The deck to check.
+///
+/// # Returns
+///
+/// Every problem found, in deck order.
+pub fn verify(exercises: &[Exercise]) -> Vec<Problem> {
+    let mut problems = Vec::new();
+
+    for (group, exercise) in exercises.iter().enumerate() {
+        let mut report = |question: usize, message: String| {
+            problems.push(Problem {
+                group: group + 1,
+                question: question + 1,
+                message,
+            })
+        };
+
+        match exercise {
+            Exercise::Mcq(items) => {
+                for (q, mcq) in items.iter().enumerate() {
+                    check_answer(mcq.answer(), mcq.options(), |m| report(q, m));
+                    check_letters(mcq.options().len(), |m| report(q, m));
+                    if !mcq.feedback().is_empty() && mcq.feedback().len() != mcq.options().len() {
+                        report(
+                            q,
+                            format!(
+                                "{} feedback entries for {} options",
+                                mcq.feedback().len(),
+                                mcq.options().len()
+                            ),
+                        );
+                    }
+                }
+            }
+            Exercise::McqMulti(items) => {
+                for (q, mcq) in items.iter().enumerate() {
+                    for answer in mcq.answers() {
+                        check_answer(answer, mcq.options(), |m| report(q, m));
+                    }
+                    check_letters(mcq.options().len(), |m| report(q, m));
+                }
+            }
+            Exercise::Matching(items) => {
+                // The options of a matching group are not stored separately, they are the
+                // answers chosen for its questions.
+                let mut options: Vec<String> = Vec::new();
+                for item in items {
+                    if !options.iter().any(|o| o == item.answer()) {
+                        options.push(item.answer().to_string());
+                    }
+                }
+                for (q, item) in items.iter().enumerate() {
+                    check_answer(item.answer(), &options, |m| report(q, m));
+                }
+                check_letters(options.len(), |m| report(0, m));
+            }
+            _ => {}
+        }
+    }
+
+    problems
+}
+
+/// Checks that every question has a prompt and an ID of its own.
+///
+/// # Returns
+///
+/// A problem for every empty question and every repeated ID, in deck order; a repeated ID
+/// is reported at each question after the first that has it.
+pub fn verify_questions(exercises: &[Exercise]) -> Vec<Problem> {
+    let mut first_with_id: HashMap<&str, (usize, usize)> = HashMap::new();
+    let mut problems = Vec::new();
+    for (group, exercise) in exercises.iter().enumerate() {
+        for (question, q) in exercise.questions().into_iter().enumerate() {
+            let mut report = |message| {
+                problems.push(Problem {
+                    group: group + 1,
+                    question: question + 1,
+                    message,
+                })
+            };
+            if q.text().trim().is_empty() {
+                report("the question is empty".to_string());
+            }
+            let id = q.meta().id.as_str();
+            if id.is_empty() {
+                report("the question has no ID".to_string());
+            } else if let Some((g, i)) = first_with_id.get(id) {
+                report(format!("ID {} is also used by [{}] {}.", id, g, i));
+            } else {
+                first_with_id.insert(id, (group + 1, question + 1));
+            }
+        }
+    }
+    problems
+}
+
+/// Checks that every link between questions leads to a question in the deck.
+///
+/// # Returns
+///
+/// A problem for every link to an unknown ID, in deck order.
+pub fn verify_links(exercises: &[Exercise]) -> Vec<Problem> {
+    let ids: HashSet<&str> = exercises
+        .iter()
+        .flat_map(|e| e.questions())
+        .map(|q| q.meta().id.as_str())
+        .collect();
+
+    let mut problems = Vec::new();
+    for (group, exercise) in exercises.iter().enumerate() {
+        for (question, q) in exercise.questions().iter().enumerate() {
+            for link in &q.meta().links {
+                if !ids.contains(link.as_str()) {
+                    problems.push(Problem {
+                        group: group + 1,
+                        question: question + 1,
+                        message: format!("linked question {} does not exist", link),
+                    });
+                }
+            }
+        }
+    }
+    problems
+}
+
+/// Checks that every media file the deck refers to is in the media directory `dir`.
+///
+/// # Returns
+///
+/// A problem for every question whose attachment is missing, in deck order.
+pub fn verify_media(exercises: &[Exercise], dir: &Path) -> Vec<Problem> {
+    let mut problems = Vec::new();
+    for (group, exercise) in exercises.iter().enumerate() {
+        for (question, q) in exercise.questions().iter().enumerate() {
+            if let Some(media) = &q.meta().media {
+                if !dir.join(media).is_file() {
+                    problems.push(Problem {
+                        group: group + 1,
+                        question: question + 1,
+                        message: format!("media file {} is missing", media),
+                    });
+                }
+            }
+        }
+    }
+    problems
+}
+
+/// Reports an answer that isn't exactly one of the options.
+fn check_answer(answer: &str, options: &[String], mut report: impl FnMut(String)) {
+    match options.iter().filter(|o| *o == answer).count() {
+        0 => report(format!("answer '{}' is not one of the options", answer)),
+        1 => {}
+        n => report(format!(
+            "answer '{}' appears {} times in the options",
+            answer, n
+        )),
+    }
+}
+
+/// Reports option positions whose letter is out of range or doesn't round-trip.
+fn check_letters(count: usize, mut report: impl FnMut(String)) {
+    for i in 0..count {
+        let letter = option_letter(i);
+        if !letter.is_ascii_lowercase() {
+            report(format!(
+                "option {} is shown as '{}', not a letter",
+                i + 1,
+                letter
+            ));
+        } else if option_index(letter) != Some(i) {
+            report(format!(
+                "option ({}) does not map back to position {}",
+                letter,
+                i + 1
+            ));
+        }
+    }
+}
@@ -0,0 +1,774 @@
+use crate::node::Node;
+use serde_json::{Map, Number, Value};
+
+/// Writes a value as a YAML document in block style, one field per line.
+///
+/// Strings are written plainly where YAML would read them back as the same string, in a
+/// literal block (`|`) when they span several lines, and double-quoted otherwise.
+pub fn to_string(value: &Node) -> String {
+    let mut out = String::new();
+    match value {
+        Node::Object(fields) if !fields.is_empty() => write_mapping(&mut out, fields, 0),
+        Node::Array(items) if !items.is_empty() => write_sequence(&mut out, items, 0),
+        scalar => {
+            out.push_str(&inline(scalar));
+            out.push('\n');
+        }
+    }
+    out
+}
+
+/// Writes the fields of a mapping, each on its own line starting at `indent`.
+fn write_mapping(out: &mut String, fields: &[(String, Node)], indent: usize) {
+    for (key, value) in fields {
+        out.push_str(&" ".repeat(indent));
+        out.push_str(&scalar(key));
+        out.push(':');
+        write_child(out, value, indent);
+    }
+}
+
+/// Writes the items of a sequence, each starting with a dash at `indent`.
+///
+/// A mapping or sequence item starts on the dash's line, e.g. `- question: ...`.
+fn write_sequence(out: &mut String, items: &[Node], indent: usize) {
+    for item in items {
+        let mut nested = String::new();
+        match item {
+            Node::Object(fields) if !fields.is_empty() => {
+                write_mapping(&mut nested, fields, indent + 2)
+            }
+            Node::Array(items) if !items.is_empty() => {
+                write_sequence(&mut nested, items, indent + 2)
+            }
+            _ => {
+                out.push_str(&" ".repeat(indent));
+                out.push('-');
+                write_child(out, item, indent);
+                continue;
+            }
+        }
+        out.push_str(&" ".repeat(indent));
+        out.push_str("- ");
+        out.push_str(&nested[indent + 2..]);
+    }
+}
+
+/// Writes the value following a `key:` or `-` written at `indent`, ending the line.
+fn write_child(out: &mut String, value: &Node, indent: usize) {
+    match value {
+        Node::Object(fields) if !fields.is_empty() => {
+            out.push('\n');
+            write_mapping(out, fields, indent + 2);
+        }
+        Node::Array(items) if !items.is_empty() => {
+            out.push('\n');
+            write_sequence(out, items, indent + 2);
+        }
+        Node::String(s) if fits_literal_block(s) => {
+            let body = s.trim_end_matches('\n');
+            let chomping = match s.len() - body.len() {
+                0 => "-",
+                1 => "",
+                _ => "+",
+            };
+            out.push_str(&format!(" |{}\n", chomping));
+            for line in body.split('\n') {
+                if !line.is_empty() {
+                    out.push_str(&" ".repeat(indent + 2));
+                    out.push_str(line);
+                }
+                out.push('\n');
+            }
+            for _ in 1..s.len() - body.len() {
+                out.push('\n');
+            }
+        }
+        _ => {
+            out.push(' ');
+            out.push_str(&inline(value));
+            out.push('\n');
+        }
+    }
+}
+
+/// Writes a value that fits on the line of its key: a scalar or an empty collection.
+fn inline(value: &Node) -> String {
+    match value {
+        Node::Null => "null".to_string(),
+        Node::Bool(b) => b.to_string(),
+        Node::Number(n) => n.to_string(),
+        Node::String(s) => scalar(s),
+        Node::Array(_) => "[]".to_string(),
+        Node::Object(_) => "{}".to_string(),
+    }
+}
+
+/// Checks whether a string can be written as a literal block and read back unchanged.
+fn fits_literal_block(s: &str) -> bool {
+    let body = s.trim_end_matches('\n');
+    s.contains('\n')
+        && !body.is_empty()
+        && !body.starts_with([' ', '\n'])
+        && body
+            .split('\n')
+            .all(|line| line.is_empty() || !line.trim_matches(' ').is_empty())
+        && s.chars().all(|c| c == '\n' || c == '\t' || is_printable(c))
+}
+
+/// Checks whether a character can appear in a YAML file as it is.
+fn is_printable(c: char) -> bool {
+    !(c.is_control() || matches!(c, '\u{feff}' | '\u{2028}' | '\u{2029}'))
+}
+
+/// Writes a string, plainly if that reads back as the same string, otherwise quoted.
+fn scalar(s: &str) -> String {
+    let first = s.chars().next();
+    let plain = !s.is_empty()
+        && s.trim() == s
+        && s.chars().all(is_printable)
+        && !first.is_some_and(|c| "-?:,[]{}#&*!|>'\"%@`.+".contains(c) || c.is_ascii_digit())
+        && !s.contains(": ")
+        && !s.contains(" #")
+        && !s.ends_with(':')
+        && !matches!(
+            s.to_lowercase().as_str(),
+            "null" | "true" | "false" | "yes" | "no" | "on" | "off" | "y" | "n"
+        )
+        && matches!(resolve_plain(s), Ok(Value::String(_)));
+    if plain {
+        return s.to_string();
+    }
+
+    let mut quoted = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\t' => quoted.push_str("\\t"),
+            '\r' => quoted.push_str("\\r"),
+            c if !is_printable(c) => quoted.push_str(&format!("\\u{:04X}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Parses a YAML document into a value.
+///
+/// Block mappings and sequences, flow collections written on one line, plain and quoted
+/// scalars, block scalars (`|` and `>`) and comments are understood, which covers what
+/// `to_string` writes and what is usually written by hand. Anchors, aliases, tags and
+/// several documents in one file are reported as errors rather than misread.
+///
+/// # Returns
+///
+/// The value, or an error naming the line where parsing failed.
+pub fn from_str(text: &str) -> Result<Value, String> {
+    let mut parser = Parser {
+        lines: text.lines().map(String::from).collect(),
+        pos: 0,
+    };
+    parser.skip_blank();
+    if parser.peek()?.is_some_and(|(_, content)| content == "---") {
+        parser.pos += 1;
+    }
+
+    let value = parser.parse_block(0)?;
+    parser.skip_blank();
+    match parser.peek()? {
+        None => Ok(value),
+        Some((_, content)) if content == "..." => Ok(value),
+        Some((_, content)) if content == "---" => {
+            Err(parser.error("only one document per file is supported"))
+        }
+        Some(_) => Err(parser.error("unexpected indentation or text")),
+    }
+}
+
+/// Reads the block structure of a YAML document line by line.
+struct Parser {
+    lines: Vec<String>,
+    /// The index of the current line.
+    pos: usize,
+}
+
+impl Parser {
+    fn error(&self, message: &str) -> String {
+        format!("line {}: {}", self.pos + 1, message)
+    }
+
+    /// Moves past blank lines and lines holding only a comment.
+    fn skip_blank(&mut self) {
+        while self.pos < self.lines.len() {
+            let line = self.lines[self.pos].trim();
+            if !line.is_empty() && !line.starts_with('#') {
+                break;
+            }
+            self.pos += 1;
+        }
+    }
+
+    /// Returns the indentation and the trimmed content of the current line.
+    fn peek(&self) -> Result<Option<(usize, String)>, String> {
+        let Some(line) = self.lines.get(self.pos) else {
+            return Ok(None);
+        };
+        let content = line.trim_start_matches(' ');
+        if content.starts_with('\t') {
+            return Err(self.error("tabs can't be used to indent YAML"));
+        }
+        Ok(Some((
+            line.len() - content.len(),
+            content.trim_end().to_string(),
+        )))
+    }
+
+    /// Parses the node starting on the next line, if it is indented by at least `min`.
+    fn parse_block(&mut self, min: usize) -> Result<Value, String> {
+        self.skip_blank();
+        let Some((indent, content)) = self.peek()? else {
+            return Ok(Value::Null);
+        };
+        if indent < min || is_document_marker(indent, &content) {
+            return Ok(Value::Null);
+        }
+        if is_sequence_item(&content) {
+            self.parse_sequence(indent)
+        } else if self.mapping_key(&content)?.is_some() {
+            self.parse_mapping(indent)
+        } else {
+            let value = self.parse_inline(&content)?;
+            self.pos += 1;
+            Ok(value)
+        }
+    }
+
+    /// Parses the entries of a block mapping indented by `indent`.
+    fn parse_mapping(&mut self, indent: usize) -> Result<Value, String> {
+        let mut map = Map::new();
+        loop {
+            self.skip_blank();
+            let Some((n, content)) = self.peek()? else {
+                break;
+            };
+            if n < indent || is_sequence_item(&content) || is_document_marker(n, &content) {
+                break;
+            }
+            if n > indent {
+                return Err(self.error("unexpected indentation"));
+            }
+            let Some((key, rest)) = self.mapping_key(&content)? else {
+                return Err(self.error("expected `key: value`"));
+            };
+            if map.contains_key(&key) {
+                return Err(self.error(&format!("duplicate key '{}'", key)));
+            }
+            let value = self.parse_value(&rest, indent, true)?;
+            map.insert(key, value);
+        }
+        Ok(Value::Object(map))
+    }
+
+    /// Parses the items of a block sequence whose dashes are indented by `indent`.
+    fn parse_sequence(&mut self, indent: usize) -> Result<Value, String> {
+        let mut items = Vec::new();
+        loop {
+            self.skip_blank();
+            let Some((n, content)) = self.peek()? else {
+                break;
+            };
+            if n < indent || !is_sequence_item(&content) {
+                break;
+            }
+            if n > indent {
+                return Err(self.error("unexpected indentation"));
+            }
+            let after = &content[1..];
+            let rest = after.trim_start_matches(' ');
+            if rest.is_empty() || rest.starts_with(['#', '|', '>']) {
+                items.push(self.parse_value(rest, indent, false)?);
+            } else {
+                // The item starts on the dash's line: read it as if the dash were a space.
+                let column = indent + 1 + after.len() - rest.len();
+                self.lines[self.pos] = format!("{}{}", " ".repeat(column), rest);
+                items.push(self.parse_block(column)?);
+            }
+        }
+        Ok(Value::Array(items))
+    }
+
+    /// Parses the value after a `key:` or `-` on the current line of a node at `indent`.
+    ///
+    /// An empty rest means the value is the block on the following lines; a mapping's
+    /// value may also be a sequence indented as much as its key.
+    fn parse_value(
+        &mut self,
+        rest: &str,
+        indent: usize,
+        in_mapping: bool,
+    ) -> Result<Value, String> {
+        if rest.is_empty() || rest.starts_with('#') {
+            self.pos += 1;
+            self.skip_blank();
+            return match self.peek()? {
+                Some((n, content)) if n == indent && in_mapping && is_sequence_item(&content) => {
+                    self.parse_sequence(n)
+                }
+                _ => self.parse_block(indent + 1),
+            };
+        }
+        if rest.starts_with(['|', '>']) {
+            return self.parse_block_scalar(rest, indent);
+        }
+        let value = self.parse_inline(rest)?;
+        self.pos += 1;
+        Ok(value)
+    }
+
+    /// Splits a line into a mapping key and the rest after its colon.
+    ///
+    /// # Returns
+    ///
+    /// `None` if the line isn't a `key: value` entry.
+    fn mapping_key(&self, content: &str) -> Result<Option<(String, String)>, String> {
+        if content.starts_with(['[', '{']) {
+            return Ok(None);
+        }
+        if content == "?" || content.starts_with("? ") {
+            return Err(self.error("complex mapping keys (`?`) are not supported"));
+        }
+        if content.starts_with(['"', '\'']) {
+            let mut inline = Inline::new(content, self.pos);
+            let key = inline.parse_quoted()?;
+            let rest: String = inline.chars[inline.pos..].iter().collect();
+            let rest = rest.trim_start_matches(' ');
+            return Ok(rest
+                .strip_prefix(':')
+                .filter(|r| r.is_empty() || r.starts_with(' '))
+                .map(|r| (key, r.trim().to_string())));
+        }
+
+        let bytes = content.as_bytes();
+        for (i, &b) in bytes.iter().enumerate() {
+            if b == b'#' && i > 0 && bytes[i - 1] == b' ' {
+                return Ok(None);
+            }
+            if b == b':' && bytes.get(i + 1).is_none_or(|&next| next == b' ') {
+                let key = content[..i].trim_end();
+                if key.is_empty() || key.starts_with(['&', '*', '!']) {
+                    return Err(self.error("anchors, aliases and tags are not supported"));
+                }
+                return Ok(Some((key.to_string(), content[i + 1..].trim().to_string())));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Parses a block scalar started by `header` (e.g. `|` or `>-`) on the current line,
+    /// whose content is indented more than `indent`.
+    fn parse_block_scalar(&mut self, header: &str, indent: usize) -> Result<Value, String> {
+        let folded = header.starts_with('>');
+        let mut chomping = ' ';
+        let mut explicit = None;
+        let indicators = header[1..].split('#').next().unwrap_or("").trim_end();
+        for c in indicators.chars() {
+            match c {
+                '-' | '+' if chomping == ' ' => chomping = c,
+                '1'..='9' if explicit.is_none() => explicit = c.to_digit(10),
+                _ => return Err(self.error("invalid block scalar header")),
+            }
+        }
+        self.pos += 1;
+
+        let is_blank = |line: &str| line.chars().all(|c| c == ' ');
+        let content_indent = match explicit {
+            Some(n) => indent + n as usize,
+            None => self.lines[self.pos..]
+                .iter()
+                .find(|line| !is_blank(line))
+                .map_or(0, |line| line.len() - line.trim_start_matches(' ').len()),
+        };
+        let mut lines = Vec::new();
+        if content_indent > indent {
+            while let Some(line) = self.lines.get(self.pos) {
+                if is_blank(line) {
+                    lines.push(line.get(content_indent..).unwrap_or("").to_string());
+                } else if line.len() - line.trim_start_matches(' ').len() >= content_indent {
+                    lines.push(line[content_indent..].to_string());
+                } else {
+                    break;
+                }
+                self.pos += 1;
+            }
+        }
+
+        let trailing = lines.iter().rev().take_while(|l| l.is_empty()).count();
+        let body = &lines[..lines.len() - trailing];
+        let mut text = if folded { fold(body) } else { body.join("\n") };
+        match chomping {
+            '-' => {}
+            '+' => text.push_str(&"\n".repeat(trailing + usize::from(!body.is_empty()))),
+            _ if !body.is_empty() => text.push('\n'),
+            _ => {}
+        }
+        Ok(Value::String(text))
+    }
+
+    /// Parses a scalar or a one-line flow collection making up the rest of a line.
+    fn parse_inline(&self, text: &str) -> Result<Value, String> {
+        let mut inline = Inline::new(text, self.pos);
+        let value = inline.parse_value(false)?;
+        inline.skip_spaces();
+        match inline.peek() {
+            None | Some('#') => Ok(value),
+            Some(_) => Err(inline.error("unexpected text after the value")),
+        }
+    }
+}
+
+/// Checks whether a line starts (`---`) or ends (`...`) a document.
+fn is_document_marker(indent: usize, content: &str) -> bool {
+    indent == 0 && (content == "---" || content == "...")
+}
+
+/// Checks whether a line starts a sequence item.
+fn is_sequence_item(content: &str) -> bool {
+    content == "-" || content.starts_with("- ")
+}
+
+/// Joins the lines of a folded block scalar: line breaks between lines of text become
+/// spaces, and a break followed by blank lines is dropped in favour of theirs. Lines
+/// indented further are kept on lines of their own.
+fn fold(lines: &[String]) -> String {
+    let is_text = |line: &String| !line.is_empty() && !line.starts_with([' ', '\t']);
+    let mut text = String::new();
+    for (i, line) in lines.iter().enumerate() {
+        if i > 0 {
+            let previous = &lines[i - 1];
+            let next_text = lines[i..].iter().find(|l| !l.is_empty());
+            if is_text(previous) && is_text(line) {
+                text.push(' ');
+            } else if !(is_text(previous) && line.is_empty() && next_text.is_some_and(is_text)) {
+                text.push('\n');
+            }
+        }
+        text.push_str(line);
+    }
+    text
+}
+
+/// Works out the value of a plain scalar: null, a boolean, a number or a string.
+fn resolve_plain(s: &str) -> Result<Value, String> {
+    match s {
+        "" | "~" | "null" | "Null" | "NULL" => return Ok(Value::Null),
+        "true" | "True" | "TRUE" => return Ok(Value::Bool(true)),
+        "false" | "False" | "FALSE" => return Ok(Value::Bool(false)),
+        ".inf" | "+.inf" | "-.inf" | ".Inf" | ".INF" | ".nan" | ".NaN" | ".NAN" => {
+            return Err(format!("'{}' can't be stored; numbers must be finite", s))
+        }
+        _ => {}
+    }
+
+    let unsigned = s.strip_prefix(['-', '+']).unwrap_or(s);
+    if !unsigned.is_empty() && unsigned.bytes().all(|b| b.is_ascii_digit()) {
+        if let Ok(n) = s.parse::<i64>() {
+            return Ok(Value::Number(n.into()));
+        }
+        if let Ok(n) = unsigned.parse::<u64>() {
+            if !s.starts_with('-') {
+                return Ok(Value::Number(n.into()));
+            }
+        }
+    }
+    for (prefix, radix) in [("0x", 16), ("0o", 8)] {
+        if let Some(digits) = s.strip_prefix(prefix) {
+            if let Ok(n) = u64::from_str_radix(digits, radix) {
+                return Ok(Value::Number(n.into()));
+            }
+        }
+    }
+    if s.bytes().any(|b| b.is_ascii_digit())
+        && s.bytes()
+            .all(|b| b.is_ascii_digit() || b"+-.eE".contains(&b))
+    {
+        if let Some(n) = s.parse::<f64>().ok().and_then(Number::from_f64) {
+            return Ok(Value::Number(n));
+        }
+    }
+    Ok(Value::String(s.to_string()))
+}
+
+/// Reads a value written on one line: a scalar or a flow collection.
+struct Inline {
+    chars: Vec<char>,
+    pos: usize,
+    /// The index of the line being read, for error messages.
+    line: usize,
+}
+
+impl Inline {
+    fn new(text: &str, line: usize) -> Self {
+        Inline {
+            chars: text.chars().collect(),
+            pos: 0,
+            line,
+        }
+    }
+
+    fn error(&self, message: &str) -> String {
+        format!("line {}: {}", self.line + 1, message)
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn skip_spaces(&mut self) {
+        while self.peek().is_some_and(|c| c == ' ' || c == '\t') {
+            self.pos += 1;
+        }
+    }
+
+    /// Parses a value, which ends at a flow indicator when `in_flow` is set.
+    fn parse_value(&mut self, in_flow: bool) -> Result<Value, String> {
+        self.skip_spaces();
+        match self.peek() {
+            Some('[') => self.parse_flow_sequence(),
+            Some('{') => self.parse_flow_mapping(),
+            Some('"' | '\'') => self.parse_quoted().map(Value::String),
+            Some('&' | '*' | '!') => Err(self.error("anchors, aliases and tags are not supported")),
+            _ => {
+                let plain = self.parse_plain(in_flow);
+                resolve_plain(&plain).map_err(|e| self.error(&e))
+            }
+        }
+    }
+
+    /// Reads a plain scalar up to a comment, the end of the line, or in a flow collection
+    /// up to the next indicator.
+    fn parse_plain(&mut self, in_flow: bool) -> String {
+        let start = self.pos;
+        while let Some(c) = self.peek() {
+            let next = self.chars.get(self.pos + 1).copied();
+            let ends = match c {
+                '#' => self.pos > start && self.chars[self.pos - 1] == ' ',
+                ',' | ']' | '}' => in_flow,
+                ':' => in_flow && next.is_none_or(|n| " ,]}".contains(n)),
+                _ => false,
+            };
+            if ends {
+                break;
+            }
+            self.pos += 1;
+        }
+        self.chars[start..self.pos]
+            .iter()
+            .collect::<String>()
+            .trim_end()
+            .to_string()
+    }
+
+    /// Reads a single- or double-quoted string, which must end on the same line.
+    fn parse_quoted(&mut self) -> Result<String, String> {
+        let quote = self.chars[self.pos];
+        self.pos += 1;
+        let mut s = String::new();
+        loop {
+            let Some(c) = self.peek() else {
+                return Err(self.error("unterminated quoted string"));
+            };
+            self.pos += 1;
+            match c {
+                '\'' if quote == '\'' => {
+                    if self.peek() == Some('\'') {
+                        self.pos += 1;
+                        s.push('\'');
+                    } else {
+                        return Ok(s);
+                    }
+                }
+                '"' if quote == '"' => return Ok(s),
+                '\\' if quote == '"' => s.push(self.parse_escape()?),
+                c => s.push(c),
+            }
+        }
+    }
+
+    /// Reads the character escaped after a backslash in a double-quoted string.
+    fn parse_escape(&mut self) -> Result<char, String> {
+        let Some(c) = self.peek() else {
+            return Err(self.error("unterminated quoted string"));
+        };
+        self.pos += 1;
+        let hex_digits = match c {
+            'x' => 2,
+            'u' => 4,
+            'U' => 8,
+            _ => {
+                return Ok(match c {
+                    '0' => '\0',
+                    'a' => '\u{7}',
+                    'b' => '\u{8}',
+                    't' | '\t' => '\t',
+                    'n' => '\n',
+                    'v' => '\u{b}',
+                    'f' => '\u{c}',
+                    'r' => '\r',
+                    'e' => '\u{1b}',
+                    ' ' => ' ',
+                    '"' => '"',
+                    '/' => '/',
+                    '\\' => '\\',
+                    'N' => '\u{85}',
+                    '_' => '\u{a0}',
+                    'L' => '\u{2028}',
+                    'P' => '\u{2029}',
+                    _ => return Err(self.error(&format!("unknown escape '\\{}'", c))),
+                })
+            }
+        };
+        let digits: String = self
+            .chars
+            .get(self.pos..self.pos + hex_digits)
+            .unwrap_or(&[])
+            .iter()
+            .collect();
+        self.pos += hex_digits;
+        u32::from_str_radix(&digits, 16)
+            .ok()
+            .filter(|_| digits.len() == hex_digits)
+            .and_then(char::from_u32)
+            .ok_or_else(|| self.error(&format!("invalid escape '\\{}{}'", c, digits)))
+    }
+
+    /// Reads a flow sequence such as `[a, "b", 3]`.
+    fn parse_flow_sequence(&mut self) -> Result<Value, String> {
+        self.pos += 1;
+        let mut items = Vec::new();
+        loop {
+            self.skip_spaces();
+            match self.peek() {
+                Some(']') => {
+                    self.pos += 1;
+                    return Ok(Value::Array(items));
+                }
+                None => {
+                    return Err(
+                        self.error("unterminated '[' (flow collections must fit on one line)")
+                    )
+                }
+                _ => {}
+            }
+            items.push(self.parse_value(true)?);
+            self.skip_spaces();
+            match self.peek() {
+                Some(',') => self.pos += 1,
+                Some(']') => {}
+                _ => return Err(self.error("expected ',' or ']'")),
+            }
+        }
+    }
+
+    /// Reads a flow mapping such as `{a: 1, b: two}`.
+    fn parse_flow_mapping(&mut self) -> Result<Value, String> {
+        self.pos += 1;
+        let mut map = Map::new();
+        loop {
+            self.skip_spaces();
+            match self.peek() {
+                Some('}') => {
+                    self.pos += 1;
+                    return Ok(Value::Object(map));
+                }
+                None => {
+                    return Err(
+                        self.error("unterminated '{' (flow collections must fit on one line)")
+                    )
+                }
+                _ => {}
+            }
+            let key = match self.parse_value(true)? {
+                Value::String(s) => s,
+                Value::Null => String::new(),
+                other => other.to_string(),
+            };
+            self.skip_spaces();
+            if self.peek() != Some(':') {
+                return Err(self.error("expected ':' after a key"));
+            }
+            self.pos += 1;
+            let value = match self.peek() {
+                Some(',' | '}') => Value::Null,
+                _ => self.parse_value(true)?,
+            };
+            if map.insert(key.clone(), value).is_some() {
+                return Err(self.error(&format!("duplicate key '{}'", key)));
+            }
+            self.skip_spaces();
+            match self.peek() {
+                Some(',') => self.pos += 1,
+                Some('}') => {}
+                _ => return Err(self.error("expected ',' or '}'")),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn reads_comments_flow_collections_and_block_scalars() {
+        let text = "\
+# A deck written by hand
+version: 2 # the current version
+exercises:
+  - type: Recall
+    data:
+      - question: 'What''s an ego?' # quoted
+        answers: [egoist, \"ego, ist\", {a: 1}]
+        explanation: >
+          Folded lines
+          join with spaces.
+
+          A blank line keeps a break.
+        mnemonic: |-
+          Literal lines
+            keep their indentation
+        tags: []
+";
+        assert_eq!(
+            from_str(text).unwrap(),
+            json!({
+                "version": 2,
+                "exercises": [{
+                    "type": "Recall",
+                    "data": [{
+                        "question": "What's an ego?",
+                        "answers": ["egoist", "ego, ist", { "a": 1 }],
+                        "explanation": "Folded lines join with spaces.\nA blank line keeps a break.\n",
+                        "mnemonic": "Literal lines\n  keep their indentation",
+                        "tags": [],
+                    }],
+                }],
+            })
+        );
+    }
+
+    #[test]
+    fn rejects_anchors_aliases_and_tags() {
+        for text in [
+            "base: &base 1\nother: *base\n",
+            "value: !!str 1\n",
+            "- &item a\n",
+        ] {
+            let error = from_str(text).unwrap_err();
+            assert!(error.contains("not supported"), "{:?}: {}", text, error);
+        }
+    }
+}